@@ -5,10 +5,10 @@ use kernel::{
     cortex_m::{
         arch::CortexM, cortex_m_rt::FCPU, interrupts, irqn::SysIrqn, scb::SCB, systick::SysTick,
     },
-    kernel::kernel::Kernel,
+    kernel::{kernel::Kernel, stack::Stack},
     serial::{SerialConfig, SerialTrait},
     serial_utils::Hex,
-    soc::mps2_an38x::{UartDevice, UART0},
+    soc::{UartDevice, UART0},
     stdio,
 };
 use kernel::{print, println};
@@ -18,6 +18,16 @@ pub const KOBJS: usize = 32;
 
 pub const USER_THREAD_SIZE: usize = 16384;
 
+/// User thread stack, aligned on its size so that the MPU covers it exactly.
+#[repr(C, align(16384))]
+pub struct UserStack(pub Stack<USER_THREAD_SIZE>);
+
+impl UserStack {
+    pub const fn uninit() -> Self {
+        UserStack(Stack::uninit())
+    }
+}
+
 #[no_mangle]
 pub extern "C" fn _start() {
     // Initialize uart
@@ -31,6 +41,10 @@ pub extern "C" fn _start() {
     // Set UART0 as main uart
     stdio::set_uart(uart);
 
+    // Print through the debugger console instead (e.g. CI runs under QEMU)
+    #[cfg(feature = "semihosting")]
+    stdio::set_semihosting();
+
     // Show startup state    let cpuid = SCB::new().get_cpuid();
     let mut scb = SCB::instance();
     println!("CPUID base: {}", Hex::U32(scb.get_cpuid()));
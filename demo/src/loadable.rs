@@ -1,27 +1,34 @@
 use kernel::{
     self,
-    kernel::{kernel::Kernel, CpuVariant},
+    kernel::{elf_loader::loader::ElfImage, kernel::Kernel, CpuVariant},
     println, user_print,
 };
 
 use core::ffi::c_void;
 
-use crate::entry::USER_THREAD_SIZE;
-use kernel::kernel::{stack::Stack, thread::Thread, timeout::Timeout, userspace};
+use crate::entry::UserStack;
+use kernel::kernel::{thread::Thread, userspace};
 
 pub fn init_misc<'a, CPU: CpuVariant>() -> Thread<'a, CPU> {
     #[link_section = ".noinit"]
-    static mut THREAD_STACK_LOADALE: Stack<USER_THREAD_SIZE> = Stack::uninit();
-    let stack = unsafe { THREAD_STACK_LOADALE.get_info() };
+    static mut THREAD_STACK_LOADALE: UserStack = UserStack::uninit();
+    let stack = unsafe { THREAD_STACK_LOADALE.0.get_info() };
     let thread = Thread::init(&stack, mytask_misc, 0xaaaa0000 as *mut c_void, 0);
+    thread.set_name("misc");
 
     thread
 }
 
 extern "C" fn mytask_misc(_arg: *mut c_void) -> ! {
+    const PERIOD_MS: u64 = 1000;
+
+    // Absolute deadlines, so that the period does not drift
+    let mut next = userspace::k_uptime_ms();
+
     loop {
         user_print!(".");
-        userspace::k_sleep(Timeout::from_seconds(1));
+        next += PERIOD_MS;
+        userspace::k_sleep_until_ms(next);
     }
 }
 
@@ -42,5 +49,14 @@ pub fn init<'a, CPU: CpuVariant, const K: usize, const F: u32>(
         Err(e) => println!("Error loading elf: {:?}", e),
     }
 
+    // Can be loaded again from the shell (`load hello`)
+    let image = ElfImage {
+        name: "hello",
+        bytes: elf_bytes,
+    };
+    if let Err(e) = ker.register_elf_image(image) {
+        println!("Error registering elf image: {:?}", e);
+    }
+
     thread
 }
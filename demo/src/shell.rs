@@ -1,48 +1,519 @@
+//! Interactive shell over the stdio UART.
+//!
+//! Lines are edited with echo, backspace and a history browsed with the up and
+//! down arrows, then split on whitespace and dispatched to the command with the
+//! matching name in `COMMANDS`.
+
 use core::ffi::c_void;
 
-use crate::entry::USER_THREAD_SIZE;
+use crate::entry::{UserStack, USER_THREAD_SIZE};
 use kernel::{
-    kernel::{stack::Stack, thread::Thread, timeout::Timeout, userspace, CpuVariant},
-    println,
-    serial_utils::Hex,
-    user_println,
+    kernel::{
+        syscalls::SyncPrimitiveType,
+        sysinfo::{
+            KobjInfo, MemoryInfo, ThreadInfo, ThreadInfoState, VersionInfo,
+            FEATURE_DEADLOCK_DETECTION, FEATURE_DEBUG, FEATURE_LOADABLE_PIE, FEATURE_MPU,
+            FEATURE_STATS, FEATURE_TRACE, FEATURE_WORKQUEUE,
+        },
+        thread::Thread,
+        timeout::Timeout,
+        userspace, CpuVariant,
+    },
+    user_print, user_println,
 };
 
+const PROMPT: &str = "> ";
+const LINE_LEN: usize = 64;
+const HISTORY_LEN: usize = 8;
+const MAX_ARGS: usize = 8;
+
+const MAX_THREADS: usize = 16;
+const MAX_KOBJS: usize = 32;
+
+const EINVAL: i32 = -22;
+
 pub fn init_shell_thread<'a, CPU: CpuVariant>() -> Thread<'a, CPU> {
     #[link_section = ".noinit"]
-    static mut THREAD_STACK_SHELL: Stack<USER_THREAD_SIZE> = Stack::uninit();
-    let stack = unsafe { THREAD_STACK_SHELL.get_info() };
+    static mut THREAD_STACK_SHELL: UserStack = UserStack::uninit();
+    let stack = unsafe { THREAD_STACK_SHELL.0.get_info() };
     let thread = Thread::init(&stack, mytask_shell, 0xaaaa0000 as *mut c_void, 0);
+    thread.set_name("shell");
+    // Runs the system commands (load, kill, reboot)
+    thread.set_trusted();
 
     thread
 }
 
 extern "C" fn mytask_shell(_arg: *mut c_void) -> ! {
+    let mut shell = Shell::new();
+
+    user_println!("shell ready, type 'help' for the list of commands");
+    user_print!("{}", PROMPT);
+
     loop {
-        if let Some(byte) = userspace::k_stdio_read1() {
-            println!("recv: {}", Hex::U8(byte));
+        match userspace::k_stdio_read1() {
+            Some(byte) => shell.feed(byte),
+            None => {
+                userspace::k_sleep(Timeout::from_ms(10));
+            }
+        }
+    }
+}
 
-            let mut syscall_ret = 0;
+struct Command {
+    name: &'static str,
+    usage: &'static str,
+    help: &'static str,
+    /// Called with the arguments following the command name, returns 0 or
+    /// a negative error code.
+    handler: fn(&[&str]) -> i32,
+}
 
-            match byte {
-                b'y' => {
-                    println!("yield !");
-                    userspace::k_yield();
-                }
-                b's' => {
-                    println!("SVC sleep");
-                    syscall_ret = userspace::k_sleep(Timeout::from_ms(1000));
+static COMMANDS: &[Command] = &[
+    Command {
+        name: "help",
+        usage: "",
+        help: "list the commands",
+        handler: cmd_help,
+    },
+    Command {
+        name: "version",
+        usage: "",
+        help: "kernel version and features",
+        handler: cmd_version,
+    },
+    Command {
+        name: "ps",
+        usage: "",
+        help: "list the threads",
+        handler: cmd_ps,
+    },
+    Command {
+        name: "kobj",
+        usage: "",
+        help: "list the kernel objects",
+        handler: cmd_kobj,
+    },
+    Command {
+        name: "mem",
+        usage: "",
+        help: "kernel heap and shell stack usage",
+        handler: cmd_mem,
+    },
+    Command {
+        name: "uptime",
+        usage: "",
+        help: "time elapsed since boot",
+        handler: cmd_uptime,
+    },
+    Command {
+        name: "load",
+        usage: "<image>",
+        help: "load a registered ELF image and start it",
+        handler: cmd_load,
+    },
+    Command {
+        name: "kill",
+        usage: "<tid>",
+        help: "stop a thread",
+        handler: cmd_kill,
+    },
+    Command {
+        name: "trace",
+        usage: "",
+        help: "dump the kernel trace buffer (binary)",
+        handler: cmd_trace,
+    },
+    Command {
+        name: "reboot",
+        usage: "",
+        help: "reset the system",
+        handler: cmd_reboot,
+    },
+];
+
+fn cmd_help(_args: &[&str]) -> i32 {
+    for command in COMMANDS {
+        user_println!(
+            "  {:<8} {:<8} {}",
+            command.name,
+            command.usage,
+            command.help
+        );
+    }
+    0
+}
+
+fn cmd_version(_args: &[&str]) -> i32 {
+    let mut info = VersionInfo::default();
+    let ret = userspace::k_sysinfo_version(&mut info);
+    if ret < 0 {
+        return ret;
+    }
+
+    user_print!("kernel {}, features:", info.version());
+    for (feature, name) in [
+        (FEATURE_WORKQUEUE, "workqueue"),
+        (FEATURE_DEADLOCK_DETECTION, "deadlock-detection"),
+        (FEATURE_MPU, "mpu"),
+        (FEATURE_STATS, "stats"),
+        (FEATURE_DEBUG, "debug"),
+        (FEATURE_LOADABLE_PIE, "loadable-pie"),
+        (FEATURE_TRACE, "trace"),
+    ] {
+        if info.features & feature != 0 {
+            user_print!(" {}", name);
+        }
+    }
+    user_println!();
+    0
+}
+
+fn cmd_ps(_args: &[&str]) -> i32 {
+    let mut threads = [ThreadInfo::default(); MAX_THREADS];
+    let count = userspace::k_sysinfo_threads(&mut threads);
+    if count < 0 {
+        return count;
+    }
+
+    user_println!(
+        "{:>4} {:<16} {:<8} {:>5} {:>5} {:>10} {:>12}",
+        "tid",
+        "name",
+        "state",
+        "prio",
+        "kobj",
+        "timeout",
+        "stack"
+    );
+    for thread in threads.iter().take(count as usize) {
+        let state = match thread.state {
+            ThreadInfoState::Stopped => "stopped",
+            ThreadInfoState::Ready => "ready",
+            ThreadInfoState::Pending => "pending",
+        };
+        user_print!(
+            "{:>4} {:<16} {:<8} {:>5} {:>5} ",
+            thread.id,
+            thread.name(),
+            state,
+            thread.priority,
+            thread.pending_kobj
+        );
+        if thread.timeout == u64::MAX {
+            user_print!("{:>10} ", "-");
+        } else {
+            user_print!("{:>10} ", thread.timeout);
+        }
+        user_println!("{:>5}/{:<6}", thread.stack_used, thread.stack_size);
+    }
+    0
+}
+
+fn cmd_kobj(_args: &[&str]) -> i32 {
+    let mut kobjs = [KobjInfo {
+        kobj: 0,
+        kind: SyncPrimitiveType::Sync,
+        waiters: 0,
+        owner: 0,
+    }; MAX_KOBJS];
+    let count = userspace::k_sysinfo_kobjs(&mut kobjs);
+    if count < 0 {
+        return count;
+    }
+
+    user_println!(
+        "{:>4} {:<10} {:>7} {:>5}",
+        "kobj",
+        "kind",
+        "waiters",
+        "owner"
+    );
+    for kobj in kobjs.iter().take(count as usize) {
+        let kind = match kobj.kind {
+            SyncPrimitiveType::Sync => "sync",
+            SyncPrimitiveType::Signal => "signal",
+            SyncPrimitiveType::Semaphore => "semaphore",
+            SyncPrimitiveType::Mutex => "mutex",
+        };
+        user_print!("{:>4} {:<10} {:>7} ", kobj.kobj, kind, kobj.waiters);
+        if kobj.owner == 0 {
+            user_println!("{:>5}", "-");
+        } else {
+            user_println!("{:>5}", kobj.owner);
+        }
+    }
+    0
+}
+
+fn cmd_mem(_args: &[&str]) -> i32 {
+    let mut info = MemoryInfo::default();
+    let ret = userspace::k_sysinfo_memory(&mut info);
+    if ret < 0 {
+        return ret;
+    }
+
+    user_println!(
+        "heap: {} / {} bytes used",
+        info.heap_size - info.heap_free,
+        info.heap_size
+    );
+    user_println!(
+        "shell stack: {} / {} bytes used",
+        userspace::k_stack_usage(),
+        USER_THREAD_SIZE
+    );
+    0
+}
+
+fn cmd_uptime(_args: &[&str]) -> i32 {
+    let ms = userspace::k_uptime_ms();
+
+    user_println!(
+        "up {}.{:03} s ({} ticks)",
+        ms / 1000,
+        ms % 1000,
+        userspace::k_uptime_ticks()
+    );
+    0
+}
+
+fn cmd_load(args: &[&str]) -> i32 {
+    let [image] = args else {
+        return EINVAL;
+    };
+
+    let tid = userspace::k_load(image);
+    if tid >= 0 {
+        user_println!("{} started, tid {}", image, tid);
+    }
+    tid.min(0)
+}
+
+fn cmd_kill(args: &[&str]) -> i32 {
+    let [tid] = args else {
+        return EINVAL;
+    };
+
+    match tid.parse::<u32>() {
+        Ok(tid) => userspace::k_kill(tid),
+        Err(_) => EINVAL,
+    }
+}
+
+fn cmd_trace(_args: &[&str]) -> i32 {
+    userspace::k_trace_dump()
+}
+
+fn cmd_reboot(_args: &[&str]) -> i32 {
+    userspace::k_reboot()
+}
+
+/// Splits a line into words and runs the matching command.
+fn execute(line: &str) {
+    let mut words = line.split_whitespace();
+    let Some(name) = words.next() else {
+        return;
+    };
+
+    let mut args = [""; MAX_ARGS];
+    let mut argc = 0;
+    for word in words {
+        if argc == MAX_ARGS {
+            user_println!("too many arguments");
+            return;
+        }
+        args[argc] = word;
+        argc += 1;
+    }
+
+    match COMMANDS.iter().find(|command| command.name == name) {
+        Some(command) => {
+            let ret = (command.handler)(&args[..argc]);
+            if ret == EINVAL {
+                user_println!("usage: {} {}", command.name, command.usage);
+            } else if ret < 0 {
+                user_println!("error: {}", ret);
+            }
+        }
+        None => user_println!("unknown command: {}", name),
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Line {
+    buf: [u8; LINE_LEN],
+    len: usize,
+}
+
+impl Line {
+    const fn empty() -> Self {
+        Line {
+            buf: [0; LINE_LEN],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        // Only printable ASCII characters are inserted
+        core::str::from_utf8(&self.buf[..self.len]).unwrap_or("")
+    }
+
+    fn push(&mut self, byte: u8) -> bool {
+        if self.len < LINE_LEN {
+            self.buf[self.len] = byte;
+            self.len += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn pop(&mut self) -> bool {
+        if self.len > 0 {
+            self.len -= 1;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// Last entered lines, in a ring buffer.
+struct History {
+    lines: [Line; HISTORY_LEN],
+    /// Number of lines ever entered
+    count: usize,
+}
+
+impl History {
+    const fn new() -> Self {
+        History {
+            lines: [Line::empty(); HISTORY_LEN],
+            count: 0,
+        }
+    }
+
+    fn push(&mut self, line: &Line) {
+        if line.len == 0
+            || self
+                .get(1)
+                .is_some_and(|last| last.as_str() == line.as_str())
+        {
+            return;
+        }
+
+        self.lines[self.count % HISTORY_LEN] = *line;
+        self.count += 1;
+    }
+
+    /// Returns the n-th most recent line, starting from 1.
+    fn get(&self, n: usize) -> Option<&Line> {
+        if n == 0 || n > self.count.min(HISTORY_LEN) {
+            None
+        } else {
+            Some(&self.lines[(self.count - n) % HISTORY_LEN])
+        }
+    }
+}
+
+/// State of the escape sequence decoder (arrow keys send `ESC [ A` to `ESC [ D`).
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    None,
+    Esc,
+    Csi,
+}
+
+struct Shell {
+    line: Line,
+    history: History,
+    /// Position in the history while browsing it, 0 for the line being edited
+    browsing: usize,
+    escape: Escape,
+    /// Last byte was a carriage return, a following line feed is ignored
+    after_cr: bool,
+}
+
+impl Shell {
+    const fn new() -> Self {
+        Shell {
+            line: Line::empty(),
+            history: History::new(),
+            browsing: 0,
+            escape: Escape::None,
+            after_cr: false,
+        }
+    }
+
+    fn feed(&mut self, byte: u8) {
+        let after_cr = core::mem::replace(&mut self.after_cr, byte == b'\r');
+
+        match (self.escape, byte) {
+            (Escape::Esc, b'[') => self.escape = Escape::Csi,
+            (Escape::Csi, b'A') => {
+                self.escape = Escape::None;
+                self.browse(self.browsing + 1);
+            }
+            (Escape::Csi, b'B') => {
+                self.escape = Escape::None;
+                self.browse(self.browsing.saturating_sub(1));
+            }
+            (Escape::Esc | Escape::Csi, _) => self.escape = Escape::None,
+            (Escape::None, 0x1b) => self.escape = Escape::Esc,
+            (Escape::None, b'\n') if after_cr => {}
+            (Escape::None, b'\r' | b'\n') => self.enter(),
+            (Escape::None, 0x08 | 0x7f) => {
+                if self.line.pop() {
+                    user_print!("\x08 \x08");
                 }
-                b'w' => {
-                    println!("SVC print");
-                    user_println!("Hello using SVC !!");
+            }
+            // Ctrl-C
+            (Escape::None, 0x03) => {
+                user_println!("^C");
+                self.line = Line::empty();
+                self.browsing = 0;
+                user_print!("{}", PROMPT);
+            }
+            (Escape::None, 0x20..=0x7e) => {
+                if self.line.push(byte) {
+                    user_print!("{}", byte as char);
                 }
-                _ => {}
             }
-
-            println!("syscall_ret: {}", Hex::U32(syscall_ret as u32));
+            _ => {}
         }
+    }
+
+    fn enter(&mut self) {
+        user_println!();
 
-        userspace::k_sleep(Timeout::from_ms(100));
+        let line = self.line;
+        self.history.push(&line);
+        self.line = Line::empty();
+        self.browsing = 0;
+
+        execute(line.as_str());
+
+        user_print!("{}", PROMPT);
+    }
+
+    /// Replaces the edited line with the n-th most recent line of the history,
+    /// or clears it for 0.
+    fn browse(&mut self, n: usize) {
+        let line = match n {
+            0 => Line::empty(),
+            n => match self.history.get(n) {
+                Some(line) => *line,
+                None => return,
+            },
+        };
+
+        // Erase the line on the terminal and print the new one
+        for _ in 0..self.line.len {
+            user_print!("\x08 \x08");
+        }
+        self.line = line;
+        self.browsing = n;
+        user_print!("{}", self.line.as_str());
     }
 }
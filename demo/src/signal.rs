@@ -1,58 +1,84 @@
-use core::ffi::c_void;
+use core::{
+    ffi::c_void,
+    sync::atomic::{AtomicI32, Ordering},
+};
 
-use crate::entry::USER_THREAD_SIZE;
+use crate::entry::UserStack;
 use kernel::{
-    kernel::{stack::Stack, thread::Thread, timeout::Timeout, userspace, CpuVariant},
-    println,
+    kernel::{thread::Thread, timeout::Timeout, userspace, CpuVariant},
+    mem::region::{Access, MemoryRegion},
+    user_println,
+};
+
+// Memory shared between the signal threads, aligned so that it can be
+// covered by a single memory protection region
+#[repr(C, align(32))]
+struct Shared {
+    // Kernel object of the signal created by the producer
+    signal_kobj: AtomicI32,
+}
+
+static SHARED: Shared = Shared {
+    signal_kobj: AtomicI32::new(-1),
 };
 
 pub fn init_threads<'a, CPU: CpuVariant>() -> [Thread<'a, CPU>; 3] {
     // initialize task1
     #[link_section = ".noinit"]
-    static mut THREAD_STACK1: Stack<USER_THREAD_SIZE> = Stack::uninit();
-    let stack1 = unsafe { THREAD_STACK1.get_info() };
+    static mut THREAD_STACK1: UserStack = UserStack::uninit();
+    let stack1 = unsafe { THREAD_STACK1.0.get_info() };
     let task1 = Thread::init(&stack1, signal_consumer, 0xaaaa0000 as *mut c_void, 0);
+    task1.set_name("consumer1");
 
     // initialize task2
     #[link_section = ".noinit"]
-    static mut THREAD_STACK2: Stack<USER_THREAD_SIZE> = Stack::uninit();
-    let stack2 = unsafe { THREAD_STACK2.get_info() };
+    static mut THREAD_STACK2: UserStack = UserStack::uninit();
+    let stack2 = unsafe { THREAD_STACK2.0.get_info() };
     let task2 = Thread::init(&stack2, signal_consumer, 0xbbbb0000 as *mut c_void, 0);
+    task2.set_name("consumer2");
 
     // initialize task3
     #[link_section = ".noinit"]
-    static mut THREAD_STACK3: Stack<USER_THREAD_SIZE> = Stack::uninit();
-    let stack3 = unsafe { THREAD_STACK3.get_info() };
+    static mut THREAD_STACK3: UserStack = UserStack::uninit();
+    let stack3 = unsafe { THREAD_STACK3.0.get_info() };
     let task3 = Thread::init(&stack3, signal_producer, 0xcccc0000 as *mut c_void, 0);
+    task3.set_name("producer");
+
+    let tasks = [task1, task2, task3];
+    for task in tasks.iter() {
+        let _ = task.add_region(MemoryRegion::of(&SHARED, Access::ReadWrite));
+    }
 
-    [task1, task2, task3]
+    tasks
 }
 
 extern "C" fn signal_consumer(_arg: *mut c_void) -> ! {
     userspace::k_sleep(Timeout::from_ms(1000));
     loop {
-        let signal_val = userspace::k_signal_poll(0, Timeout::from_ms(3000));
-        println!("consumer: poll signal = {}", signal_val);
+        let signal = SHARED.signal_kobj.load(Ordering::Relaxed);
+        let signal_val = userspace::k_signal_poll(signal, Timeout::from_ms(3000));
+        user_println!("consumer: poll signal = {}", signal_val);
 
         if signal_val >= 0 {
             break;
         }
     }
 
-    println!("consumer: done");
+    user_println!("consumer: done");
     userspace::k_stop();
 }
 
 extern "C" fn signal_producer(_arg: *mut c_void) -> ! {
     let signal = userspace::k_signal_create();
-    println!("producer: create signal = {}", signal);
+    user_println!("producer: create signal = {}", signal);
+    SHARED.signal_kobj.store(signal, Ordering::Relaxed);
 
     let signal_value = 12345;
 
     userspace::k_sleep(Timeout::from_ms(5000));
     let ret = userspace::k_signal(signal, signal_value);
-    println!("producer: signal = {}, ret = {}", signal, ret);
+    user_println!("producer: signal = {}, ret = {}", signal, ret);
 
-    println!("producer: done");
+    user_println!("producer: done");
     userspace::k_stop();
 }
@@ -1,7 +1,35 @@
-const CHIP_LINKER_SCRIPT: &str = "mps2_an38x.x";
+use std::{env, fs, path::PathBuf};
+
+/// Memory map of each SoC (feature), copied as `memory.x` in the output
+/// directory which is added to the linker search path: the applications link
+/// with `-Tmemory.x -Tkernel/common.x`.
+const CHIP_LINKER_SCRIPTS: [(&str, &str); 5] = [
+    ("mps2-an385", "mps2_an38x.x"),
+    ("mps2-an386", "mps2_an38x.x"),
+    ("mps2-an505", "mps2_an505.x"),
+    ("microbit", "nrf51.x"),
+    ("stm32f405", "stm32f405.x"),
+];
 const COMMON_LINKER_SCRIPT: &str = "common.x";
 
 fn main() {
-    println!("cargo:rerun-if-changed={}", CHIP_LINKER_SCRIPT);
+    for (_, script) in CHIP_LINKER_SCRIPTS {
+        println!("cargo:rerun-if-changed={}", script);
+    }
     println!("cargo:rerun-if-changed={}", COMMON_LINKER_SCRIPT);
+
+    let enabled = |feature: &str| {
+        let var = format!("CARGO_FEATURE_{}", feature.to_uppercase().replace('-', "_"));
+        env::var_os(var).is_some()
+    };
+
+    // No memory map for the hosted port or the host unit tests
+    if let Some((_, script)) = CHIP_LINKER_SCRIPTS
+        .iter()
+        .find(|(feature, _)| enabled(feature))
+    {
+        let out = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+        fs::copy(script, out.join("memory.x")).unwrap();
+        println!("cargo:rustc-link-search={}", out.display());
+    }
 }
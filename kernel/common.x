@@ -5,6 +5,10 @@ EXTERN(RESET_VECTOR);
 
 PROVIDE(_stack_top = ORIGIN(RAM) + LENGTH(RAM));
 
+/* Flash boundaries, user threads are allowed to execute and read it */
+PROVIDE(_flash_start = ORIGIN(FLASH));
+PROVIDE(_flash_end = ORIGIN(FLASH) + LENGTH(FLASH));
+
 SECTIONS
 {
   .isr_vector ORIGIN(FLASH) :
@@ -43,6 +47,15 @@ SECTIONS
     _edata = .;
   } > RAM AT > FLASH /* Reside in FLASH, loaded to RAM */
 
+  /* Not initialized by the reset handler (e.g. thread stacks), last section in
+   * RAM: the kernel stack grows down from the end of RAM to _enoinit */
+  .noinit (NOLOAD) :
+  {
+    *(.noinit .noinit.*);
+    . = ALIGN(8);
+    _enoinit = .;
+  } > RAM
+
   /DISCARD/ :
   {
     /* We don't do stack unwinding, so discard the exception handling sections */
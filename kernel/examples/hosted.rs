@@ -0,0 +1,86 @@
+//! Runs the kernel as a Linux process: a busy worker never calls the kernel, a
+//! higher priority monitor preempts it every second to print its progress,
+//! then exits.
+//!
+//! make run-hosted
+
+use core::{
+    ffi::c_void,
+    ptr::addr_of_mut,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use kernel::{
+    hosted::{self, arch::Hosted, systick::SysTick, terminal::Terminal},
+    kernel::{
+        kernel::Kernel, stack::Stack, thread::Thread, timeout::Timeout, userspace, CpuVariant,
+    },
+    serial::{SerialConfig, SerialTrait},
+    stdio, user_println,
+};
+
+const FREQ_SYS_TICK: u32 = 100; // Hz
+const KOBJS: usize = 8;
+
+const THREAD_STACK_SIZE: usize = hosted::MIN_STACK_SIZE;
+
+/// Number of monitor periods before exiting
+const MONITOR_PERIODS: u32 = 5;
+
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+static mut WORKER_STACK: Stack<THREAD_STACK_SIZE> = Stack::uninit();
+static mut MONITOR_STACK: Stack<THREAD_STACK_SIZE> = Stack::uninit();
+
+extern "C" fn worker(_arg: *mut c_void) -> ! {
+    // Busy, only preempted by the tick
+    loop {
+        COUNTER.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+extern "C" fn monitor(_arg: *mut c_void) -> ! {
+    for _ in 0..MONITOR_PERIODS {
+        userspace::k_sleep(Timeout::from_ms(1000));
+        user_println!(
+            "monitor: uptime = {} ms, counter = {}",
+            userspace::k_uptime_ms(),
+            COUNTER.load(Ordering::Relaxed)
+        );
+    }
+
+    let err = userspace::k_exit(0);
+    panic!("exit failed: {}", err)
+}
+
+fn main() {
+    hosted::check_address_space();
+
+    let terminal = Terminal;
+    terminal.init(&SerialConfig::default());
+    stdio::set_terminal(terminal);
+
+    let systick =
+        SysTick::<FREQ_SYS_TICK>::configure_period::<{ <Hosted as CpuVariant>::FCPU }>(true);
+    let mut kernel = Kernel::<Hosted, KOBJS, FREQ_SYS_TICK>::init(systick);
+
+    let (worker_stack, monitor_stack) = unsafe {
+        (
+            (*addr_of_mut!(WORKER_STACK)).get_info(),
+            (*addr_of_mut!(MONITOR_STACK)).get_info(),
+        )
+    };
+    let worker = Thread::init(&worker_stack, worker, core::ptr::null_mut(), 1);
+    worker.set_name("worker");
+    let monitor = Thread::init(&monitor_stack, monitor, core::ptr::null_mut(), 0);
+    monitor.set_name("monitor");
+    // Exits at the end of the example
+    monitor.set_trusted();
+
+    kernel.register_thread(&worker);
+    kernel.register_thread(&monitor);
+
+    loop {
+        kernel.kernel_loop();
+    }
+}
@@ -0,0 +1,98 @@
+//! Runs the kernel on the BBC micro:bit (nRF51822, Cortex-M0) in QEMU: a busy
+//! worker never calls the kernel, a higher priority monitor preempts it every
+//! second to print its progress, then exits QEMU (semihosting).
+//!
+//! make run-microbit
+
+#![no_std]
+#![no_main]
+
+use core::{
+    ffi::c_void,
+    fmt::Write,
+    ptr::addr_of_mut,
+    sync::atomic::{AtomicU32, Ordering},
+};
+
+use kernel::{
+    cortex_m::{
+        armv6m::CortexM0, cortex_m_rt::FCPU, irqn::SysIrqn, nvic::NVIC, scb::SCB, systick::SysTick,
+    },
+    kernel::{kernel::Kernel, stack::Stack, thread::Thread, timeout::Timeout, userspace},
+    serial::{SerialConfig, SerialTrait},
+    soc::nrf51::{UartDevice, UART0},
+    stdio, user_println,
+};
+
+const FREQ_SYS_TICK: u32 = 100; // Hz
+const KOBJS: usize = 8;
+
+// The nRF51822 only has 16 KiB of RAM
+const THREAD_STACK_SIZE: usize = 1024;
+
+/// Number of monitor periods before exiting
+const MONITOR_PERIODS: u32 = 5;
+
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+static mut WORKER_STACK: Stack<THREAD_STACK_SIZE> = Stack::uninit();
+static mut MONITOR_STACK: Stack<THREAD_STACK_SIZE> = Stack::uninit();
+
+extern "C" fn worker(_arg: *mut c_void) -> ! {
+    // Busy, only preempted by the tick. ARMv6-M has no atomic read-modify-write
+    // instructions, this thread is the only writer.
+    loop {
+        COUNTER.store(COUNTER.load(Ordering::Relaxed) + 1, Ordering::Relaxed);
+    }
+}
+
+extern "C" fn monitor(_arg: *mut c_void) -> ! {
+    for _ in 0..MONITOR_PERIODS {
+        userspace::k_sleep(Timeout::from_ms(1000));
+        user_println!(
+            "monitor: uptime = {} ms, counter = {}",
+            userspace::k_uptime_ms(),
+            COUNTER.load(Ordering::Relaxed)
+        );
+    }
+
+    let err = userspace::k_exit(0);
+    panic!("exit failed: {}", err)
+}
+
+#[no_mangle]
+pub extern "C" fn _start() {
+    let mut uart = UartDevice::<FCPU>::new(UART0);
+    uart.init(&SerialConfig::default());
+    let _ = uart.write_str("kernel starting on the micro:bit\n");
+
+    stdio::set_uart(uart);
+
+    let mut scb = SCB::instance();
+    scb.set_priority(SysIrqn::PENDSV, NVIC::PRIO_LOWEST);
+    scb.set_priority(SysIrqn::SVCALL, NVIC::PRIO_LOWEST);
+    scb.set_priority(SysIrqn::SYSTICK, 0);
+
+    let systick = SysTick::<FREQ_SYS_TICK>::configure_period::<FCPU>(true);
+    let mut kernel = Kernel::<CortexM0, KOBJS, FREQ_SYS_TICK>::init(systick);
+
+    let (worker_stack, monitor_stack) = unsafe {
+        (
+            (*addr_of_mut!(WORKER_STACK)).get_info(),
+            (*addr_of_mut!(MONITOR_STACK)).get_info(),
+        )
+    };
+    let worker = Thread::init(&worker_stack, worker, core::ptr::null_mut(), 1);
+    worker.set_name("worker");
+    let monitor = Thread::init(&monitor_stack, monitor, core::ptr::null_mut(), 0);
+    monitor.set_name("monitor");
+    // Exits at the end of the example
+    monitor.set_trusted();
+
+    kernel.register_thread(&worker);
+    kernel.register_thread(&monitor);
+
+    loop {
+        kernel.kernel_loop();
+    }
+}
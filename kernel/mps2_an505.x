@@ -0,0 +1,7 @@
+/* Memory map for MPS2 AN505 (secure aliases) */
+
+MEMORY
+{
+  FLASH (rx)            : ORIGIN = 0x10000000, LENGTH = 0x00400000    /* 4MB  ZBTSRAM1 */
+  RAM (rwx)             : ORIGIN = 0x38000000, LENGTH = 0x00400000    /* 4MB  ZBTSRAM2 & ZBTSRAM3 */
+}
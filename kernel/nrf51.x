@@ -0,0 +1,7 @@
+/* Memory map for the nRF51822 (BBC micro:bit) */
+
+MEMORY
+{
+  FLASH (rx)            : ORIGIN = 0x00000000, LENGTH = 0x00040000    /* 256KB */
+  RAM (rwx)             : ORIGIN = 0x20000000, LENGTH = 0x00004000    /* 16KB */
+}
@@ -1,11 +1,57 @@
-use core::arch::{asm, global_asm};
-
-use crate::kernel::{
-    elf_loader::{Lex, PICRegImpl},
-    CpuVariant, ExceptionStackFrame, ThreadEntry,
-};
+#[cfg(target_arch = "arm")]
+use core::arch::asm;
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+use core::{arch::global_asm, mem::offset_of};
+
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+use super::cortex_m_rt::FCPU;
+#[cfg(target_arch = "arm")]
+use crate::kernel::elf_loader::{Lex, PICRegImpl};
+#[cfg(all(target_arch = "arm", feature = "cm33"))]
+use crate::kernel::stack::StackInfo;
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+use crate::kernel::CpuVariant;
+use crate::kernel::{syscalls::SVCCallParams, ExceptionStackFrame, ThreadEntry};
+
+/// EXC_RETURN of a thread which has not used the FPU yet: return to thread
+/// mode, on the process stack, with a basic frame
+pub const EXC_RETURN_THREAD_PSP: u32 = 0xFFFF_FFFD;
+
+/// Bit of EXC_RETURN cleared when the exception frame is extended with the FP
+/// caller saved registers
+pub const EXC_RETURN_BASIC_FRAME: u32 = 1 << 4;
+
+/// Offset of the stack limit in the callee context, for the assembly
+#[cfg(all(target_arch = "arm", feature = "cm33"))]
+const PSPLIM_OFFSET: usize = offset_of!(__callee_context, psplim);
+#[cfg(all(target_arch = "arm", not(feature = "cm0"), not(feature = "cm33")))]
+const PSPLIM_OFFSET: usize = 0;
+
+/// Offset of the FP registers in the callee context, for the assembly
+#[cfg(all(target_arch = "arm", not(feature = "cm0"), feature = "fpu"))]
+const S16_S31_OFFSET: usize = offset_of!(__callee_context, s16_s31);
+#[cfg(all(target_arch = "arm", not(feature = "cm0"), not(feature = "fpu")))]
+const S16_S31_OFFSET: usize = 0;
+
+/// EXC_RETURN of the kernel, saved by PendSV when it switches to a thread: the
+/// frame on the main stack is extended if the kernel used the FPU.
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+#[used]
+#[no_mangle]
+static mut Z_KERNEL_EXC_RETURN: u32 = 0xFFFF_FFF9;
+
+/// Values of s0-s31 when a thread uses the FPU for the first time, loaded by
+/// PendSV so that the thread does not read the registers of the kernel or of
+/// the previous thread.
+#[cfg(all(target_arch = "arm", not(feature = "cm0"), feature = "fpu"))]
+#[used]
+#[no_mangle]
+static Z_FP_ZEROES: [u32; 32] = [0; 32];
 
 // Stack frame produced by an exception
+//
+// If the thread used the FPU, the frame is extended with s0-s15, FPSCR and a
+// reserved word after these registers.
 #[repr(C)]
 #[allow(non_camel_case_types)]
 pub struct __exception_sf {
@@ -43,6 +89,34 @@ impl ExceptionStackFrame for __exception_sf {
             (*sf).xpsr = XPSR;
         };
     }
+
+    unsafe fn syscall_params(stack_ptr: *const u32) -> SVCCallParams {
+        // At this point, the process exception frame looks like this
+        // sp + 00: r0 (syscall arg 0)
+        // sp + 04: r1 (syscall arg 1)
+        // sp + 08: r2 (syscall arg 2)
+        // sp + 0C: r3 (syscall arg 3)
+        // sp + 10: r12
+        // sp + 14: lr
+        // sp + 18: return address (instruction following the svc)
+        // sp + 1C: xPSR
+        let sf = &*(stack_ptr as *const Self);
+
+        // Read syscall main id from yielded PC
+        // "svc 0xbb" is encoded as the following 16bits instruction: 0xdfbb
+        let pc_svc = sf.pc as *const u16;
+        // Subtract 1 because return address (RA) includes the "thumb" flag that
+        // needs to be removed to get the actual instruction
+        let svc_instruction = core::ptr::read(pc_svc.sub(1));
+
+        SVCCallParams {
+            r0: sf.r0,
+            r1: sf.r1,
+            r2: sf.r2,
+            r3: sf.r3,
+            syscall_id: (svc_instruction & 0xFF) as u8,
+        }
+    }
 }
 
 // Representation of the callee saved context in stack
@@ -61,6 +135,16 @@ pub struct __callee_context {
     pub v7: u32, // r10
     pub v8: u32, // r11
     pub ip: u32, // r12
+    /// EXC_RETURN of the thread, its exception frame is extended if bit 4 is
+    /// clear
+    pub exc_return: u32,
+    /// FP callee saved registers (s16-s31), only saved if the thread used the FPU
+    #[cfg(feature = "fpu")]
+    pub s16_s31: [u32; 16],
+    /// Process stack limit of the thread (PSPLIM), a UsageFault is raised if
+    /// its stack pointer goes below it
+    #[cfg(feature = "cm33")]
+    pub psplim: u32,
 }
 
 impl __callee_context {
@@ -75,6 +159,11 @@ impl __callee_context {
             v7: 0,
             v8: 0,
             ip: 0,
+            exc_return: EXC_RETURN_THREAD_PSP,
+            #[cfg(feature = "fpu")]
+            s16_s31: [0; 16],
+            #[cfg(feature = "cm33")]
+            psplim: 0,
         }
     }
 }
@@ -85,10 +174,16 @@ impl Default for __callee_context {
     }
 }
 
+/// ARMv7-M (Cortex-M3/M4) and ARMv8-M Mainline (Cortex-M33), see
+/// `armv6m::CortexM0` for the Cortex-M0.
+///
+/// On the Cortex-M33, the process stack limit (PSPLIM) is switched with the
+/// thread, a stack overflow raises a UsageFault (STKOF) which kills it.
 pub struct CortexM;
 
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
 impl CpuVariant for CortexM {
-    const FCPU: u32 = 25_000_000;
+    const FCPU: u32 = FCPU;
 
     // Add types for interrupts handlers like sytick, pendsv, svc
 
@@ -106,6 +201,12 @@ impl CpuVariant for CortexM {
     #[cfg(feature = "loadable-elf-reg-r10")]
     type PICRegImpl = R10;
 
+    #[cfg(feature = "cm33")]
+    fn init_context(context: &mut Self::CalleeContext, stack: &StackInfo) {
+        // Above the canary at the bottom of the stack, 8 bytes aligned
+        context.psplim = stack.stack_start() as u32 + 8;
+    }
+
     #[export_name = "switch_to_user"]
     unsafe fn switch_to_user(
         mut stack_ptr: *mut u32,
@@ -113,10 +214,18 @@ impl CpuVariant for CortexM {
     ) -> *mut u32 {
         asm!(
             "
-            // 1. Save kernel call-saved registers on the stack
+            // 1. Save kernel call-saved registers on the stack, PendSV
+            // overwrites the FP ones with those of the thread
             push {{v1-v8, ip}}
+            .if {fpu}
+            vpush {{d8-d15}}
+            .endif
     
-            // 2. Set user stack pointer
+            // 2. Set user stack limit then stack pointer
+            .if {splim}
+            ldr r2, [r1, #{psplim}]
+            msr psplim, r2
+            .endif
             msr psp, r0
     
             // 3. Restore user process context
@@ -140,11 +249,19 @@ impl CpuVariant for CortexM {
             mrs r0, psp
     
             // 7. Pop kernel call-saved registers from the stack
+            .if {fpu}
+            vpop {{d8-d15}}
+            .endif
             pop {{v1-v8, ip}}
         
             ",
             inout("r0") stack_ptr,
             in("r1") process_regs,
+            out("r2") _,
+            out("r3") _,
+            fpu = const cfg!(feature = "fpu") as u32,
+            splim = const cfg!(feature = "cm33") as u32,
+            psplim = const PSPLIM_OFFSET,
         );
 
         stack_ptr
@@ -153,6 +270,7 @@ impl CpuVariant for CortexM {
 
 // 1. Calls to pendsv saves:
 //  r0-r3, r12, lr, return addr, xpsr
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
 global_asm!(
     "
     .section .text, \"ax\"
@@ -162,8 +280,10 @@ global_asm!(
 z_svc:
     // SVC manages syscall and switch to the kernel
 
-    // 1. Switch to priviledged mode
-    mov r0, #0
+    // 1. Switch to priviledged mode, the other CONTROL bits (FP state of
+    // ARMv8-M) are kept
+    mrs r0, CONTROL
+    bic r0, r0, #1
     msr CONTROL, r0
 
     // 2. sync barrier required after CONTROL, from armv7 manual:
@@ -177,42 +297,112 @@ z_svc:
     mov r1, #1
     str r1, [r0]
 
-    // 4. load EXC_RETURN value to return in supervisor stack
-    ldr lr, =0xFFFFFFF9
-
-    // 5. switch to kernel
-    bx lr
+    // 4. switch to kernel
+    b z_return_to_kernel
     "
 );
 
 // 1. Calls to pendsv saves:
 //  r0-r3, r12, lr, return addr, xpsr
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
 global_asm!(
     "
     .section .text, \"ax\"
     .global z_pendsv
+    .extern Z_KERNEL_EXC_RETURN
     .thumb_func
 z_pendsv:
     // PendSV manages final changes to switch to the user process
 
-    // 1. Switch to unpriviledged mode
-    mov r0, #1
+    // 1. Save the EXC_RETURN value of the kernel
+    ldr r0, =Z_KERNEL_EXC_RETURN
+    str lr, [r0]
+
+    // 2. Load the EXC_RETURN value of the thread from its context, which
+    // the kernel passed in r1 (stacked on the main stack)
+    mrs r0, MSP
+    ldr r1, [r0, #4]
+    ldr lr, [r1, #{exc_return}]
+
+    // 3. Restore the FP callee saved registers of the thread if it used
+    // the FPU (extended frame), its caller saved ones are restored from its
+    // exception frame. Otherwise clear all of them, its first floating point
+    // instruction then starts from zeroed registers (FPSCR is reset from
+    // FPDSCR by the hardware)
+    .if {fpu}
+    .fpu fpv4-sp-d16
+    tst lr, #{basic_frame}
+    bne 1f
+    add r1, r1, #{s16_s31}
+    vldmia r1, {{s16-s31}}
+    b 2f
+1:
+    ldr r1, =Z_FP_ZEROES
+    vldmia r1, {{s0-s31}}
+2:
+    .endif
+
+    // 4. Switch to unpriviledged mode
+    mrs r0, CONTROL
+    orr r0, r0, #1
     msr CONTROL, r0
 
-    // 2. sync barrier required after CONTROL, from armv7 manual:
+    // 5. sync barrier required after CONTROL, from armv7 manual:
     // 'Software must use an ISB barrier instruction to ensure 
     //  a write to the CONTROL register takes effect before the 
     //  next instruction is executed.'
     isb
 
-    // 3. load EXC_RETURN value to return in process stack
-    ldr lr, =0xFFFFFFFD
-
-    // 4. switch to user
+    // 6. switch to user
     bx lr
+    ",
+    exc_return = const offset_of!(__callee_context, exc_return),
+    s16_s31 = const S16_S31_OFFSET,
+    basic_frame = const EXC_RETURN_BASIC_FRAME,
+    fpu = const cfg!(feature = "fpu") as u32,
+);
+
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+global_asm!(
     "
+    .section .text, \"ax\"
+    .global z_return_to_kernel
+    .extern Z_KERNEL_EXC_RETURN
+    .thumb_func
+z_return_to_kernel:
+    // Common return path of the exceptions switching from a thread to the
+    // kernel, lr holds the EXC_RETURN value of the exception
+
+    // 1. Save the EXC_RETURN value of the thread in its context, which
+    // the kernel passed in r1 (stacked on the main stack)
+    mrs r0, MSP
+    ldr r1, [r0, #4]
+    str lr, [r1, #{exc_return}]
+
+    // 2. Save the FP callee saved registers of the thread if it used the
+    // FPU (extended frame), this floating point instruction also writes
+    // its FP caller saved registers in its exception frame (lazy stacking)
+    .if {fpu}
+    .fpu fpv4-sp-d16
+    tst lr, #{basic_frame}
+    bne 1f
+    add r1, r1, #{s16_s31}
+    vstmia r1, {{s16-s31}}
+1:
+    .endif
+
+    // 3. Return to the kernel with the EXC_RETURN value saved by PendSV
+    ldr r0, =Z_KERNEL_EXC_RETURN
+    ldr lr, [r0]
+    bx lr
+    ",
+    exc_return = const offset_of!(__callee_context, exc_return),
+    s16_s31 = const S16_S31_OFFSET,
+    basic_frame = const EXC_RETURN_BASIC_FRAME,
+    fpu = const cfg!(feature = "fpu") as u32,
 );
 
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
 global_asm!(
     "
     .section .text, \"ax\"
@@ -223,21 +413,128 @@ z_systick:
     // cannot be preempted This is a *natural* critical section with 
     // the maximum degree
 
-    // 1. Switch to priviledged mode
-    mov r0, #0
+    // 1. A tick taken by the kernel (main stack) returns to it, the
+    // kernel reads the elapsed ticks from the SysTick counter
+    tst lr, #4
+    it eq
+    bxeq lr
+
+    // 2. Switch to priviledged mode
+    mrs r0, CONTROL
+    bic r0, r0, #1
     msr CONTROL, r0
 
-    // 2. sync barrier required after CONTROL, from armv7 manual:
+    // 3. sync barrier required after CONTROL, from armv7 manual:
     // 'Software must use an ISB barrier instruction to ensure
     //  a write to the CONTROL register takes effect before the
     //  next instruction is executed.'
     isb
 
-    // 3. load EXC_RETURN value to return in supervisor stack
-    ldr lr, =0xFFFFFFF9
-
     // 4. switch to kernel
-    bx lr
+    b z_return_to_kernel
+    "
+);
+
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+global_asm!(
+    "
+    .section .text, \"ax\"
+    .global z_fault
+    .extern Z_FAULT_FLAG
+    .extern z_kernel_fault
+    .thumb_func
+z_fault:
+    // HardFault, MemManage, BusFault and UsageFault
+
+    // 1. Retrieve the fault exception number
+    mrs r1, IPSR
+
+    // 2. A fault in the kernel (main stack) cannot be recovered,
+    // report it and halt
+    tst lr, #4
+    bne 1f
+    mrs r0, MSP
+    b z_kernel_fault
+
+1:
+    // 3. Switch to priviledged mode
+    mrs r0, CONTROL
+    bic r0, r0, #1
+    msr CONTROL, r0
+
+    // 4. sync barrier required after CONTROL, from armv7 manual:
+    // 'Software must use an ISB barrier instruction to ensure
+    //  a write to the CONTROL register takes effect before the
+    //  next instruction is executed.'
+    isb
+
+    // 5. Write the exception number to Z_FAULT_FLAG variable
+    ldr r0, =Z_FAULT_FLAG
+    str r1, [r0]
+
+    // 6. switch to kernel, which kills the faulty thread
+    b z_return_to_kernel
+    "
+);
+
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+global_asm!(
+    "
+    .section .text, \"ax\"
+    .global z_irq
+    .extern Z_IRQ_PENDING
+    .extern Z_IRQ_FLAG
+    .thumb_func
+z_irq:
+    // Device interrupts, their handlers are called by the kernel loop
+
+    // 1. Retrieve the interrupt number (exception number - 16), its word
+    // and bit in the NVIC registers
+    mrs r0, IPSR
+    sub r0, r0, #16
+    lsr r1, r0, #5
+    and r2, r0, #31
+    mov r3, #1
+    lsl r3, r3, r2
+
+    // 2. Mask the interrupt (NVIC_ICER) until the kernel handled it
+    ldr r2, =0xE000E180
+    str r3, [r2, r1, lsl #2]
+
+    // 3. Record it as pending, with interrupts disabled as the kernel and
+    // interrupts of higher priority also update the bitmap
+    mrs r12, PRIMASK
+    cpsid i
+    ldr r2, =Z_IRQ_PENDING
+    ldr r0, [r2, r1, lsl #2]
+    orr r0, r0, r3
+    str r0, [r2, r1, lsl #2]
+    msr PRIMASK, r12
+
+    // 4. An interrupt taken by the kernel (main stack) returns to it, the
+    // kernel dispatches it on its next return from a thread
+    tst lr, #4
+    it eq
+    bxeq lr
+
+    // 5. Write the exception number to Z_IRQ_FLAG variable
+    mrs r1, IPSR
+    ldr r0, =Z_IRQ_FLAG
+    str r1, [r0]
+
+    // 6. Switch to priviledged mode
+    mrs r0, CONTROL
+    bic r0, r0, #1
+    msr CONTROL, r0
+
+    // 7. sync barrier required after CONTROL, from armv7 manual:
+    // 'Software must use an ISB barrier instruction to ensure
+    //  a write to the CONTROL register takes effect before the
+    //  next instruction is executed.'
+    isb
+
+    // 8. switch to kernel
+    b z_return_to_kernel
     "
 );
 
@@ -245,16 +542,20 @@ z_systick:
 #[derive(Debug)]
 pub struct R9;
 
+#[cfg(target_arch = "arm")]
 impl PICRegImpl for R9 {
     unsafe fn invoke_loadable_entry(lex: &Lex) -> u32 {
         let r0: u32;
+        // High registers can only be clobbers of Thumb-1 (ARMv6-M) assembly
         asm!(
             "
+            mov r9, {got}
             blx {entry}
             ",
             entry = in(reg) lex.entry,
+            got = in(reg) lex.got_addr,
             inout("r0") lex.arg0 => r0,
-            in("r9") lex.got_addr,
+            out("r9") _,
         );
         r0
     }
@@ -264,16 +565,20 @@ impl PICRegImpl for R9 {
 #[derive(Debug)]
 pub struct R10;
 
+#[cfg(target_arch = "arm")]
 impl PICRegImpl for R10 {
     unsafe fn invoke_loadable_entry(lex: &Lex) -> u32 {
         let r0: u32;
+        // High registers can only be clobbers of Thumb-1 (ARMv6-M) assembly
         asm!(
             "
+            mov r10, {got}
             blx {entry}
             ",
             entry = in(reg) lex.entry,
+            got = in(reg) lex.got_addr,
             inout("r0") lex.arg0 => r0,
-            in("r10") lex.got_addr,
+            out("r10") _,
         );
         r0
     }
@@ -0,0 +1,280 @@
+//! ARMv6-M (Cortex-M0) port.
+//!
+//! The ARMv6-M architecture only has a subset of the Thumb-2 instructions:
+//! `ldm`/`stm`/`push`/`pop` only accept the low registers (r0-r7), immediates
+//! are limited to 8 bits, there is no `it` block and no `tst` with an
+//! immediate. The high registers r8-r11 are saved and restored through the low
+//! ones.
+//!
+//! The Cortex-M0 has no unprivileged mode (CONTROL.nPRIV is reserved), threads
+//! run privileged on the process stack. It has neither an FPU nor an MPU, and
+//! the only fault is the HardFault, without status registers.
+
+#[cfg(target_arch = "arm")]
+use core::arch::{asm, global_asm};
+
+#[cfg(target_arch = "arm")]
+use super::{
+    arch::{__callee_context, __exception_sf},
+    cortex_m_rt::FCPU,
+};
+#[cfg(target_arch = "arm")]
+use crate::kernel::CpuVariant;
+
+#[cfg(all(target_arch = "arm", feature = "loadable-elf-reg-r10"))]
+use super::arch::R10;
+#[cfg(all(target_arch = "arm", feature = "loadable-elf-reg-r9"))]
+use super::arch::R9;
+
+/// ARMv6-M (Cortex-M0), the exception frame and the callee context are the
+/// same as for the Cortex-M3/M4.
+pub struct CortexM0;
+
+#[cfg(target_arch = "arm")]
+impl CpuVariant for CortexM0 {
+    const FCPU: u32 = FCPU;
+
+    type CalleeContext = __callee_context;
+    type InitStackFrame = __exception_sf;
+
+    #[cfg(any(
+        not(any(feature = "loadable-elf-reg-r9", feature = "loadable-elf-reg-r10")),
+        all(feature = "loadable-elf-reg-r9", feature = "loadable-elf-reg-r10")
+    ))]
+    compile_error!("One and only one PIC register must be selected");
+
+    #[cfg(feature = "loadable-elf-reg-r9")]
+    type PICRegImpl = R9;
+    #[cfg(feature = "loadable-elf-reg-r10")]
+    type PICRegImpl = R10;
+
+    #[export_name = "switch_to_user"]
+    unsafe fn switch_to_user(
+        mut stack_ptr: *mut u32,
+        process_regs: *mut Self::CalleeContext,
+    ) -> *mut u32 {
+        // Interrupt Control and State Register and its PendSV set-pending bit
+        const ICSR: u32 = 0xE000_ED04;
+        const ICSR_PENDSVSET: u32 = 1 << 28;
+
+        asm!(
+            "
+            // 1. Save kernel call-saved registers on the stack, the high
+            // ones through the low ones
+            push {{r4-r7}}
+            mov r4, r8
+            mov r5, r9
+            mov r6, r10
+            mov r7, r11
+            push {{r4-r7}}
+
+            // 2. Set user stack pointer
+            msr psp, r0
+
+            // 3. Restore user process context (v1-v4 then v5-v8), r1 points
+            // to the context again afterwards
+            adds r1, #16
+            ldmia r1!, {{r4-r7}}
+            mov r8, r4
+            mov r9, r5
+            mov r10, r6
+            mov r11, r7
+            subs r1, #32
+            ldmia r1!, {{r4-r7}}
+            subs r1, #16
+
+            // 4. trigger a pendSV: set PENDSVSET bit in ICSR register
+            str r3, [r2]
+            isb
+
+            // =============================================================
+            // PendSV triggered; now we have returned from the exception
+            // after a PendSV called by the user process
+            // =============================================================
+
+            // 5. Save user process context
+            stmia r1!, {{r4-r7}}
+            mov r4, r8
+            mov r5, r9
+            mov r6, r10
+            mov r7, r11
+            stmia r1!, {{r4-r7}}
+
+            // 6. Save user process stack pointer back to r0
+            mrs r0, psp
+
+            // 7. Pop kernel call-saved registers from the stack
+            pop {{r4-r7}}
+            mov r8, r4
+            mov r9, r5
+            mov r10, r6
+            mov r11, r7
+            pop {{r4-r7}}
+            ",
+            inout("r0") stack_ptr,
+            inout("r1") process_regs => _,
+            in("r2") ICSR,
+            in("r3") ICSR_PENDSVSET,
+        );
+
+        stack_ptr
+    }
+}
+
+// The EXC_RETURN values are loaded from a literal pool (`.ltorg`) as the
+// immediates are limited to 8 bits, they return to the kernel (thread mode,
+// main stack) or to the thread (thread mode, process stack).
+#[cfg(target_arch = "arm")]
+global_asm!(
+    "
+    .section .text, \"ax\"
+    .global z_svc
+    .extern Z_SYSCALL_FLAG
+    .thumb_func
+z_svc:
+    // SVC manages syscall and switch to the kernel
+
+    // 1. Write 1 to Z_SYSCALL_FLAG variable
+    ldr r0, =Z_SYSCALL_FLAG
+    movs r1, #1
+    str r1, [r0]
+
+    // 2. switch to kernel
+    ldr r0, =0xFFFFFFF9
+    bx r0
+    .ltorg
+    "
+);
+
+#[cfg(target_arch = "arm")]
+global_asm!(
+    "
+    .section .text, \"ax\"
+    .global z_pendsv
+    .thumb_func
+z_pendsv:
+    // PendSV manages final changes to switch to the user process
+
+    // 1. switch to user
+    ldr r0, =0xFFFFFFFD
+    bx r0
+    .ltorg
+    "
+);
+
+#[cfg(target_arch = "arm")]
+global_asm!(
+    "
+    .section .text, \"ax\"
+    .global z_systick
+    .thumb_func
+z_systick:
+    // 1. A tick taken by the kernel (main stack) returns to it, the
+    // kernel reads the elapsed ticks from the SysTick counter
+    mov r0, lr
+    movs r1, #4
+    tst r0, r1
+    bne 1f
+    bx lr
+
+1:
+    // 2. switch to kernel
+    ldr r0, =0xFFFFFFF9
+    bx r0
+    .ltorg
+    "
+);
+
+#[cfg(target_arch = "arm")]
+global_asm!(
+    "
+    .section .text, \"ax\"
+    .global z_fault
+    .extern Z_FAULT_FLAG
+    .extern z_kernel_fault
+    .thumb_func
+z_fault:
+    // HardFault, the only fault of the ARMv6-M architecture
+
+    // 1. Retrieve the fault exception number
+    mrs r1, IPSR
+
+    // 2. A fault in the kernel (main stack) cannot be recovered,
+    // report it and halt (z_kernel_fault may be out of range of b)
+    mov r0, lr
+    movs r2, #4
+    tst r0, r2
+    bne 1f
+    mrs r0, MSP
+    ldr r2, =z_kernel_fault
+    bx r2
+
+1:
+    // 3. Write the exception number to Z_FAULT_FLAG variable
+    ldr r0, =Z_FAULT_FLAG
+    str r1, [r0]
+
+    // 4. switch to kernel, which kills the faulty thread
+    ldr r0, =0xFFFFFFF9
+    bx r0
+    .ltorg
+    "
+);
+
+#[cfg(target_arch = "arm")]
+global_asm!(
+    "
+    .section .text, \"ax\"
+    .global z_irq
+    .extern Z_IRQ_PENDING
+    .extern Z_IRQ_FLAG
+    .thumb_func
+z_irq:
+    // Device interrupts, their handlers are called by the kernel loop
+
+    // 1. Retrieve the interrupt number (exception number - 16), the offset
+    // of its word and its bit in the NVIC registers
+    mrs r0, IPSR
+    subs r0, #16
+    lsrs r1, r0, #5
+    lsls r1, r1, #2
+    movs r3, #31
+    ands r3, r0
+    movs r2, #1
+    lsls r2, r3
+
+    // 2. Mask the interrupt (NVIC_ICER) until the kernel handled it
+    ldr r3, =0xE000E180
+    str r2, [r3, r1]
+
+    // 3. Record it as pending, with interrupts disabled as the kernel and
+    // interrupts of higher priority also update the bitmap
+    mrs r0, PRIMASK
+    cpsid i
+    ldr r3, =Z_IRQ_PENDING
+    adds r3, r3, r1
+    ldr r1, [r3]
+    orrs r1, r2
+    str r1, [r3]
+    msr PRIMASK, r0
+
+    // 4. An interrupt taken by the kernel (main stack) returns to it, the
+    // kernel dispatches it on its next return from a thread
+    mov r0, lr
+    movs r1, #4
+    tst r0, r1
+    bne 1f
+    bx lr
+
+1:
+    // 5. Write the exception number to Z_IRQ_FLAG variable
+    mrs r1, IPSR
+    ldr r0, =Z_IRQ_FLAG
+    str r1, [r0]
+
+    // 6. switch to kernel
+    ldr r0, =0xFFFFFFF9
+    bx r0
+    .ltorg
+    "
+);
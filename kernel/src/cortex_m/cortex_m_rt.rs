@@ -1,13 +1,49 @@
-use core::{
-    intrinsics::{volatile_load, volatile_store},
-    ptr::{self, addr_of, addr_of_mut},
-};
+use core::intrinsics::{volatile_load, volatile_store};
+#[cfg(target_arch = "arm")]
+use core::ptr::{self, addr_of, addr_of_mut};
+
+#[cfg(target_arch = "arm")]
+use super::interrupts;
+#[cfg(target_arch = "arm")]
+use crate::{kernel::kernel::KernelState, soc::IRQ_COUNT, stdio};
+
+#[cfg(all(feature = "panic-reset", feature = "panic-exit"))]
+compile_error!("features `panic-reset` and `panic-exit` are mutually exclusive");
 
 // TODO move to mps2_an38x
+#[cfg(not(any(feature = "microbit", feature = "mps2-an505", feature = "stm32f405")))]
 pub const FCPU: u32 = 25_000_000;
-
+// PLL clocked from the HSI, see `soc::stm32f4::rcc`
+#[cfg(feature = "stm32f405")]
+pub const FCPU: u32 = 168_000_000;
+#[cfg(feature = "mps2-an505")]
+pub const FCPU: u32 = 20_000_000;
+// 16 MHz crystal of the nRF51822
+#[cfg(feature = "microbit")]
+pub const FCPU: u32 = 16_000_000;
+
+#[cfg(target_arch = "arm")]
 #[panic_handler]
-fn panic(_info: &core::panic::PanicInfo) -> ! {
+fn panic(info: &core::panic::PanicInfo) -> ! {
+    // Nothing else must run from now on
+    let _cs = interrupts::disable();
+
+    // Print even if the panic occurred before stdio initialization
+    stdio::write_args_fallback(format_args!("\n*** kernel {}\n", info));
+
+    let state = KernelState::snapshot();
+    stdio::write_args_fallback(format_args!(
+        "*** current thread: {:p}, ticks: {}\n",
+        state.current_thread, state.ticks
+    ));
+
+    #[cfg(feature = "panic-reset")]
+    super::scb::SCB::instance().system_reset();
+
+    #[cfg(feature = "panic-exit")]
+    super::semihosting::exit(false);
+
+    #[cfg(not(any(feature = "panic-reset", feature = "panic-exit")))]
     loop {}
 }
 
@@ -21,12 +57,8 @@ pub unsafe extern "C" fn _unimplemented() {
     unimplemented!()
 }
 
-#[no_mangle]
-pub unsafe extern "C" fn _fault_handler() {
-    loop {}
-}
-
 /* External symbols defined in the application */
+#[cfg(target_arch = "arm")]
 extern "C" {
     // Not a real function, but a symbol in the linker script
     // which points to the top of the stack
@@ -36,52 +68,86 @@ extern "C" {
     fn _start();
 
     /* Various interrupt handlers implementation:
-     * Systick, SVC and PendSV */
+     * Systick, SVC, PendSV, faults and device interrupts */
     fn z_systick();
     fn z_svc();
     fn z_pendsv();
+    fn z_fault();
+    fn z_irq();
 }
 
+/// Exception or interrupt handler
+pub type Vector = unsafe extern "C" fn();
+
+#[cfg(target_arch = "arm")]
+#[repr(C)]
+pub struct VectorTable {
+    pub exceptions: [Vector; 16],
+    /// Device interrupts, all enter the kernel through `z_irq`
+    pub irqs: [Vector; IRQ_COUNT],
+}
+
+#[cfg(target_arch = "arm")]
+const VECTORS: VectorTable = VectorTable {
+    exceptions: [
+        // Initial Stack Pointer
+        _stack_top,
+        // Reset Handler
+        _reset_handler,
+        // NMI Handler
+        _default_handler,
+        // Hard Fault Handler
+        z_fault,
+        // MPU Fault Handler
+        z_fault,
+        // Bus Fault Handler
+        z_fault,
+        // Usage Fault Handler
+        z_fault,
+        // Reserved
+        _unimplemented,
+        // Reserved
+        _unimplemented,
+        // Reserved
+        _unimplemented,
+        // Reserved
+        _unimplemented,
+        // SVCall Handler
+        z_svc,
+        // Debug Monitor Handler
+        _default_handler,
+        // Reserved
+        _unimplemented,
+        // PendSV Handler
+        z_pendsv,
+        // SysTick Handler
+        z_systick,
+    ],
+    irqs: [z_irq; IRQ_COUNT],
+};
+
+#[cfg(target_arch = "arm")]
 #[no_mangle]
 #[used]
 #[link_section = ".vector_table"]
-static VECTOR_TABLE: [unsafe extern "C" fn(); 16] = [
-    // Initial Stack Pointer
-    _stack_top,
-    // Reset Handler
-    _reset_handler,
-    // NMI Handler
-    _default_handler,
-    // Hard Fault Handler
-    _fault_handler,
-    // MPU Fault Handler
-    _fault_handler,
-    // Bus Fault Handler
-    _fault_handler,
-    // Usage Fault Handler
-    _fault_handler,
-    // Reserved
-    _unimplemented,
-    // Reserved
-    _unimplemented,
-    // Reserved
-    _unimplemented,
-    // Reserved
-    _unimplemented,
-    // SVCall Handler
-    z_svc,
-    // Debug Monitor Handler
-    _default_handler,
-    // Reserved
-    _unimplemented,
-    // PendSV Handler
-    z_pendsv,
-    // SysTick Handler
-    z_systick,
-    // DEVICES INTERRUPTS
-];
+static VECTOR_TABLE: VectorTable = VECTORS;
+
+/// Vector table aligned for VTOR, on the next power of two of its size
+#[cfg(target_arch = "arm")]
+#[repr(C, align(512))]
+pub struct AlignedVectorTable(pub VectorTable);
+
+#[cfg(target_arch = "arm")]
+const _: () = assert!(size_of::<VectorTable>() <= align_of::<AlignedVectorTable>());
+
+/// Copy of the vector table in RAM, which the kernel relocates the vector
+/// table to so that vectors can be changed at runtime (see `irq`), ARMv6-M has
+/// no VTOR.
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+pub static mut Z_RAM_VECTOR_TABLE: AlignedVectorTable = AlignedVectorTable(VECTORS);
 
 // Retrieve symbols from the linker script
+#[cfg(target_arch = "arm")]
 extern "C" {
     static mut _sbss: u8;
     static mut _ebss: u8;
@@ -89,6 +155,8 @@ extern "C" {
     static mut _sdata: u8;
     static mut _edata: u8;
     static _sidata: u8;
+
+    static _enoinit: u8;
 }
 
 // This trick prevent to have two instances of the same code
@@ -97,8 +165,21 @@ extern "C" {
 pub static __ONCE__: () = ();
 
 // TODO also mark as "-> !"
+#[cfg(target_arch = "arm")]
 #[no_mangle]
 pub unsafe extern "C" fn _reset_handler() {
+    // Enable the FPU before any floating point instruction
+    #[cfg(feature = "fpu")]
+    super::fpu::Fpu::instance().init();
+
+    // The kernel stack grows down to the end of the last section in RAM
+    // (.noinit)
+    #[cfg(feature = "cm33")]
+    {
+        let msplim = addr_of!(_enoinit) as u32;
+        core::arch::asm!("msr msplim, {}", in(reg) msplim, options(nomem, nostack));
+    }
+
     // Clear the .bss section
     let bss_size = addr_of!(_ebss) as usize - addr_of!(_sbss) as usize;
     ptr::write_bytes(addr_of_mut!(_sbss), 0, bss_size);
@@ -107,6 +188,13 @@ pub unsafe extern "C" fn _reset_handler() {
     let data_size = addr_of!(_edata) as usize - addr_of!(_sdata) as usize;
     ptr::copy_nonoverlapping(addr_of!(_sidata), addr_of_mut!(_sdata), data_size);
 
+    // Run at FCPU before the application configures its peripherals, the
+    // UART and SysTick settings are wrong at any other frequency
+    #[cfg(feature = "stm32f405")]
+    if let Err(err) = crate::soc::stm32f4::rcc::init() {
+        panic!("system clock not configured: {:?}", err);
+    }
+
     // Call the entry point of the program (it is the main !)
     _start();
 
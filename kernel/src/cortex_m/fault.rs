@@ -0,0 +1,219 @@
+//! Fault status decoding and reporting (HardFault, MemManage, BusFault, UsageFault).
+//!
+//! Faults raised by user threads (process stack) are routed back to the kernel
+//! by the `z_fault` handler, which kills the thread. Faults raised by the kernel
+//! itself (main stack) cannot be recovered: a report is printed and the system
+//! halts.
+//!
+//! On ARMv6-M (Cortex-M0), all faults are HardFaults, reported without status.
+
+use core::ptr;
+
+use crate::println;
+
+use super::{arch::__exception_sf, scb::SCB};
+
+/// Exception numbers of the fault handlers (IPSR value)
+pub const HARDFAULT: u32 = 3;
+pub const MEMMANAGE: u32 = 4;
+pub const BUSFAULT: u32 = 5;
+pub const USAGEFAULT: u32 = 6;
+
+// MemManage Fault Status Register (CFSR[7:0])
+const IACCVIOL: u32 = 1 << 0;
+const DACCVIOL: u32 = 1 << 1;
+const MUNSTKERR: u32 = 1 << 3;
+const MSTKERR: u32 = 1 << 4;
+const MLSPERR: u32 = 1 << 5;
+const MMARVALID: u32 = 1 << 7;
+
+// BusFault Status Register (CFSR[15:8])
+const IBUSERR: u32 = 1 << 8;
+const PRECISERR: u32 = 1 << 9;
+const IMPRECISERR: u32 = 1 << 10;
+const UNSTKERR: u32 = 1 << 11;
+const STKERR: u32 = 1 << 12;
+const LSPERR: u32 = 1 << 13;
+const BFARVALID: u32 = 1 << 15;
+
+// UsageFault Status Register (CFSR[31:16])
+const UNDEFINSTR: u32 = 1 << 16;
+const INVSTATE: u32 = 1 << 17;
+const INVPC: u32 = 1 << 18;
+const NOCP: u32 = 1 << 19;
+// Stack limit violation (ARMv8-M Mainline, PSPLIM/MSPLIM)
+const STKOF: u32 = 1 << 20;
+const UNALIGNED: u32 = 1 << 24;
+const DIVBYZERO: u32 = 1 << 25;
+
+// HardFault Status Register
+const HFSR_VECTTBL: u32 = 1 << 1;
+const HFSR_FORCED: u32 = 1 << 30;
+const HFSR_DEBUGEVT: u32 = 1 << 31;
+
+/// Errors occurring while stacking the exception frame, the frame cannot be trusted
+const STACKING_ERRORS: u32 = MSTKERR | MLSPERR | STKERR | LSPERR | STKOF;
+
+const CFSR_DESCRIPTIONS: [(u32, &str); 18] = [
+    (IACCVIOL, "instruction access violation"),
+    (DACCVIOL, "data access violation"),
+    (MUNSTKERR, "MemManage fault on unstacking"),
+    (MSTKERR, "MemManage fault on stacking"),
+    (MLSPERR, "MemManage fault on FP lazy state preservation"),
+    (IBUSERR, "instruction bus error"),
+    (PRECISERR, "precise data bus error"),
+    (IMPRECISERR, "imprecise data bus error"),
+    (UNSTKERR, "BusFault on unstacking"),
+    (STKERR, "BusFault on stacking"),
+    (LSPERR, "BusFault on FP lazy state preservation"),
+    (UNDEFINSTR, "undefined instruction"),
+    (INVSTATE, "invalid state (EPSR)"),
+    (INVPC, "invalid PC load (EXC_RETURN)"),
+    (NOCP, "no coprocessor"),
+    (STKOF, "stack overflow (stack limit)"),
+    (UNALIGNED, "unaligned access"),
+    (DIVBYZERO, "divide by zero"),
+];
+
+const HFSR_DESCRIPTIONS: [(u32, &str); 3] = [
+    (HFSR_VECTTBL, "vector table read"),
+    (HFSR_FORCED, "forced (escalated configurable fault)"),
+    (HFSR_DEBUGEVT, "debug event"),
+];
+
+/// Snapshot of the fault status registers.
+#[derive(Clone, Copy, Debug)]
+pub struct FaultInfo {
+    /// Exception number of the fault
+    pub exception: u32,
+    /// Configurable Fault Status Register
+    pub cfsr: u32,
+    /// HardFault Status Register
+    pub hfsr: u32,
+    /// MemManage Fault Address Register
+    pub mmfar: u32,
+    /// BusFault Address Register
+    pub bfar: u32,
+}
+
+impl FaultInfo {
+    /// Reads the fault status registers for the given fault exception.
+    #[cfg(not(feature = "cm0"))]
+    pub fn read(exception: u32) -> Self {
+        let scb = SCB::instance();
+
+        FaultInfo {
+            exception,
+            cfsr: scb.get_cfsr(),
+            hfsr: scb.get_hfsr(),
+            mmfar: scb.get_mmfar(),
+            bfar: scb.get_bfar(),
+        }
+    }
+
+    /// ARMv6-M has no fault status registers (accessing them faults), only the
+    /// exception number is known.
+    #[cfg(feature = "cm0")]
+    pub fn read(exception: u32) -> Self {
+        FaultInfo {
+            exception,
+            cfsr: 0,
+            hfsr: 0,
+            mmfar: 0,
+            bfar: 0,
+        }
+    }
+
+    /// Clears the fault status bits, so that next faults are reported accurately.
+    pub fn clear(&self) {
+        if cfg!(not(feature = "cm0")) {
+            let mut scb = SCB::instance();
+            scb.clear_cfsr(self.cfsr);
+            scb.clear_hfsr(self.hfsr);
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self.exception {
+            HARDFAULT => "HardFault",
+            MEMMANAGE => "MemManage",
+            BUSFAULT => "BusFault",
+            USAGEFAULT => "UsageFault",
+            _ => "unknown fault",
+        }
+    }
+
+    /// Whether the exception frame has been stacked successfully.
+    pub fn frame_valid(&self) -> bool {
+        self.cfsr & STACKING_ERRORS == 0
+    }
+
+    /// Prints a decoded report of the fault.
+    ///
+    /// # Arguments
+    ///
+    /// * `frame` - Stack pointer of the faulting context, pointing to the
+    ///   exception frame (only read if it has been stacked successfully).
+    pub fn report(&self, frame: *const u32) {
+        println!(
+            "{} (CFSR: {:#010x}, HFSR: {:#010x})",
+            self.name(),
+            self.cfsr,
+            self.hfsr
+        );
+
+        for (bit, description) in CFSR_DESCRIPTIONS.iter() {
+            if self.cfsr & bit != 0 {
+                println!("  {}", description);
+            }
+        }
+        for (bit, description) in HFSR_DESCRIPTIONS.iter() {
+            if self.hfsr & bit != 0 {
+                println!("  {}", description);
+            }
+        }
+
+        if self.cfsr & MMARVALID != 0 {
+            println!("  MemManage fault address: {:#010x}", self.mmfar);
+        }
+        if self.cfsr & BFARVALID != 0 {
+            println!("  BusFault address: {:#010x}", self.bfar);
+        }
+
+        println!("  SP: {:p}", frame);
+
+        if self.frame_valid() {
+            let sf = unsafe { ptr::read(frame as *const __exception_sf) };
+
+            println!(
+                "  PC: {:#010x} LR: {:#010x} xPSR: {:#010x}",
+                sf.pc, sf.lr, sf.xpsr
+            );
+            println!(
+                "  r0: {:#010x} r1: {:#010x} r2: {:#010x} r3: {:#010x} r12: {:#010x}",
+                sf.r0, sf.r1, sf.r2, sf.r3, sf.r12
+            );
+        } else {
+            println!("  exception frame could not be stacked");
+        }
+    }
+}
+
+/// Fault raised by the kernel itself, the system cannot recover.
+///
+/// Called by the `z_fault` handler with the main stack pointer (pointing to the
+/// exception frame) and the exception number.
+///
+/// # Safety
+///
+/// Must only be called from the fault handler, with `frame` pointing to the
+/// exception frame stacked on the main stack (it is read if the fault status
+/// reports it as valid).
+#[no_mangle]
+pub unsafe extern "C" fn z_kernel_fault(frame: *const u32, exception: u32) -> ! {
+    println!("kernel fault, system halted");
+    FaultInfo::read(exception).report(frame);
+
+    #[allow(clippy::empty_loop)]
+    loop {}
+}
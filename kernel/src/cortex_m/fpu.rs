@@ -0,0 +1,66 @@
+//! Floating point unit of the Cortex-M4F (FPv4-SP) and Cortex-M33 (FPv5-SP).
+//!
+//! The FPU is enabled with lazy stacking: when an exception is taken while the
+//! running context uses the FPU (CONTROL.FPCA), room is reserved for s0-s15 and
+//! FPSCR in its exception frame (extended frame), and they are only written
+//! there once the handler executes a floating point instruction.
+//!
+//! The callee saved registers s16-s31 are not part of the exception frame, the
+//! kernel saves them in the context of the threads which use the FPU.
+
+use core::ops::Deref;
+
+use volatile_register::RW;
+
+use super::scb::SCB;
+
+pub const FPU_BASE: usize = 0xE000_EF34;
+
+#[repr(C)]
+pub struct FpuRegs {
+    /// Offset: 0x000 (R/W)  Floating-Point Context Control Register
+    pub fpccr: RW<u32>,
+    /// Offset: 0x004 (R/W)  Floating-Point Context Address Register
+    pub fpcar: RW<u32>,
+    /// Offset: 0x008 (R/W)  Floating-Point Default Status Control Register
+    pub fpdscr: RW<u32>,
+}
+
+/// Automatic state preservation: CONTROL.FPCA is set by floating point
+/// instructions, exception frames are then extended
+const FPCCR_ASPEN: u32 = 1 << 31;
+/// Lazy state preservation
+const FPCCR_LSPEN: u32 = 1 << 30;
+
+pub struct Fpu {}
+
+impl Fpu {
+    pub const PTR: *const FpuRegs = FPU_BASE as *const FpuRegs;
+
+    #[inline(always)]
+    pub fn instance() -> Self {
+        Fpu {}
+    }
+
+    /// Enables the FPU with lazy stacking, must be called before any floating
+    /// point instruction.
+    pub fn init(&mut self) {
+        SCB::instance().enable_fpu_access();
+
+        unsafe {
+            self.fpccr.modify(|fpccr| fpccr | FPCCR_ASPEN | FPCCR_LSPEN);
+
+            #[cfg(target_arch = "arm")]
+            core::arch::asm!("dsb", "isb", options(nostack, preserves_flags))
+        };
+    }
+}
+
+impl Deref for Fpu {
+    type Target = FpuRegs;
+
+    #[inline(always)]
+    fn deref(&self) -> &FpuRegs {
+        unsafe { &*Self::PTR }
+    }
+}
@@ -1,10 +1,10 @@
-use core::{
-    arch::asm,
-    sync::atomic::{compiler_fence, Ordering},
-};
+#[cfg(target_arch = "arm")]
+use core::arch::asm;
+use core::sync::atomic::{compiler_fence, Ordering};
 
 use super::critical_section::{self, Cs, GlobalIrq};
 
+#[cfg(target_arch = "arm")]
 pub fn enabled() -> bool {
     let primask: u32;
 
@@ -14,7 +14,15 @@ pub fn enabled() -> bool {
     primask & (1 << 0) == 0
 }
 
+// There are no interrupts to mask on the host, critical sections only keep
+// the compiler from reordering memory accesses
+#[cfg(not(target_arch = "arm"))]
+pub fn enabled() -> bool {
+    true
+}
+
 pub fn disable() -> Cs<GlobalIrq> {
+    #[cfg(target_arch = "arm")]
     unsafe {
         asm!("cpsid i");
     }
@@ -37,6 +45,7 @@ pub fn enable(_cs: Cs<GlobalIrq>) {
      * memory operations must complete before enabling interrupts */
     compiler_fence(Ordering::SeqCst);
 
+    #[cfg(target_arch = "arm")]
     unsafe {
         asm!("cpsie i");
     }
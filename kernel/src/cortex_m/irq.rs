@@ -0,0 +1,83 @@
+//! Device interrupts.
+//!
+//! All the device interrupts enter the kernel through `z_irq`, which masks the
+//! interrupt in the NVIC and records it as pending. The kernel loop then calls
+//! the handler registered for it (`Kernel::register_irq_handler`) in the kernel
+//! context, and unmasks it once the handler returned. A thread interrupted by a
+//! device interrupt returns to the kernel right away, an interrupt taken by the
+//! kernel itself is dispatched on the next return to the kernel (interrupt,
+//! syscall or fault of a thread).
+//!
+//! The vector table is relocated in RAM (VTOR) when the kernel is initialized,
+//! so that a device interrupt can also be routed to its own handler at runtime
+//! (`set_vector`), bypassing the kernel. ARMv6-M has no VTOR.
+
+use core::ptr::addr_of_mut;
+
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+use core::ptr::{addr_of, write_volatile};
+
+use super::interrupts;
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+use super::{
+    cortex_m_rt::{Vector, Z_RAM_VECTOR_TABLE},
+    nvic::InterruptNumber,
+    scb::SCB,
+};
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+use crate::{
+    kernel::errno::{KResult, Kerr},
+    soc::IRQ_COUNT,
+};
+
+/// Words of the pending interrupts bitmap, as many as NVIC enable registers
+pub const IRQ_WORDS: usize = 8;
+
+/// Device interrupts taken but not dispatched by the kernel yet, set by
+/// `z_irq` with interrupts disabled.
+///
+/// This address must be accessible from assembly code.
+#[used]
+#[no_mangle]
+static mut Z_IRQ_PENDING: [u32; IRQ_WORDS] = [0; IRQ_WORDS];
+
+/// Takes the device interrupts recorded as pending by `z_irq`, as a bitmap.
+pub fn take_pending() -> [u32; IRQ_WORDS] {
+    interrupts::atomic_restore(|_| unsafe { core::mem::take(&mut *addr_of_mut!(Z_IRQ_PENDING)) })
+}
+
+/// Relocates the vector table to its copy in RAM.
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+pub fn relocate_vector_table() {
+    let table = unsafe { addr_of!(Z_RAM_VECTOR_TABLE) } as u32;
+
+    SCB::instance().set_vector_table(table);
+}
+
+/// Routes a device interrupt to the given handler, which runs in handler mode
+/// without any kernel involvement, or back to the kernel (`z_irq`) if `None`.
+///
+/// # Returns
+///
+/// * `Ok(())` if the vector has been changed.
+/// * `Err(Kerr::InvalidArguments)` if the SoC has no such interrupt.
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+pub fn set_vector<I: InterruptNumber>(irqn: I, handler: Option<Vector>) -> KResult<()> {
+    extern "C" {
+        fn z_irq();
+    }
+
+    let index = irqn.number() as usize;
+    if index >= IRQ_COUNT {
+        return Err(Kerr::InvalidArguments);
+    }
+
+    unsafe {
+        let vector = addr_of_mut!(Z_RAM_VECTOR_TABLE.0.irqs[index]);
+        write_volatile(vector, handler.unwrap_or(z_irq));
+
+        core::arch::asm!("dsb", "isb", options(nostack, preserves_flags));
+    }
+
+    Ok(())
+}
@@ -1,11 +1,63 @@
 pub mod arch;
+#[cfg(feature = "cm0")]
+pub mod armv6m;
 pub mod cortex_m_rt;
+#[cfg(all(target_arch = "arm", not(feature = "cm0")))]
 pub mod cpu;
 pub mod critical_section;
+pub mod fault;
+#[cfg(feature = "fpu")]
+pub mod fpu;
 pub mod interrupts;
+pub mod irq;
 pub mod irqn;
+pub mod mpu;
+#[cfg(feature = "cm33")]
+pub mod mpu_v8m;
 pub mod nvic;
 pub mod scb;
+pub mod semihosting;
 pub mod systick;
 
 pub const SCS_BASE: usize = 0xE000E000;
+
+#[cfg(any(
+    all(feature = "cm0", feature = "cm3"),
+    all(feature = "cm0", feature = "cm4"),
+    all(feature = "cm0", feature = "cm33"),
+    all(feature = "cm3", feature = "cm4"),
+    all(feature = "cm3", feature = "cm33"),
+    all(feature = "cm4", feature = "cm33")
+))]
+compile_error!(
+    "features `cm0`, `cm3`, `cm4` and `cm33` (`microbit`, `mps2-an385`, `mps2-an386` and `mps2-an505`) are mutually exclusive"
+);
+
+#[cfg(all(feature = "cm0", feature = "fpu"))]
+compile_error!("the Cortex-M0 (`cm0`) has no FPU, disable the `fpu` feature");
+
+#[cfg(all(feature = "cm0", feature = "kernel-mpu"))]
+compile_error!("the Cortex-M0 (`cm0`) has no MPU, disable the `kernel-mpu` feature");
+
+// The ARMv6-M architecture only has a subset of the Thumb-2 instructions, the
+// context switch of the Cortex-M3/M4 does not assemble for it and conversely
+#[cfg(all(feature = "cm0", target_arch = "arm", target_feature = "thumb2"))]
+compile_error!("the Cortex-M0 (`cm0`) requires the `thumbv6m-none-eabi` target");
+
+#[cfg(all(
+    any(feature = "cm3", feature = "cm4", feature = "cm33"),
+    target_arch = "arm",
+    not(target_feature = "thumb2")
+))]
+compile_error!("the Cortex-M3/M4/M33 (`cm3`, `cm4`, `cm33`) require a `thumbv7` or `thumbv8m.main` target");
+
+#[cfg(all(feature = "cm3", feature = "fpu"))]
+compile_error!("the Cortex-M3 (`cm3`) has no FPU, disable the `fpu` feature");
+
+// The Cortex-M3 has neither the DSP extension nor an FPU
+#[cfg(all(
+    feature = "cm3",
+    target_arch = "arm",
+    any(target_feature = "dsp", target_abi = "eabihf")
+))]
+compile_error!("the Cortex-M3 (`cm3`) requires the `thumbv7m-none-eabi` target");
@@ -0,0 +1,278 @@
+//! ARMv7-M Memory Protection Unit (PMSAv7).
+//!
+//! The MPU is configured with the privileged default memory map enabled
+//! (PRIVDEFENA), so the kernel can access the whole memory while user threads
+//! can only access the regions programmed for them:
+//!
+//! - region 0: flash (code and read-only data), read-only and executable
+//! - region 1: stack of the thread
+//! - regions 2 to 7: memory regions of the thread (e.g. loaded ELF sections,
+//!   explicitly shared regions)
+//!
+//! ARMv7-M regions must have a power of two size (at least 32 bytes) and be
+//! aligned on their size, regions of 256 bytes or more are divided in 8
+//! subregions that can be disabled individually. A memory region must be
+//! covered exactly by an MPU region, possibly trimmed with subregions, it is
+//! never rounded outward: regions and stacks are typically given a power of
+//! two size and aligned on it.
+
+use core::ops::Deref;
+
+use volatile_register::{RO, RW};
+
+use crate::{
+    kernel::errno::{KResult, Kerr},
+    mem::region::{Access, MemoryRegion, MAX_THREAD_REGIONS},
+};
+
+pub const MPU_BASE: usize = 0xE000_ED90;
+
+/// Number of regions supported by the ARMv7-M MPU.
+pub const MPU_REGIONS: usize = 8;
+
+pub const FLASH_REGION: usize = 0;
+pub const STACK_REGION: usize = 1;
+pub const THREAD_REGIONS_FIRST: usize = 2;
+
+const _: () = assert!(THREAD_REGIONS_FIRST + MAX_THREAD_REGIONS <= MPU_REGIONS);
+
+#[repr(C)]
+pub struct MpuRegs {
+    /// Offset: 0x000 (R/ )  MPU Type Register
+    pub type_: RO<u32>,
+    /// Offset: 0x004 (R/W)  MPU Control Register
+    pub ctrl: RW<u32>,
+    /// Offset: 0x008 (R/W)  MPU Region Number Register
+    pub rnr: RW<u32>,
+    /// Offset: 0x00C (R/W)  MPU Region Base Address Register
+    pub rbar: RW<u32>,
+    /// Offset: 0x010 (R/W)  MPU Region Attribute and Size Register
+    pub rasr: RW<u32>,
+}
+
+const CTRL_ENABLE: u32 = 1 << 0;
+const CTRL_PRIVDEFENA: u32 = 1 << 2;
+
+const RASR_ENABLE: u32 = 1 << 0;
+const RASR_SIZE_POS: u32 = 1;
+const RASR_SRD_POS: u32 = 8;
+const RASR_XN: u32 = 1 << 28;
+const RASR_AP_POS: u32 = 24;
+
+// Normal memory, outer and inner write-through, no write allocate
+const RASR_NORMAL: u32 = 1 << 17; // C = 1, B = 0, TEX = 0b000
+
+const AP_FULL_ACCESS: u32 = 0b011;
+const AP_READ_ONLY: u32 = 0b110;
+
+/// Encoded MPU region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MpuRegion {
+    pub rbar: u32,
+    pub rasr: u32,
+}
+
+impl MpuRegion {
+    /// Encodes a memory region as the smallest MPU region covering it exactly.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MpuRegion)` covering exactly `[start, start + size)`.
+    /// * `Err(Kerr::InvalidArguments)` if the region is empty or cannot be
+    ///   covered without granting access outside of it.
+    pub fn encode(region: &MemoryRegion) -> KResult<Self> {
+        if region.size == 0 {
+            return Err(Kerr::InvalidArguments);
+        }
+
+        let start = region.start as u64;
+        let end = start + region.size as u64;
+
+        // Smallest aligned power of two block containing the region, whose
+        // enabled subregions (if any) match the region bounds
+        let log2 = (5..=32)
+            .find(|&log2| {
+                let size = 1u64 << log2;
+                let base = start & !(size - 1);
+                let exact = start == base && end == base + size;
+                let sub = size / 8;
+                let trimmed = size >= 256 && start % sub == 0 && end % sub == 0;
+                end <= base + size && (exact || trimmed)
+            })
+            .ok_or(Kerr::InvalidArguments)?;
+        let size = 1u64 << log2;
+        let base = start & !(size - 1);
+
+        // Disable the subregions outside of the memory region
+        let srd = if size >= 256 {
+            let sub = size / 8;
+            let first = (start - base) / sub;
+            let last = (end - 1 - base) / sub;
+            let enabled = (0..8)
+                .filter(|i| (first..=last).contains(i))
+                .fold(0u32, |mask, i| mask | (1 << i));
+            !enabled & 0xff
+        } else {
+            0
+        };
+
+        let ap = match region.access {
+            Access::ReadOnly => AP_READ_ONLY,
+            Access::ReadWrite => AP_FULL_ACCESS,
+        };
+        let xn = if region.executable { 0 } else { RASR_XN };
+
+        Ok(MpuRegion {
+            rbar: base as u32,
+            rasr: xn
+                | ap << RASR_AP_POS
+                | RASR_NORMAL
+                | srd << RASR_SRD_POS
+                | (log2 - 1) << RASR_SIZE_POS
+                | RASR_ENABLE,
+        })
+    }
+}
+
+pub struct Mpu {}
+
+impl Mpu {
+    pub const PTR: *const MpuRegs = MPU_BASE as *const MpuRegs;
+
+    #[inline(always)]
+    pub fn instance() -> Self {
+        Mpu {}
+    }
+
+    /// Number of regions supported, 0 if the MPU is not implemented.
+    pub fn get_regions_count(&self) -> u32 {
+        (self.type_.read() >> 8) & 0xff
+    }
+
+    /// Programs the flash region, disables other regions and enables the MPU.
+    pub fn init(&mut self, flash: &MemoryRegion) {
+        unsafe { self.ctrl.write(0) };
+
+        for number in 0..MPU_REGIONS {
+            self.disable_region(number);
+        }
+        self.set_region(FLASH_REGION, Some(flash));
+
+        unsafe { self.ctrl.write(CTRL_ENABLE | CTRL_PRIVDEFENA) };
+        Self::barrier();
+    }
+
+    /// Programs a region, a memory region which cannot be encoded exactly
+    /// disables it rather than granting more than the memory region.
+    pub fn set_region(&mut self, number: usize, region: Option<&MemoryRegion>) {
+        match region.and_then(|region| MpuRegion::encode(region).ok()) {
+            Some(encoded) => unsafe {
+                self.rnr.write(number as u32);
+                // Disable the region while it is being updated
+                self.rasr.write(0);
+                self.rbar.write(encoded.rbar);
+                self.rasr.write(encoded.rasr);
+            },
+            None => self.disable_region(number),
+        }
+    }
+
+    pub fn disable_region(&mut self, number: usize) {
+        unsafe {
+            self.rnr.write(number as u32);
+            self.rasr.write(0);
+        }
+    }
+
+    /// Programs the regions of the thread about to be switched to.
+    pub fn configure_thread(
+        &mut self,
+        stack: &MemoryRegion,
+        regions: &[Option<MemoryRegion>; MAX_THREAD_REGIONS],
+    ) {
+        self.set_region(STACK_REGION, Some(stack));
+
+        for (i, region) in regions.iter().enumerate() {
+            self.set_region(THREAD_REGIONS_FIRST + i, region.as_ref());
+        }
+
+        Self::barrier();
+    }
+
+    #[inline(always)]
+    fn barrier() {
+        #[cfg(target_arch = "arm")]
+        unsafe {
+            core::arch::asm!("dsb", "isb", options(nostack, preserves_flags))
+        };
+    }
+}
+
+impl Deref for Mpu {
+    type Target = MpuRegs;
+
+    #[inline(always)]
+    fn deref(&self) -> &MpuRegs {
+        unsafe { &*Self::PTR }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Decodes the address ranges enabled by an encoded region.
+    fn decode(encoded: &MpuRegion) -> impl Iterator<Item = (u64, u64)> {
+        let size = 1u64 << (((encoded.rasr >> RASR_SIZE_POS) & 0x1f) + 1);
+        let base = encoded.rbar as u64;
+        let srd = (encoded.rasr >> RASR_SRD_POS) & 0xff;
+        let sub = if size >= 256 { size / 8 } else { size };
+        let count = size / sub;
+
+        (0..count)
+            .filter(move |&i| srd & (1 << i) == 0)
+            .map(move |i| (base + i * sub, base + (i + 1) * sub))
+    }
+
+    fn rw(start: usize, size: usize) -> MemoryRegion {
+        MemoryRegion::new(start, size, Access::ReadWrite, false)
+    }
+
+    #[test]
+    fn test_encode_within_region() {
+        let regions = [
+            rw(0x2000_0000, 32),
+            rw(0x2000_0400, 1024),
+            rw(0x2000_4000, 16384),
+            rw(0x2000_3000, 0x3000),
+            rw(0x2000_0100, 0x200),
+            rw(0x0000_0000, 0x40_0000),
+        ];
+
+        for region in regions.iter() {
+            let encoded = MpuRegion::encode(region).expect("region should be encoded");
+            let mut covered = 0;
+            for (start, end) in decode(&encoded) {
+                assert!(start >= region.start as u64 && end <= region.end() as u64);
+                covered += end - start;
+            }
+            assert_eq!(covered, region.size as u64);
+        }
+    }
+
+    #[test]
+    fn test_encode_rejects_inexact() {
+        let regions = [
+            rw(0x2000_0000, 0),
+            rw(0x2000_0000, 4),
+            rw(0x2000_0008, 32),
+            rw(0x2000_0000, 100),
+            rw(0x2000_0008, 16384),
+            rw(0x2000_0100, 0x1000),
+        ];
+
+        for region in regions.iter() {
+            assert!(MpuRegion::encode(region).is_err(), "{:?}", region);
+        }
+    }
+}
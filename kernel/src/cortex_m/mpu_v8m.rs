@@ -0,0 +1,228 @@
+//! ARMv8-M Memory Protection Unit (PMSAv8).
+//!
+//! Same layout and interface as the ARMv7-M MPU (`mpu` module): the privileged
+//! default memory map is enabled (PRIVDEFENA), region 0 maps the flash, region
+//! 1 the stack of the thread and regions 2 to 7 its memory regions.
+//!
+//! ARMv8-M regions are defined by a base and a limit address with a 32 bytes
+//! granularity, a memory region must be aligned on it as it is never rounded
+//! outward. Unlike ARMv7-M, regions must not overlap: an access matching
+//! several regions faults.
+//!
+//! The memory attributes are taken from MAIR0, all the regions use the
+//! attribute 0 (normal memory).
+
+use core::ops::Deref;
+
+use volatile_register::{RO, RW};
+
+use crate::{
+    kernel::errno::{KResult, Kerr},
+    mem::region::{Access, MemoryRegion, MAX_THREAD_REGIONS},
+};
+
+pub const MPU_BASE: usize = 0xE000_ED90;
+
+/// Number of regions used by the kernel, the Cortex-M33 implements up to 16.
+pub const MPU_REGIONS: usize = 8;
+
+pub const FLASH_REGION: usize = 0;
+pub const STACK_REGION: usize = 1;
+pub const THREAD_REGIONS_FIRST: usize = 2;
+
+const _: () = assert!(THREAD_REGIONS_FIRST + MAX_THREAD_REGIONS <= MPU_REGIONS);
+
+#[repr(C)]
+pub struct MpuRegs {
+    /// Offset: 0x000 (R/ )  MPU Type Register
+    pub type_: RO<u32>,
+    /// Offset: 0x004 (R/W)  MPU Control Register
+    pub ctrl: RW<u32>,
+    /// Offset: 0x008 (R/W)  MPU Region Number Register
+    pub rnr: RW<u32>,
+    /// Offset: 0x00C (R/W)  MPU Region Base Address Register
+    pub rbar: RW<u32>,
+    /// Offset: 0x010 (R/W)  MPU Region Limit Address Register
+    pub rlar: RW<u32>,
+    /// Offset: 0x014 (R/W)  MPU Region Base/Limit Address Register aliases 1 to 3
+    pub rbar_rlar_a: [RW<u32>; 6],
+    _reserved0: u32,
+    /// Offset: 0x030 (R/W)  MPU Memory Attribute Indirection Register 0
+    pub mair0: RW<u32>,
+    /// Offset: 0x034 (R/W)  MPU Memory Attribute Indirection Register 1
+    pub mair1: RW<u32>,
+}
+
+const CTRL_ENABLE: u32 = 1 << 0;
+const CTRL_PRIVDEFENA: u32 = 1 << 2;
+
+/// Regions granularity
+const GRANULE: u64 = 32;
+
+const RBAR_XN: u32 = 1 << 0;
+const RBAR_AP_POS: u32 = 1;
+
+const RLAR_ENABLE: u32 = 1 << 0;
+const RLAR_ATTR_INDX_POS: u32 = 1;
+
+// Normal memory, outer and inner write-through non-transient, read allocate, no
+// write allocate
+const MAIR_ATTR_NORMAL: u32 = 0xAA;
+const ATTR_INDX_NORMAL: u32 = 0;
+
+// Read-write/read-only by privileged and unprivileged code
+const AP_FULL_ACCESS: u32 = 0b01;
+const AP_READ_ONLY: u32 = 0b11;
+
+/// Encoded MPU region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MpuRegion {
+    pub rbar: u32,
+    pub rlar: u32,
+}
+
+impl MpuRegion {
+    /// Encodes a memory region whose bounds are aligned on the 32 bytes granularity.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(MpuRegion)` covering exactly `[start, start + size)`.
+    /// * `Err(Kerr::InvalidArguments)` if the region is empty or not aligned.
+    pub fn encode(region: &MemoryRegion) -> KResult<Self> {
+        let start = region.start as u64;
+        let size = region.size as u64;
+
+        if size == 0 || start % GRANULE != 0 || size % GRANULE != 0 {
+            return Err(Kerr::InvalidArguments);
+        }
+
+        let base = start;
+        // Address of the last granule of the region
+        let limit = start + size - GRANULE;
+
+        let ap = match region.access {
+            Access::ReadOnly => AP_READ_ONLY,
+            Access::ReadWrite => AP_FULL_ACCESS,
+        };
+        let xn = if region.executable { 0 } else { RBAR_XN };
+
+        Ok(MpuRegion {
+            // Non-shareable
+            rbar: base as u32 | ap << RBAR_AP_POS | xn,
+            rlar: limit as u32 | ATTR_INDX_NORMAL << RLAR_ATTR_INDX_POS | RLAR_ENABLE,
+        })
+    }
+}
+
+pub struct Mpu {}
+
+impl Mpu {
+    pub const PTR: *const MpuRegs = MPU_BASE as *const MpuRegs;
+
+    #[inline(always)]
+    pub fn instance() -> Self {
+        Mpu {}
+    }
+
+    /// Number of regions supported, 0 if the MPU is not implemented.
+    pub fn get_regions_count(&self) -> u32 {
+        (self.type_.read() >> 8) & 0xff
+    }
+
+    /// Programs the memory attributes and the flash region, disables other
+    /// regions and enables the MPU.
+    pub fn init(&mut self, flash: &MemoryRegion) {
+        unsafe {
+            self.ctrl.write(0);
+            self.mair0.write(MAIR_ATTR_NORMAL << (8 * ATTR_INDX_NORMAL));
+        }
+
+        for number in 0..MPU_REGIONS {
+            self.disable_region(number);
+        }
+        self.set_region(FLASH_REGION, Some(flash));
+
+        unsafe { self.ctrl.write(CTRL_ENABLE | CTRL_PRIVDEFENA) };
+        Self::barrier();
+    }
+
+    /// Programs a region, a memory region which cannot be encoded exactly
+    /// disables it rather than granting more than the memory region.
+    pub fn set_region(&mut self, number: usize, region: Option<&MemoryRegion>) {
+        match region.and_then(|region| MpuRegion::encode(region).ok()) {
+            Some(encoded) => unsafe {
+                self.rnr.write(number as u32);
+                // Disable the region while it is being updated
+                self.rlar.write(0);
+                self.rbar.write(encoded.rbar);
+                self.rlar.write(encoded.rlar);
+            },
+            None => self.disable_region(number),
+        }
+    }
+
+    pub fn disable_region(&mut self, number: usize) {
+        unsafe {
+            self.rnr.write(number as u32);
+            self.rlar.write(0);
+        }
+    }
+
+    /// Programs the regions of the thread about to be switched to.
+    pub fn configure_thread(
+        &mut self,
+        stack: &MemoryRegion,
+        regions: &[Option<MemoryRegion>; MAX_THREAD_REGIONS],
+    ) {
+        self.set_region(STACK_REGION, Some(stack));
+
+        for (i, region) in regions.iter().enumerate() {
+            self.set_region(THREAD_REGIONS_FIRST + i, region.as_ref());
+        }
+
+        Self::barrier();
+    }
+
+    #[inline(always)]
+    fn barrier() {
+        #[cfg(target_arch = "arm")]
+        unsafe {
+            core::arch::asm!("dsb", "isb", options(nostack, preserves_flags))
+        };
+    }
+}
+
+impl Deref for Mpu {
+    type Target = MpuRegs;
+
+    #[inline(always)]
+    fn deref(&self) -> &MpuRegs {
+        unsafe { &*Self::PTR }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rw(start: usize, size: usize) -> MemoryRegion {
+        MemoryRegion::new(start, size, Access::ReadWrite, false)
+    }
+
+    #[test]
+    fn test_encode_within_region() {
+        for region in [rw(0x2000_0020, 32), rw(0x2000_0100, 0x1e0)].iter() {
+            let encoded = MpuRegion::encode(region).expect("region should be encoded");
+            let base = (encoded.rbar & !(GRANULE as u32 - 1)) as usize;
+            let end = (encoded.rlar & !(GRANULE as u32 - 1)) as usize + GRANULE as usize;
+            assert_eq!((base, end), (region.start, region.end()));
+        }
+    }
+
+    #[test]
+    fn test_encode_rejects_unaligned() {
+        for region in [rw(0x2000_0000, 0), rw(0x2000_0008, 32), rw(0x2000_0000, 40)].iter() {
+            assert!(MpuRegion::encode(region).is_err(), "{:?}", region);
+        }
+    }
+}
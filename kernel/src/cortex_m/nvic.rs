@@ -58,11 +58,34 @@ pub struct NVICRegs {
     stir: WO<u32>,
 }
 
+/// Number of a device interrupt (IRQn >= 0), implemented by the interrupt
+/// enums of the SoCs (e.g. `soc::mps2_an38x::Irqn`).
+pub trait InterruptNumber: Copy {
+    fn number(self) -> u16;
+}
+
+/// Raw interrupt numbers, e.g. recorded by the kernel.
+impl InterruptNumber for u16 {
+    fn number(self) -> u16 {
+        self
+    }
+}
+
 pub struct NVIC;
 
 impl NVIC {
-    #[cfg(any(feature = "cm3", feature = "cm4"))]
+    #[cfg(all(
+        any(feature = "cm3", feature = "cm4", feature = "cm33"),
+        not(feature = "stm32f405")
+    ))]
     pub const PRIO_BITS: u8 = 3;
+    #[cfg(feature = "stm32f405")]
+    pub const PRIO_BITS: u8 = 4;
+    #[cfg(feature = "cm0")]
+    pub const PRIO_BITS: u8 = 2;
+
+    /// Lowest priority (highest value) of the interrupts
+    pub const PRIO_LOWEST: u8 = (1 << Self::PRIO_BITS) - 1;
 
     pub const PTR: *const NVICRegs = 0xE000_E100 as *const NVICRegs;
 
@@ -70,25 +93,115 @@ impl NVIC {
         NVIC {}
     }
 
+    /// Register of the interrupt in a bank of 32 interrupts (ISER, ICER...),
+    /// and its bit in it.
+    #[inline(always)]
+    fn bit<I: InterruptNumber>(irqn: I) -> (usize, u32) {
+        let number = irqn.number() as usize;
+        (number / 32, 1 << (number % 32))
+    }
+
+    pub fn enable<I: InterruptNumber>(&mut self, irqn: I) {
+        let (index, bit) = Self::bit(irqn);
+        unsafe { self.iser[index].write(bit) }
+    }
+
+    /// Disables the interrupt, it is not taken anymore once this returns.
+    pub fn disable<I: InterruptNumber>(&mut self, irqn: I) {
+        let (index, bit) = Self::bit(irqn);
+        unsafe { self.icer[index].write(bit) }
+
+        #[cfg(target_arch = "arm")]
+        unsafe {
+            core::arch::asm!("dsb", "isb", options(nostack, preserves_flags))
+        };
+    }
+
+    pub fn is_enabled<I: InterruptNumber>(&self, irqn: I) -> bool {
+        let (index, bit) = Self::bit(irqn);
+        self.iser[index].read() & bit != 0
+    }
+
+    pub fn pend<I: InterruptNumber>(&mut self, irqn: I) {
+        let (index, bit) = Self::bit(irqn);
+        unsafe { self.ispr[index].write(bit) }
+    }
+
+    pub fn unpend<I: InterruptNumber>(&mut self, irqn: I) {
+        let (index, bit) = Self::bit(irqn);
+        unsafe { self.icpr[index].write(bit) }
+    }
+
+    pub fn is_pending<I: InterruptNumber>(&self, irqn: I) -> bool {
+        let (index, bit) = Self::bit(irqn);
+        self.ispr[index].read() & bit != 0
+    }
+
+    /// Whether the interrupt handler is running (or preempted), there are no
+    /// Interrupt Active Bit Registers on ARMv6-M.
+    #[cfg(not(feature = "cm0"))]
+    pub fn is_active<I: InterruptNumber>(&self, irqn: I) -> bool {
+        let (index, bit) = Self::bit(irqn);
+        self.iabr[index].read() & bit != 0
+    }
+
+    /// Pends the interrupt through the Software Trigger Interrupt Register,
+    /// which unprivileged code can write if CCR.USERSETMPEND is set.
+    #[cfg(not(feature = "cm0"))]
+    pub fn trigger<I: InterruptNumber>(&mut self, irqn: I) {
+        unsafe { self.stir.write(irqn.number() as u32) }
+    }
+
+    // No Software Trigger Interrupt Register on ARMv6-M
+    #[cfg(feature = "cm0")]
+    pub fn trigger<I: InterruptNumber>(&mut self, irqn: I) {
+        self.pend(irqn)
+    }
+
     /* Reference implementation: __NVIC_GetPriority
      * https://github.com/ARM-software/CMSIS_5/blob/develop/CMSIS/Core/Include/core_cm3.h#L1694
      *
      * prio can be between 0 and 7
      */
-    pub fn get_priority(&mut self, irqn: u16) -> u8 {
-        let reg: &RW<u8> = &self.ipr[irqn as usize];
+    #[cfg(not(feature = "cm0"))]
+    pub fn get_priority<I: InterruptNumber>(&self, irqn: I) -> u8 {
+        let reg: &RW<u8> = &self.ipr[irqn.number() as usize];
         reg.read() >> (8 - Self::PRIO_BITS)
     }
 
+    // The Interrupt Priority Registers are only word accessible on ARMv6-M
+    #[cfg(feature = "cm0")]
+    pub fn get_priority<I: InterruptNumber>(&self, irqn: I) -> u8 {
+        let (reg, shift) = self.ipr_word(irqn.number());
+        (reg.read() >> shift) as u8 >> (8 - Self::PRIO_BITS)
+    }
+
     /* Reference implementation: __NVIC_SetPriority
      * https://github.com/ARM-software/CMSIS_5/blob/develop/CMSIS/Core/Include/core_cm3.h#L1672
      *
      * prio can be between 0 and 7
      */
-    pub fn set_priority(&mut self, irqn: u16, prio: u8) {
-        let reg: &RW<u8> = &self.ipr[irqn as usize];
+    #[cfg(not(feature = "cm0"))]
+    pub fn set_priority<I: InterruptNumber>(&mut self, irqn: I, prio: u8) {
+        let reg: &RW<u8> = &self.ipr[irqn.number() as usize];
         unsafe { reg.write(prio << (8 - Self::PRIO_BITS)) }
     }
+
+    #[cfg(feature = "cm0")]
+    pub fn set_priority<I: InterruptNumber>(&mut self, irqn: I, prio: u8) {
+        let (reg, shift) = self.ipr_word(irqn.number());
+        let prio = ((prio << (8 - Self::PRIO_BITS)) as u32) << shift;
+        unsafe { reg.modify(|ipr| (ipr & !(0xff << shift)) | prio) }
+    }
+
+    /// Register holding the priority of the interrupt, and the position of
+    /// the priority in it.
+    #[cfg(feature = "cm0")]
+    fn ipr_word(&self, irqn: u16) -> (&RW<u32>, u32) {
+        let index = irqn as usize;
+        let reg = unsafe { &*(self.ipr.as_ptr() as *const RW<u32>).add(index / 4) };
+        (reg, (index as u32 % 4) * 8)
+    }
 }
 
 impl Deref for NVIC {
@@ -49,6 +49,17 @@ pub struct SCBRegs {
     cpacr: RW<u32>,
 }
 
+const SHCSR_MEMFAULTENA: u32 = 1 << 16;
+const SHCSR_BUSFAULTENA: u32 = 1 << 17;
+const SHCSR_USGFAULTENA: u32 = 1 << 18;
+
+/// Full access to the coprocessors CP10 and CP11 (FPU)
+const CPACR_CP10_CP11_FULL: u32 = 0xF << 20;
+
+const AIRCR_VECTKEY: u32 = 0x05FA << 16;
+const AIRCR_PRIGROUP_MASK: u32 = 0x7 << 8;
+const AIRCR_SYSRESETREQ: u32 = 1 << 2;
+
 pub struct SCB {}
 
 impl SCB {
@@ -65,6 +76,7 @@ impl SCB {
 
     // Valid from SYSTICK(-1) to MEMORYMANAGEMENT(-12)
     // prio can be between 0 and 7
+    #[cfg(not(feature = "cm0"))]
     pub fn get_priority(&self, irqn: SysIrqn) -> u8 {
         let index = ((irqn as u32) & 0xf) - 4;
         self.shp[index as usize].read() >> (8 - NVIC::PRIO_BITS)
@@ -72,10 +84,107 @@ impl SCB {
 
     // Valid from SYSTICK(-1) to MEMORYMANAGEMENT(-12)
     // prio can be between 0 and 7
+    #[cfg(not(feature = "cm0"))]
     pub fn set_priority(&mut self, irqn: SysIrqn, prio: u8) {
         let index = ((irqn as u32) & 0xf) - 4;
         unsafe { self.shp[index as usize].write(prio << (8 - NVIC::PRIO_BITS)) }
     }
+
+    // Valid for SYSTICK(-1), PENDSV(-2) and SVCALL(-5)
+    // prio can be between 0 and 3
+    #[cfg(feature = "cm0")]
+    pub fn get_priority(&self, irqn: SysIrqn) -> u8 {
+        let (shpr, shift) = self.shpr_word(irqn);
+        (shpr.read() >> shift) as u8 >> (8 - NVIC::PRIO_BITS)
+    }
+
+    // Valid for SYSTICK(-1), PENDSV(-2) and SVCALL(-5)
+    // prio can be between 0 and 3
+    #[cfg(feature = "cm0")]
+    pub fn set_priority(&mut self, irqn: SysIrqn, prio: u8) {
+        let (shpr, shift) = self.shpr_word(irqn);
+        let prio = ((prio << (8 - NVIC::PRIO_BITS)) as u32) << shift;
+        unsafe { shpr.modify(|shpr| (shpr & !(0xff << shift)) | prio) }
+    }
+
+    /// The System Handler Priority Registers are only word accessible on
+    /// ARMv6-M, returns the register holding the priority of the handler and
+    /// the position of the priority in it.
+    #[cfg(feature = "cm0")]
+    fn shpr_word(&self, irqn: SysIrqn) -> (&RW<u32>, u32) {
+        let index = ((irqn as usize) & 0xf) - 4;
+        let shpr = unsafe { &*(self.shp.as_ptr() as *const RW<u32>).add(index / 4) };
+        (shpr, (index as u32 % 4) * 8)
+    }
+
+    /// Enables the MemManage, BusFault and UsageFault handlers, otherwise
+    /// these faults escalate to HardFault.
+    ///
+    /// ARMv6-M only has the HardFault, this does nothing.
+    pub fn enable_faults(&mut self) {
+        const FAULTS: u32 = SHCSR_MEMFAULTENA | SHCSR_BUSFAULTENA | SHCSR_USGFAULTENA;
+        if cfg!(not(feature = "cm0")) {
+            unsafe { self.shcsr.modify(|shcsr| shcsr | FAULTS) }
+        }
+    }
+
+    /// Relocates the vector table, its address must be aligned on the next
+    /// power of two of its size. There is no VTOR on ARMv6-M.
+    #[cfg(not(feature = "cm0"))]
+    pub fn set_vector_table(&mut self, addr: u32) {
+        unsafe {
+            self.vtor.write(addr);
+            #[cfg(target_arch = "arm")]
+            core::arch::asm!("dsb", "isb", options(nostack, preserves_flags));
+        }
+    }
+
+    /// Grants privileged and unprivileged accesses to the FPU.
+    pub fn enable_fpu_access(&mut self) {
+        unsafe { self.cpacr.modify(|cpacr| cpacr | CPACR_CP10_CP11_FULL) }
+    }
+
+    pub fn get_cfsr(&self) -> u32 {
+        self.cfsr.read()
+    }
+
+    /// Clears the given fault status bits (write one to clear).
+    pub fn clear_cfsr(&mut self, bits: u32) {
+        unsafe { self.cfsr.write(bits) }
+    }
+
+    pub fn get_mmfar(&self) -> u32 {
+        self.mmfar.read()
+    }
+
+    pub fn get_bfar(&self) -> u32 {
+        self.bfar.read()
+    }
+
+    pub fn get_hfsr(&self) -> u32 {
+        self.hfsr.read()
+    }
+
+    /// Clears the given HardFault status bits (write one to clear).
+    pub fn clear_hfsr(&mut self, bits: u32) {
+        unsafe { self.hfsr.write(bits) }
+    }
+
+    /// Requests a system reset, keeping the priority grouping.
+    pub fn system_reset(&mut self) -> ! {
+        unsafe {
+            #[cfg(target_arch = "arm")]
+            core::arch::asm!("dsb", options(nostack, preserves_flags));
+            self.aircr
+                .modify(|aircr| AIRCR_VECTKEY | (aircr & AIRCR_PRIGROUP_MASK) | AIRCR_SYSRESETREQ);
+            #[cfg(target_arch = "arm")]
+            core::arch::asm!("dsb", options(nostack, preserves_flags));
+        }
+
+        // Wait for the reset to take effect
+        #[allow(clippy::empty_loop)]
+        loop {}
+    }
 }
 
 impl Deref for SCB {
@@ -0,0 +1,175 @@
+//! ARM semihosting, requests handled by the debugger or the emulator (QEMU
+//! with `-semihosting-config enable=on`).
+//!
+//! A semihosting request is a `bkpt 0xAB` instruction with the operation number
+//! in r0 and a pointer to its parameter block in r1. Without a debugger
+//! attached, the breakpoint escalates to a HardFault.
+//!
+//! QEMU only handles requests issued in privileged mode, unless started with
+//! `-semihosting-config enable=on,userspace=on`.
+
+#[cfg(target_arch = "arm")]
+use core::arch::asm;
+
+use crate::serial::{SerialConfig, SerialTrait};
+
+/// Operation numbers
+const SYS_WRITEC: u32 = 0x03;
+const SYS_WRITE0: u32 = 0x04;
+const SYS_CLOCK: u32 = 0x10;
+const SYS_TIME: u32 = 0x11;
+const SYS_EXIT: u32 = 0x18;
+const SYS_EXIT_EXTENDED: u32 = 0x20;
+
+/// Reason codes of SYS_EXIT
+const ADP_STOPPED_APPLICATION_EXIT: u32 = 0x20026;
+const ADP_STOPPED_RUNTIME_ERROR_UNKNOWN: u32 = 0x20023;
+
+/// Size of the buffer used to NUL terminate strings written with SYS_WRITE0
+const WRITE0_CHUNK: usize = 64;
+
+/// Issues a semihosting request.
+///
+/// # Safety
+///
+/// `arg` must be valid for the given operation.
+#[cfg(target_arch = "arm")]
+#[inline(always)]
+pub unsafe fn call(op: u32, arg: usize) -> u32 {
+    let ret: u32;
+
+    asm!("bkpt 0xAB", inout("r0") op => ret, in("r1") arg, options(nostack));
+
+    ret
+}
+
+/// Requests handled by the host C library, for the hosted port.
+///
+/// # Safety
+///
+/// `arg` must be valid for the given operation.
+#[cfg(all(not(target_arch = "arm"), feature = "hosted"))]
+pub unsafe fn call(op: u32, arg: usize) -> u32 {
+    match op {
+        SYS_WRITEC => {
+            libc::write(1, arg as *const libc::c_void, 1);
+            0
+        }
+        SYS_WRITE0 => {
+            let s = arg as *const libc::c_char;
+            libc::write(1, s as *const libc::c_void, libc::strlen(s));
+            0
+        }
+        SYS_CLOCK => {
+            let mut ts = libc::timespec {
+                tv_sec: 0,
+                tv_nsec: 0,
+            };
+            libc::clock_gettime(libc::CLOCK_PROCESS_CPUTIME_ID, &mut ts);
+            (ts.tv_sec as u32) * 100 + (ts.tv_nsec / 10_000_000) as u32
+        }
+        SYS_TIME => libc::time(core::ptr::null_mut()) as u32,
+        SYS_EXIT => libc::exit((arg as u32 != ADP_STOPPED_APPLICATION_EXIT) as libc::c_int),
+        SYS_EXIT_EXTENDED => libc::exit(*(arg as *const u32).add(1) as libc::c_int),
+        _ => u32::MAX,
+    }
+}
+
+/// No debugger on the host, requests fail as if they were not supported.
+///
+/// # Safety
+///
+/// Always safe, for consistency with the target implementation.
+#[cfg(not(any(target_arch = "arm", feature = "hosted")))]
+pub unsafe fn call(_op: u32, _arg: usize) -> u32 {
+    u32::MAX
+}
+
+/// Writes a character to the debugger console.
+pub fn write_char(c: u8) {
+    unsafe { call(SYS_WRITEC, &c as *const u8 as usize) };
+}
+
+/// Writes a string to the debugger console.
+///
+/// The string is copied to NUL terminated chunks, NUL characters it contains
+/// are not written.
+pub fn write_str(s: &str) {
+    let mut buf = [0u8; WRITE0_CHUNK + 1];
+
+    for chunk in s.as_bytes().chunks(WRITE0_CHUNK) {
+        buf[..chunk.len()].copy_from_slice(chunk);
+        buf[chunk.len()] = 0;
+
+        unsafe { call(SYS_WRITE0, buf.as_ptr() as usize) };
+    }
+}
+
+/// Returns the time elapsed since the execution started, in centiseconds, or
+/// `None` on error.
+pub fn clock() -> Option<u32> {
+    match unsafe { call(SYS_CLOCK, 0) } as i32 {
+        -1 => None,
+        centiseconds => Some(centiseconds as u32),
+    }
+}
+
+/// Returns the host time, in seconds since the Unix epoch.
+pub fn time() -> u32 {
+    unsafe { call(SYS_TIME, 0) }
+}
+
+/// Exits the emulator, successfully or with an error.
+///
+/// On ARMv7-M, SYS_EXIT takes the reason code directly in r1, the exit status
+/// cannot be reported: QEMU exits with 0 for a successful exit, 1 otherwise.
+/// Use `exit_with_status` to report a status code.
+pub fn exit(success: bool) -> ! {
+    let reason = if success {
+        ADP_STOPPED_APPLICATION_EXIT
+    } else {
+        ADP_STOPPED_RUNTIME_ERROR_UNKNOWN
+    };
+
+    unsafe { call(SYS_EXIT, reason as usize) };
+
+    // Only reached if the request is not handled
+    #[allow(clippy::empty_loop)]
+    loop {}
+}
+
+/// Exits the emulator with the given status code, which QEMU uses as its own
+/// exit status (e.g. number of failed tests in CI).
+pub fn exit_with_status(status: u32) -> ! {
+    let block = [ADP_STOPPED_APPLICATION_EXIT, status];
+
+    unsafe { call(SYS_EXIT_EXTENDED, block.as_ptr() as usize) };
+
+    // The debugger does not support SYS_EXIT_EXTENDED
+    exit(status == 0)
+}
+
+/// Debugger console, usable as the stdio backend.
+///
+/// Reading is not supported: SYS_READC blocks until a character is available.
+#[derive(Default)]
+pub struct Console;
+
+impl SerialTrait for Console {
+    fn init(&self, _config: &SerialConfig) {}
+
+    fn write_byte(&self, byte: u8) {
+        write_char(byte)
+    }
+
+    fn read(&self) -> Option<u8> {
+        None
+    }
+}
+
+impl core::fmt::Write for Console {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        write_str(s);
+        Ok(())
+    }
+}
@@ -53,6 +53,16 @@ impl<const FREQ_SYS_TICK: u32> SysTick<FREQ_SYS_TICK> {
         self.val.read()
     }
 
+    /// Number of cycles elapsed since the beginning of the current period.
+    pub fn get_elapsed_cycles(&self) -> u32 {
+        self.get_reload_value() - self.get_current_value()
+    }
+
+    /// Number of cycles in a period (the counter counts from the reload value down to 0).
+    pub fn get_period_cycles(&self) -> u32 {
+        self.get_reload_value() + 1
+    }
+
     pub fn get_countflag(&self) -> bool {
         self.ctrl.read() & (1 << COUNTFLAG_POS) != 0
     }
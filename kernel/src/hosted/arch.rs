@@ -0,0 +1,261 @@
+//! Context switch between the kernel and the threads of the hosted port.
+//!
+//! The kernel runs on the stack of the process, each thread on its own stack.
+//! A switch saves the callee saved registers on the current stack, then
+//! restores the ones saved on the other stack (`z_hosted_switch`).
+//!
+//! A thread leaves to the kernel either by calling a syscall, or when the tick
+//! signal interrupts it: the signal handler runs on the thread stack and
+//! switches to the kernel, the thread resumes when the handler returns. The
+//! tick signal is masked while the kernel runs.
+//!
+//! In both cases, the thread pushes a `HostedFrame` on its stack, holding the
+//! syscall registers like the exception frame of the Cortex-M.
+
+use core::{
+    arch::global_asm,
+    ffi::c_void,
+    ptr::{self, addr_of_mut},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+#[cfg(feature = "kernel-loadable-pie")]
+use crate::kernel::elf_loader::{Lex, PICRegImpl};
+use crate::kernel::{
+    kernel::Z_SYSCALL_FLAG, syscalls::SVCCallParams, CpuVariant, ExceptionStackFrame, ThreadEntry,
+};
+
+use super::systick;
+
+/// Frame at the top of the stack of a thread which left to the kernel, the
+/// kernel reads the syscall arguments and writes the return value in the
+/// registers (r0, r1).
+#[repr(C)]
+#[derive(Default)]
+pub struct HostedFrame {
+    pub r0: u32,
+    pub r1: u32,
+    pub r2: u32,
+    pub r3: u32,
+    /// Syscall identifier (immediate of the "svc" instruction)
+    pub svc: u32,
+    _reserved: u32,
+    /// Entry point of the thread and its argument, for its first switch
+    pub entry: usize,
+    pub arg0: usize,
+}
+
+impl ExceptionStackFrame for HostedFrame {
+    fn initialize_at(stack_ptr: *mut u32, entry: ThreadEntry, arg0: *mut c_void) {
+        let frame = HostedFrame {
+            entry: entry as usize,
+            arg0: arg0 as usize,
+            ..Default::default()
+        };
+
+        unsafe { (stack_ptr as *mut Self).write(frame) };
+    }
+
+    unsafe fn syscall_params(stack_ptr: *const u32) -> SVCCallParams {
+        let frame = &*(stack_ptr as *const Self);
+
+        SVCCallParams {
+            r0: frame.r0,
+            r1: frame.r1,
+            r2: frame.r2,
+            r3: frame.r3,
+            syscall_id: frame.svc as u8,
+        }
+    }
+}
+
+/// Stack pointer of a thread which left to the kernel, the callee saved
+/// registers are saved on its stack.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HostedContext {
+    /// 0 until the first switch to the thread
+    rsp: usize,
+}
+
+/// Stack pointer of the kernel while a thread runs
+static mut KERNEL_RSP: usize = 0;
+
+/// Context of the running thread
+static mut CURRENT: *mut HostedContext = ptr::null_mut();
+
+/// Frame pushed by the last thread which left to the kernel
+static mut FRAME: *mut u32 = ptr::null_mut();
+
+/// Set while a thread runs, the tick signal then switches to the kernel
+static IN_THREAD: AtomicBool = AtomicBool::new(false);
+
+extern "C" {
+    fn z_hosted_switch(save_rsp: *mut usize, rsp: usize);
+}
+
+global_asm!(
+    "
+    .section .text
+    .global z_hosted_switch
+z_hosted_switch:
+    // rdi: where to save the current stack pointer
+    // rsi: stack pointer to switch to
+
+    // 1. Save the callee saved registers and the stack pointer
+    push rbp
+    push rbx
+    push r12
+    push r13
+    push r14
+    push r15
+    mov [rdi], rsp
+
+    // 2. Restore the registers saved on the other stack
+    mov rsp, rsi
+    pop r15
+    pop r14
+    pop r13
+    pop r12
+    pop rbx
+    pop rbp
+
+    // 3. Return where the other side switched, or to z_hosted_thread_start
+    ret
+
+z_hosted_thread_start:
+    // First switch to a thread: r12 holds its entry point, r13 its argument
+    mov rdi, r12
+    mov rsi, r13
+    call {thread_start}
+    ud2
+    ",
+    thread_start = sym thread_start,
+);
+
+extern "C" {
+    fn z_hosted_thread_start();
+}
+
+extern "C" fn thread_start(entry: ThreadEntry, arg0: *mut c_void) -> ! {
+    // The kernel masked the tick signal
+    systick::unmask_tick();
+
+    entry(arg0)
+}
+
+/// Prepares the stack of a thread for its first switch: `z_hosted_switch`
+/// returns to `z_hosted_thread_start` with the registers holding the thread
+/// entry point.
+unsafe fn initial_rsp(stack_ptr: *mut u32) -> usize {
+    let frame = &*(stack_ptr as *const HostedFrame);
+
+    // Stack 16 bytes aligned when z_hosted_thread_start calls thread_start
+    let top = (stack_ptr as usize & !0xF) - 8;
+    let stack = top as *mut usize;
+
+    stack.write(z_hosted_thread_start as usize);
+
+    // Registers popped by z_hosted_switch: r15, r14, r13, r12, rbx, rbp
+    let regs = stack.sub(6);
+    for (i, reg) in [0, 0, frame.arg0, frame.entry, 0, 0]
+        .into_iter()
+        .enumerate()
+    {
+        regs.add(i).write(reg);
+    }
+
+    regs as usize
+}
+
+/// Saves the running thread and switches to the kernel, returns once the
+/// kernel switches to the thread again.
+unsafe fn leave_thread(frame: *mut HostedFrame) {
+    FRAME = frame as *mut u32;
+
+    z_hosted_switch(addr_of_mut!((*CURRENT).rsp), KERNEL_RSP);
+}
+
+/// Calls a syscall from the running thread, returns the registers r0 and r1
+/// written by the kernel.
+///
+/// # Safety
+///
+/// Must be called from a thread, the arguments must be valid for the syscall.
+pub unsafe fn syscall(svc: u8, r0: u32, r1: u32, r2: u32, r3: u32) -> (u32, u32) {
+    systick::mask_tick();
+    IN_THREAD.store(false, Ordering::SeqCst);
+
+    let mut frame = HostedFrame {
+        r0,
+        r1,
+        r2,
+        r3,
+        svc: svc as u32,
+        ..Default::default()
+    };
+    ptr::write_volatile(addr_of_mut!(Z_SYSCALL_FLAG), 1);
+
+    leave_thread(&mut frame);
+
+    systick::unmask_tick();
+
+    (ptr::read_volatile(&frame.r0), ptr::read_volatile(&frame.r1))
+}
+
+/// Preempts the running thread, called by the tick signal handler.
+///
+/// # Safety
+///
+/// Must be called with the tick signal masked.
+pub(super) unsafe fn interrupt() {
+    if IN_THREAD.swap(false, Ordering::SeqCst) {
+        let mut frame = HostedFrame::default();
+
+        // The tick signal is unmasked when the handler returns
+        leave_thread(&mut frame);
+    }
+}
+
+pub struct Hosted;
+
+impl CpuVariant for Hosted {
+    // Cycles are emulated at the frequency of the Cortex-M
+    const FCPU: u32 = 25_000_000;
+
+    type CalleeContext = HostedContext;
+    type InitStackFrame = HostedFrame;
+
+    #[cfg(feature = "kernel-loadable-pie")]
+    type PICRegImpl = HostedPICReg;
+
+    unsafe fn switch_to_user(
+        stack_ptr: *mut u32,
+        process_regs: *mut Self::CalleeContext,
+    ) -> *mut u32 {
+        if (*process_regs).rsp == 0 {
+            (*process_regs).rsp = initial_rsp(stack_ptr);
+        }
+
+        CURRENT = process_regs;
+
+        // The tick signal is unmasked by the thread once switched to it
+        IN_THREAD.store(true, Ordering::SeqCst);
+
+        z_hosted_switch(addr_of_mut!(KERNEL_RSP), (*process_regs).rsp);
+
+        FRAME
+    }
+}
+
+/// Loadable ELF are Arm executables, they cannot run on the host: the kernel
+/// rejects them at load time (`LoadError::NotSupported`).
+#[cfg(feature = "kernel-loadable-pie")]
+#[derive(Debug)]
+pub struct HostedPICReg;
+
+#[cfg(feature = "kernel-loadable-pie")]
+impl PICRegImpl for HostedPICReg {
+    unsafe fn invoke_loadable_entry(_lex: &Lex) -> u32 {
+        panic!("loadable ELF are not supported by the hosted port")
+    }
+}
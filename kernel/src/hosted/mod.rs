@@ -0,0 +1,53 @@
+//! Hosted port, running the kernel as a Linux process to develop and debug
+//! applications with the host tools (gdb, valgrind ...), without QEMU.
+//!
+//! - Threads run on their own stack, the kernel switches to them with a
+//!   user-level context switch (`arch`).
+//! - SysTick is emulated with the monotonic clock, a `SIGALRM` interval timer
+//!   preempts the running thread at each tick (`systick`).
+//! - stdio is mapped to the terminal (`terminal`), semihosting requests (e.g.
+//!   `k_exit`) are handled by the host C library.
+//! - A reboot exits the process with `REBOOT_EXIT_STATUS`.
+//!
+//! The syscall ABI passes pointers in 32 bits registers, the executable must
+//! be loaded in the low 4 GiB: it is linked at a fixed address with
+//! `-C relocation-model=static` and `-no-pie` (see `.cargo/config.toml`).
+//!
+//! Loadable ELF and the MPU are not supported.
+
+#[cfg(not(all(target_arch = "x86_64", target_os = "linux")))]
+compile_error!("the hosted port only supports x86_64 Linux");
+
+#[cfg(feature = "kernel-mpu")]
+compile_error!("features `hosted` and `kernel-mpu` are mutually exclusive");
+
+pub mod arch;
+pub mod systick;
+pub mod terminal;
+
+/// Minimum stack size of the threads: the frame of the tick signal is pushed
+/// on the stack of the running thread.
+pub const MIN_STACK_SIZE: usize = 32768;
+
+/// Exit status of the process when a thread requests a reboot, so that the
+/// caller can tell it apart from an exit (`k_exit`) and start it again.
+pub const REBOOT_EXIT_STATUS: i32 = 128;
+
+/// Exits the process with the given status, the terminal mode is restored by
+/// the `atexit` handler.
+pub fn exit(status: i32) -> ! {
+    unsafe { libc::exit(status) }
+}
+
+/// Checks that the executable is loaded in the low 4 GiB, where pointers fit in
+/// the syscall registers.
+pub fn check_address_space() {
+    static MARKER: u8 = 0;
+
+    let addr = &MARKER as *const u8 as usize;
+    assert!(
+        addr <= u32::MAX as usize,
+        "hosted executable loaded at {:#x}, link it with `-C relocation-model=static -C link-arg=-no-pie`",
+        addr
+    );
+}
@@ -0,0 +1,143 @@
+//! SysTick emulation: the counter is derived from the monotonic clock and an
+//! interval timer raises `SIGALRM` at each period, which preempts the running
+//! thread like the SysTick interrupt.
+//!
+//! As on the hardware, COUNTFLAG is cleared when read and only records that at
+//! least one period elapsed, ticks are lost if the kernel does not read it
+//! during a whole period (e.g. the process is not scheduled by the host).
+
+use core::{cell::Cell, mem::MaybeUninit, ptr};
+
+use super::arch;
+
+/// Signal of the interval timer
+const TICK_SIGNAL: libc::c_int = libc::SIGALRM;
+
+const NS_PER_SEC: u64 = 1_000_000_000;
+
+pub struct SysTick<const FREQ_SYS_TICK: u32> {
+    /// Number of cycles of a period minus one, as the SysTick reload value
+    reload: u32,
+    /// Instant the counter started at, in nanoseconds of the monotonic clock
+    start_ns: u64,
+    /// Index of the period during which COUNTFLAG was last read
+    last_period: Cell<u64>,
+}
+
+impl<const FREQ_SYS_TICK: u32> SysTick<FREQ_SYS_TICK> {
+    const PERIOD_NS: u64 = NS_PER_SEC / FREQ_SYS_TICK as u64;
+
+    pub fn configure_period<const FCPU: u32>(interrupt: bool) -> SysTick<FREQ_SYS_TICK> {
+        // The tick signal is only unmasked while threads run
+        mask_tick();
+
+        let systick = SysTick {
+            reload: FCPU / FREQ_SYS_TICK,
+            start_ns: monotonic_ns(),
+            last_period: Cell::new(0),
+        };
+
+        if interrupt {
+            Self::start_timer();
+        }
+
+        systick
+    }
+
+    fn start_timer() {
+        let period = libc::timeval {
+            tv_sec: (Self::PERIOD_NS / NS_PER_SEC) as libc::time_t,
+            tv_usec: (Self::PERIOD_NS % NS_PER_SEC / 1000) as libc::suseconds_t,
+        };
+        let timer = libc::itimerval {
+            it_interval: period,
+            it_value: period,
+        };
+
+        unsafe {
+            let mut action: libc::sigaction = MaybeUninit::zeroed().assume_init();
+            action.sa_sigaction = on_tick as usize;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+
+            assert_eq!(libc::sigaction(TICK_SIGNAL, &action, ptr::null_mut()), 0);
+            assert_eq!(
+                libc::setitimer(libc::ITIMER_REAL, &timer, ptr::null_mut()),
+                0
+            );
+        }
+    }
+
+    /// Index of the current period and number of cycles elapsed in it.
+    fn position(&self) -> (u64, u32) {
+        let elapsed = monotonic_ns() - self.start_ns;
+        let cycles =
+            (elapsed % Self::PERIOD_NS) * self.get_period_cycles() as u64 / Self::PERIOD_NS;
+
+        (elapsed / Self::PERIOD_NS, cycles as u32)
+    }
+
+    pub fn get_reload_value(&self) -> u32 {
+        self.reload
+    }
+
+    pub fn get_current_value(&self) -> u32 {
+        self.reload - self.position().1
+    }
+
+    /// Number of cycles elapsed since the beginning of the current period.
+    pub fn get_elapsed_cycles(&self) -> u32 {
+        self.get_reload_value() - self.get_current_value()
+    }
+
+    /// Number of cycles in a period (the counter counts from the reload value down to 0).
+    pub fn get_period_cycles(&self) -> u32 {
+        self.get_reload_value() + 1
+    }
+
+    pub fn get_countflag(&self) -> bool {
+        let (period, _) = self.position();
+
+        self.last_period.replace(period) != period
+    }
+}
+
+fn monotonic_ns() -> u64 {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut ts) };
+
+    ts.tv_sec as u64 * NS_PER_SEC + ts.tv_nsec as u64
+}
+
+extern "C" fn on_tick(_signal: libc::c_int) {
+    // The signal is masked while its handler runs
+    unsafe { arch::interrupt() };
+}
+
+fn set_tick_mask(how: libc::c_int) {
+    unsafe {
+        let mut set = MaybeUninit::<libc::sigset_t>::uninit();
+        libc::sigemptyset(set.as_mut_ptr());
+        libc::sigaddset(set.as_mut_ptr(), TICK_SIGNAL);
+
+        libc::sigprocmask(how, set.as_ptr(), ptr::null_mut());
+    }
+}
+
+/// Defers the tick signal, like disabling the SysTick interrupt.
+pub fn mask_tick() {
+    set_tick_mask(libc::SIG_BLOCK);
+}
+
+/// Delivers the tick signal, a pending one is delivered immediately.
+pub fn unmask_tick() {
+    set_tick_mask(libc::SIG_UNBLOCK);
+}
+
+/// Waits for the tick signal, with the tick signal unmasked.
+pub fn wait_for_tick() {
+    unsafe { libc::pause() };
+}
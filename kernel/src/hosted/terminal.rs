@@ -0,0 +1,75 @@
+//! Terminal of the hosted process, usable as the stdio backend.
+//!
+//! When stdin is a terminal, it is switched to non canonical mode without echo,
+//! like a serial line: characters are read as they are typed, the shell echoes
+//! them. The original mode is restored when the process exits.
+
+use core::mem::MaybeUninit;
+
+use crate::serial::{SerialConfig, SerialTrait};
+
+/// Mode of the terminal before `init`
+static mut ORIGINAL_TERMIOS: MaybeUninit<libc::termios> = MaybeUninit::uninit();
+
+extern "C" fn restore_termios() {
+    unsafe { libc::tcsetattr(0, libc::TCSANOW, ORIGINAL_TERMIOS.as_ptr()) };
+}
+
+#[derive(Default)]
+pub struct Terminal;
+
+impl SerialTrait for Terminal {
+    fn init(&self, _config: &SerialConfig) {
+        unsafe {
+            if libc::isatty(0) == 0 {
+                return;
+            }
+
+            let mut termios = MaybeUninit::<libc::termios>::uninit();
+            if libc::tcgetattr(0, termios.as_mut_ptr()) != 0 {
+                return;
+            }
+            let mut termios = termios.assume_init();
+            ORIGINAL_TERMIOS.write(termios);
+
+            termios.c_lflag &= !(libc::ICANON | libc::ECHO);
+            termios.c_cc[libc::VMIN] = 0;
+            termios.c_cc[libc::VTIME] = 0;
+
+            if libc::tcsetattr(0, libc::TCSANOW, &termios) == 0 {
+                libc::atexit(restore_termios);
+            }
+        }
+    }
+
+    fn write_byte(&self, byte: u8) {
+        unsafe { libc::write(1, &byte as *const u8 as *const libc::c_void, 1) };
+    }
+
+    fn read(&self) -> Option<u8> {
+        let mut pollfd = libc::pollfd {
+            fd: 0,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let mut byte = 0u8;
+
+        unsafe {
+            if libc::poll(&mut pollfd, 1, 0) <= 0 {
+                return None;
+            }
+
+            match libc::read(0, &mut byte as *mut u8 as *mut libc::c_void, 1) {
+                1 => Some(byte),
+                _ => None,
+            }
+        }
+    }
+}
+
+impl core::fmt::Write for Terminal {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        unsafe { libc::write(1, s.as_ptr() as *const libc::c_void, s.len()) };
+        Ok(())
+    }
+}
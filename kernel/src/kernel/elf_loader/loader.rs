@@ -1,23 +1,95 @@
-use alloc::{alloc::Global, boxed::Box};
-use core::{ffi::c_void, marker::PhantomData, mem};
+use alloc::boxed::Box;
+use core::{ffi::c_void, marker::PhantomData, mem, mem::MaybeUninit, ptr::NonNull};
 use elf::{abi::{self, PF_R}, endian::LittleEndian, segment::ProgramHeader};
 
 use crate::{
     kernel::{
-        elf_loader::entry::Lex, kernel::Kernel, stack::Stack, thread::Thread, userspace, CpuVariant,
+        elf_loader::entry::Lex,
+        errno::{KResult, Kerr},
+        kernel::Kernel,
+        stack::Stack,
+        thread::{Thread, ThreadState},
+        userspace, CpuVariant,
     },
-    print, println,
+    mem::region::{Access, MemoryRegion},
+    print, println, user_println,
 };
 
 use super::{entry::PICRegImpl, section::SectionRef};
 
+#[cfg(feature = "kernel-noinit-canaries")]
 const NOINIT_CANARIES_VALUE: u8 = 0xAA;
 
+/// Stack size of the thread of a loaded ELF image.
+const LOADABLE_STACK_SIZE: usize = 8192;
+
+/// Size of the data (.got + .data + .bss + .noinit and entry context) of a
+/// loaded ELF image.
+const LOADABLE_DATA_SIZE: usize = 4096;
+
+/// Size of the heap a loaded thread allocates memory from.
+const LOADABLE_HEAP_SIZE: usize = 4096;
+
+/// Maximum number of threads loaded from ELF images at the same time.
+pub const MAX_LOADED_THREADS: usize = 2;
+
+/// Memory of a loaded thread. The block is aligned on its size and each part
+/// on its own size, so that the MPU covers each of them exactly.
+#[repr(C, align(16384))]
+struct LoadSlotMemory {
+    stack: Stack<LOADABLE_STACK_SIZE>,
+    data: [u8; LOADABLE_DATA_SIZE],
+    heap: [u8; LOADABLE_HEAP_SIZE],
+}
+
+/// Storage of the thread of a load slot.
+type ThreadStorage<'a, CPU> = MaybeUninit<Thread<'a, CPU>>;
+
+/// Slot a thread is loaded in.
+///
+/// The memory of the slot is allocated on its first use and reused once its
+/// thread is stopped, so that loading images repeatedly does not leak memory.
+pub struct LoadSlot<'a, CPU: CpuVariant> {
+    memory: Option<(NonNull<LoadSlotMemory>, NonNull<ThreadStorage<'a, CPU>>)>,
+    thread: Option<&'a Thread<'a, CPU>>,
+}
+
+impl<CPU: CpuVariant> LoadSlot<'_, CPU> {
+    pub const fn new() -> Self {
+        LoadSlot {
+            memory: None,
+            thread: None,
+        }
+    }
+}
+
+impl<CPU: CpuVariant> Default for LoadSlot<'_, CPU> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Maximum number of ELF images registered in the kernel.
+pub const MAX_ELF_IMAGES: usize = 4;
+
+/// ELF image which can be loaded by name (e.g. from a shell).
+#[derive(Debug, Clone, Copy)]
+pub struct ElfImage {
+    pub name: &'static str,
+    pub bytes: &'static [u8],
+}
+
 /// Enumeration of errors that may occur during the ELF loading process.
 #[derive(Debug)]
 pub enum LoadError {
     /// No memory available.
     NoMemory,
+    /// All the load slots are used by running threads.
+    NoSlot,
+    /// The data sections do not fit in the data of a load slot.
+    DataTooLarge,
+    /// ELF images cannot run on this port (hosted).
+    NotSupported,
     /// Error when data sections are non-contiguous.
     NonContiguousDataSections,
     /// Error when .rodata section is not contiguous with .text section.
@@ -284,10 +356,8 @@ impl<'elf, R: PICRegImpl> Loadable<'elf, R> {
             // 3. Branch and link to entry
             let r0 = R::invoke_loadable_entry(lex);
 
-            // don't reference to lex after having call ELF entr,
-            // lex might have be overwritten by the thread growing stack
-
-            println!("Loadable ELF returned: {:x}", r0);
+            // The thread runs unprivileged, print using syscalls
+            user_println!("Loadable ELF returned: {:x}", r0);
 
             userspace::k_stop();
         }
@@ -298,8 +368,9 @@ impl<'elf, R: PICRegImpl> Loadable<'elf, R> {
     }
 
     // Make stack size and priority configurable
-    pub fn create_thread<'a, CPU: CpuVariant>(
+    fn create_thread<'a, CPU: CpuVariant>(
         &self,
+        memory: &'a mut LoadSlotMemory,
         arg0: *mut c_void,
         priority: i8,
     ) -> Result<Thread<'a, CPU>, LoadError>
@@ -308,15 +379,16 @@ impl<'elf, R: PICRegImpl> Loadable<'elf, R> {
         // so the ELF file must remain valid for the lifetime of the thread.
         'a: 'elf,
     {
-        // Allocate the stack for the thread
-        let stack = Box::try_new(Stack::<8192>::uninit()).map_err(|_| LoadError::NoMemory)?;
-        let stack = Box::leak(stack);
-        let stack_info = stack.get_info();
-
-        // Allocate the data (.got + .data + .bss + .noinit)
-        let data = Box::<[u8]>::new_uninit_slice_in(self.alloc_size, Global);
-        let data = Box::leak(data);
-        let data_base_ptr = data.as_mut_ptr() as *mut u8;
+        let stack_info = memory.stack.get_info();
+
+        // The data (.got + .data + .bss + .noinit) is followed by the entry
+        // context, so that the thread can access both from its data memory
+        // region.
+        let lex_offset = self.alloc_size.next_multiple_of(8);
+        if lex_offset + mem::size_of::<Lex>() > LOADABLE_DATA_SIZE {
+            return Err(LoadError::DataTooLarge);
+        }
+        let data_base_ptr = memory.data.as_mut_ptr();
 
         // Initialize .got: copy from elf and patch each address in the .got section
         let got_section = self
@@ -380,9 +452,13 @@ impl<'elf, R: PICRegImpl> Loadable<'elf, R> {
             got_addr: data_base_ptr, // .got section is at the beginning of the allocated data section
         };
 
-        // Write the entry context at the start of the allocated stack to
-        // not allocate an additionnal buffer
-        let ptr = unsafe { stack_info.write_obj_at(0, lex) }.ok_or(LoadError::NoMemory)?;
+        // Write the entry context after the data, it cannot live in the thread
+        // stack as the stack is painted when the thread is created
+        let ptr = unsafe {
+            let ptr = data_base_ptr.add(lex_offset) as *mut Lex;
+            ptr.write(lex);
+            ptr
+        };
 
         // Create thread for loaded program
         let thread = Thread::init(
@@ -391,6 +467,31 @@ impl<'elf, R: PICRegImpl> Loadable<'elf, R> {
             ptr as *mut c_void,
             priority,
         );
+        thread.set_name("elf");
+
+        // Allow the thread to access its code, unless it lies in the flash
+        // which all threads can execute, and its data
+        let text = MemoryRegion::new(
+            self.text_paddr as usize,
+            self.seg0_size as usize,
+            Access::ReadOnly,
+            true,
+        );
+        if !MemoryRegion::flash().contains(text.start, text.size) {
+            thread.add_region(text).map_err(|_| LoadError::NoMemory)?;
+        }
+        let data = MemoryRegion::new(
+            data_base_ptr as usize,
+            LOADABLE_DATA_SIZE,
+            Access::ReadWrite,
+            false,
+        );
+        thread.add_region(data).map_err(|_| LoadError::NoMemory)?;
+
+        // Give the thread a heap
+        thread
+            .set_heap(&mut memory.heap)
+            .map_err(|_| LoadError::NoMemory)?;
 
         Ok(thread)
     }
@@ -399,24 +500,131 @@ impl<'elf, R: PICRegImpl> Loadable<'elf, R> {
 impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
     /// Load an ELF file of a PIE into memory and create a thread for it.
     ///
+    /// The thread is created in a free load slot, the slots of the loaded
+    /// threads which are stopped are freed first.
+    ///
     /// # Parameters
     /// - `bytes`: The ELF file bytes.
     ///
     /// # Returns
     /// - A reference to the created thread.
+    /// - `Err(LoadError::NoSlot)` if `MAX_LOADED_THREADS` loaded threads are
+    ///   not stopped.
+    /// - `Err(LoadError::NotSupported)` on the hosted port, which cannot run
+    ///   Arm code.
     /// - An error if the ELF file could not be loaded.
     pub fn load_elf(&mut self, bytes: &[u8]) -> Result<&'a Thread<'a, CPU>, LoadError> {
+        if cfg!(feature = "hosted") {
+            return Err(LoadError::NotSupported);
+        }
+
         let elf =
             elf::ElfBytes::<LittleEndian>::minimal_parse(bytes).map_err(LoadError::ParseElf)?;
 
         let loadable = Loadable::<CPU::PICRegImpl>::from(elf)?;
 
-        let thread = loadable.create_thread::<CPU>(core::ptr::null_mut(), 0)?;
-        let thread = Box::new(thread);
-        let thread = Box::leak(thread);
+        self.reclaim_load_slots();
+        let slot = self
+            .load_slots
+            .iter_mut()
+            .find(|slot| slot.thread.is_none())
+            .ok_or(LoadError::NoSlot)?;
+
+        let (memory, storage) = match slot.memory {
+            Some(memory) => memory,
+            None => {
+                let memory = Box::<LoadSlotMemory>::try_new_uninit().map_err(|_| LoadError::NoMemory)?;
+                let storage = Box::try_new_uninit().map_err(|_| LoadError::NoMemory)?;
+                let memory = (
+                    NonNull::from(Box::leak(memory)).cast(),
+                    NonNull::from(Box::leak(storage)),
+                );
+                slot.memory = Some(memory);
+                memory
+            }
+        };
+
+        // SAFETY: The memory of the slot is only used by the thread of the
+        // slot, which has been stopped and removed from the kernel. The stack
+        // is painted and the data and heap are initialized before being used,
+        // so their previous content does not matter.
+        let thread = loadable.create_thread::<CPU>(
+            unsafe { &mut *memory.as_ptr() },
+            core::ptr::null_mut(),
+            0,
+        )?;
+        let thread: &'a Thread<'a, CPU> = unsafe { (*storage.as_ptr()).write(thread) };
+        slot.thread = Some(thread);
 
         self.register_thread(thread);
 
         Ok(thread)
     }
+
+    /// Returns whether a thread has been loaded from an ELF image.
+    pub(crate) fn is_loaded_thread(&self, thread: &Thread<'a, CPU>) -> bool {
+        self.load_slots
+            .iter()
+            .any(|slot| slot.thread.is_some_and(|loaded| core::ptr::eq(loaded, thread)))
+    }
+
+    /// Frees the load slots whose thread is stopped, the thread is removed
+    /// from the kernel so that its memory can be reused.
+    fn reclaim_load_slots(&mut self) {
+        for slot in self.load_slots.iter_mut() {
+            if let Some(thread) = slot.thread {
+                if thread.state.get() == ThreadState::Stopped {
+                    self.tasks.remove(thread);
+                    slot.thread = None;
+                }
+            }
+        }
+    }
+
+    /// Registers an ELF image, so that it can be loaded by name.
+    ///
+    /// # Returns
+    /// - `Err(Kerr::ENOSPC)` if `MAX_ELF_IMAGES` images are already registered.
+    pub fn register_elf_image(&mut self, image: ElfImage) -> KResult<()> {
+        let slot = self
+            .images
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(Kerr::ENOSPC)?;
+        *slot = Some(image);
+
+        Ok(())
+    }
+
+    /// Loads a registered ELF image and starts its thread.
+    ///
+    /// # Returns
+    /// - The identifier of the created thread.
+    /// - `Err(Kerr::NoEntry)` if no image is registered with this name.
+    /// - `Err(Kerr::EBUSY)` if no load slot is free.
+    /// - `Err(Kerr::NoMemory)` if the memory of the thread could not be allocated.
+    /// - `Err(Kerr::NotSupported)` on the hosted port.
+    /// - `Err(Kerr::ENOEXEC)` if the image could not be loaded.
+    pub fn load_elf_image(&mut self, name: &str) -> KResult<u32> {
+        let image = self
+            .images
+            .iter()
+            .flatten()
+            .find(|image| image.name == name)
+            .copied()
+            .ok_or(Kerr::NoEntry)?;
+
+        match self.load_elf(image.bytes) {
+            Ok(thread) => Ok(thread.id()),
+            Err(err) => {
+                println!("Error loading elf {}: {:?}", image.name, err);
+                Err(match err {
+                    LoadError::NoSlot => Kerr::EBUSY,
+                    LoadError::NoMemory => Kerr::NoMemory,
+                    LoadError::NotSupported => Kerr::NotSupported,
+                    _ => Kerr::ENOEXEC,
+                })
+            }
+        }
+    }
 }
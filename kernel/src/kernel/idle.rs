@@ -1,28 +1,48 @@
+#[cfg(target_arch = "arm")]
+use core::arch::arm::__wfi;
 use core::{
-    arch::arm::__wfi,
     ffi::c_void,
     ptr::{self, addr_of_mut},
 };
 
 use super::{stack::Stack, thread::Thread, CpuVariant};
 
+#[cfg(not(feature = "hosted"))]
 pub const IDLE_STACK_SIZE: usize = 1024;
+#[cfg(feature = "hosted")]
+pub const IDLE_STACK_SIZE: usize = crate::hosted::MIN_STACK_SIZE;
+
+/// Idle thread stack, aligned on its size so that the MPU covers it exactly.
+#[cfg_attr(not(feature = "hosted"), repr(C, align(1024)))]
+struct IdleStack(Stack<IDLE_STACK_SIZE>);
 
 #[link_section = ".noinit"]
-static mut IDLE_STACK: Stack<IDLE_STACK_SIZE> = Stack::uninit();
+static mut IDLE_STACK: IdleStack = IdleStack(Stack::uninit());
 
 pub struct Idle;
 
 impl Idle {
     extern "C" fn idle_entry(_arg0: *mut c_void) -> ! {
         loop {
-            unsafe { __wfi() };
+            #[cfg(target_arch = "arm")]
+            unsafe {
+                __wfi()
+            };
+
+            #[cfg(feature = "hosted")]
+            crate::hosted::systick::wait_for_tick();
+
+            #[cfg(not(any(target_arch = "arm", feature = "hosted")))]
+            core::hint::spin_loop();
         }
     }
 
     pub fn init<'a, CPU: CpuVariant>() -> Thread<'a, CPU> {
-        let stack_info = unsafe { &mut *addr_of_mut!(IDLE_STACK) }.get_info();
+        let stack_info = unsafe { &mut (*addr_of_mut!(IDLE_STACK)).0 }.get_info();
+
+        let thread = Thread::init(&stack_info, Self::idle_entry, ptr::null_mut(), 0);
+        thread.set_name("idle");
 
-        Thread::init(&stack_info, Self::idle_entry, ptr::null_mut(), 0)
+        thread
     }
 }
@@ -4,16 +4,17 @@
 //! thread management, and synchronization primitives.
 
 use core::{
-    alloc::{self, Allocator},
+    alloc::{Allocator, Layout},
     ptr::{self, addr_of_mut, read_volatile, write_volatile, NonNull},
 };
 
 use ::alloc::{alloc::Global, boxed::Box};
 
 use crate::{
-    cortex_m::systick::SysTick,
+    cortex_m::fault::FaultInfo,
     kernel::{
-        errno::Kerr,
+        elf_loader::loader::{ElfImage, LoadSlot, MAX_ELF_IMAGES, MAX_LOADED_THREADS},
+        errno::{KResult, Kerr},
         idle::Idle,
         sync::{
             KernelObject, KernelObjectTrait, Mutex, Semaphore, Signal, SignalValue, SwapData, Sync,
@@ -23,19 +24,56 @@ use crate::{
             IoSyscall, KernelSyscall, SVCCallParams, SyncPrimitiveCreate, SyncPrimitiveType,
             Syscall,
         },
+        sysinfo::{KobjInfo, MemoryInfo, SysInfoQuery, VersionInfo},
         thread::{PendingContext, Runqueue, Thread, ThreadState},
         timeout::{Timeout, TimeoutInstant},
-        CpuVariant,
+        workqueue::Work,
+        CpuVariant, ExceptionStackFrame,
     },
     list::singly_linked as sl,
+    mem::{
+        alloc::KERNEL_ALLOCATOR,
+        region::{Access, MemoryRegion, MAX_THREAD_REGIONS},
+    },
     println, stdio,
 };
 
-#[cfg(feature = "kernel-debug")]
-use crate::println;
+#[cfg(not(feature = "hosted"))]
+use crate::cortex_m::{
+    irq,
+    nvic::{InterruptNumber, NVIC},
+    scb::SCB,
+    systick::SysTick,
+};
+#[cfg(feature = "hosted")]
+use crate::hosted::{self, systick::SysTick};
+
+#[cfg(feature = "kernel-exit")]
+use crate::cortex_m::semihosting;
+
+#[cfg(all(feature = "kernel-mpu", not(feature = "cm33")))]
+use crate::cortex_m::mpu::{Mpu, MpuRegion};
+#[cfg(all(feature = "kernel-mpu", feature = "cm33"))]
+use crate::cortex_m::mpu_v8m::{Mpu, MpuRegion};
 
 use super::sync::AcquireOutcome;
 
+#[cfg(feature = "kernel-stats")]
+use super::stats::{split_cycles, KernelStats, KernelStatsInfo, KobjStatsInfo, StatsQuery};
+
+#[cfg(feature = "kernel-trace")]
+use super::trace::{self, TraceEvent};
+
+#[cfg(feature = "kernel-workqueue")]
+use super::workqueue::WorkQueue;
+
+/// Maximum number of device interrupt handlers registered at the same time.
+pub const MAX_IRQ_HANDLERS: usize = 8;
+
+/// Handler of a device interrupt, called by the kernel loop (kernel context)
+/// with the interrupt number.
+pub type IrqHandler<'a, CPU, const K: usize, const F: u32> = fn(&mut Kernel<'a, CPU, K, F>, u16);
+
 pub const USER_MALLOC_DEFAULT_ALIGN: usize = 4;
 pub const USER_MALLOC_MIN_ALIGN: usize = 2;
 
@@ -46,8 +84,14 @@ pub const USER_MALLOC_MIN_ALIGN: usize = 2;
 pub enum SupervisorCallReason {
     /// A syscall was invoked by the user process.
     Syscall(SVCCallParams),
-    /// An interrupt occurred.
-    Interrupted,
+    /// An interrupt occurred, holding the number of the device interrupt
+    /// (`None` for the SysTick).
+    Interrupted(Option<u16>),
+    /// The user process overflowed its stack.
+    StackOverflow,
+    /// The user process raised a fault (e.g. MPU violation, bus error,
+    /// undefined instruction), holding the fault exception number.
+    Fault(u32),
 }
 
 /// The result of the scheduler's decision on which process to run next.
@@ -63,6 +107,9 @@ pub enum SyscallOutcome {
     // TODO allow to return a pointer ??
     /// Syscall completed immediately with the given return value.
     Completed(i32),
+    /// Syscall completed immediately with the given 64 bits return value,
+    /// the low word is returned in r0 and the high word in r1.
+    Completed64(u64),
     /// Syscall made the thread pending and is waiting for a signal to complete.
     Pending,
     // /// 2 bytes align raw pointer
@@ -87,7 +134,44 @@ impl From<Option<i32>> for SyscallOutcome {
 /// This address must be accessible from assembly code.
 #[used]
 #[no_mangle]
-static mut Z_SYSCALL_FLAG: u32 = 0;
+pub(crate) static mut Z_SYSCALL_FLAG: u32 = 0;
+
+/// A flag used to indicate if a user process triggered a fault, it holds the
+/// exception number of the fault.
+///
+/// This address must be accessible from assembly code.
+#[used]
+#[no_mangle]
+static mut Z_FAULT_FLAG: u32 = 0;
+
+/// A flag used to indicate if a user process has been interrupted by a device
+/// interrupt, it holds the exception number of the interrupt.
+///
+/// This address must be accessible from assembly code.
+#[used]
+#[no_mangle]
+static mut Z_IRQ_FLAG: u32 = 0;
+
+/// Snapshot of the kernel state, reported by the panic handler which cannot
+/// reach the kernel structure.
+#[derive(Clone, Copy, Debug)]
+pub struct KernelState {
+    /// Thread the kernel switched to last, null before the first switch.
+    pub current_thread: *const (),
+    /// System tick counter.
+    pub ticks: u64,
+}
+
+impl KernelState {
+    pub fn snapshot() -> Self {
+        unsafe { read_volatile(&*addr_of_mut!(Z_KERNEL_STATE)) }
+    }
+}
+
+static mut Z_KERNEL_STATE: KernelState = KernelState {
+    current_thread: ptr::null(),
+    ticks: 0,
+};
 
 /// The core kernel structure that manages threads, scheduling, and synchronization.
 ///
@@ -99,7 +183,7 @@ static mut Z_SYSCALL_FLAG: u32 = 0;
 /// * `F` - The frequency of the system tick (SysTick) in Hz.
 pub struct Kernel<'a, CPU: CpuVariant, const K: usize, const F: u32> {
     /// The list of tasks (threads) managed by the kernel.
-    tasks: sl::List<'a, Thread<'a, CPU>, Runqueue>,
+    pub(super) tasks: sl::List<'a, Thread<'a, CPU>, Runqueue>,
 
     /// The system tick timer.
     systick: SysTick<F>,
@@ -107,12 +191,37 @@ pub struct Kernel<'a, CPU: CpuVariant, const K: usize, const F: u32> {
     /// The system tick counter.
     ticks: u64,
 
+    /// A SysTick period elapsed (COUNTFLAG consumed while reading the clock)
+    /// but the tick has not been accounted yet by `handle_interrupts`.
+    tick_pending: bool,
+
     /// The idle thread.
     idle: Thread<'a, CPU>,
 
+    /// Identifier assigned to the next registered thread.
+    next_thread_id: u32,
+
+    /// ELF images which can be loaded by name.
+    pub(super) images: [Option<ElfImage>; MAX_ELF_IMAGES],
+
+    /// Slots the threads loaded from ELF images are created in.
+    pub(super) load_slots: [LoadSlot<'a, CPU>; MAX_LOADED_THREADS],
+
+    /// Handlers of the device interrupts, with their interrupt number.
+    #[cfg(not(feature = "hosted"))]
+    irq_handlers: [Option<(u16, IrqHandler<'a, CPU, K, F>)>; MAX_IRQ_HANDLERS],
+
     /// The array of kernel objects (synchronization primitives).
     /// use heapless primitives to avoid heap allocation
     kobj: [Option<Box<dyn KernelObjectTrait<'a, CPU> + 'a>>; K],
+
+    /// The work queue for deferred work.
+    #[cfg(feature = "kernel-workqueue")]
+    workq: WorkQueue<'a, CPU>,
+
+    /// Kernel-wide statistics.
+    #[cfg(feature = "kernel-stats")]
+    stats: KernelStats<K>,
 }
 
 impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
@@ -128,13 +237,200 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
     pub fn init(systick: SysTick<F>) -> Kernel<'a, CPU, K, F> {
         let idle = Idle::init();
 
-        Kernel {
+        // The idle thread is the first thread
+        idle.set_id(1);
+
+        #[allow(unused_mut)]
+        let mut kernel = Kernel {
             tasks: sl::List::empty(),
             systick,
             ticks: 0,
+            tick_pending: false,
             idle,
+            next_thread_id: 2,
+            images: [None; MAX_ELF_IMAGES],
+            load_slots: [const { LoadSlot::new() }; MAX_LOADED_THREADS],
+            #[cfg(not(feature = "hosted"))]
+            irq_handlers: [None; MAX_IRQ_HANDLERS],
             kobj: [const { None }; K],
+            #[cfg(feature = "kernel-workqueue")]
+            workq: WorkQueue::new(),
+            #[cfg(feature = "kernel-stats")]
+            stats: KernelStats::new(),
+        };
+
+        #[cfg(feature = "kernel-workqueue")]
+        kernel.workq_init();
+
+        #[cfg(not(feature = "hosted"))]
+        SCB::instance().enable_faults();
+
+        #[cfg(all(target_arch = "arm", not(feature = "cm0")))]
+        irq::relocate_vector_table();
+
+        #[cfg(feature = "kernel-mpu")]
+        Self::mpu_init();
+
+        kernel
+    }
+
+    /// Enables the MPU, user threads are then only allowed to access the flash,
+    /// their stack and their memory regions.
+    #[cfg(feature = "kernel-mpu")]
+    fn mpu_init() {
+        Mpu::instance().init(&MemoryRegion::flash());
+    }
+
+    /// Checks that a buffer passed by a thread to a syscall lies within memory
+    /// the thread owns: its stack and its memory regions, or the flash for
+    /// read-only accesses.
+    ///
+    /// # Arguments
+    ///
+    /// * `thread` - The thread which invoked the syscall.
+    /// * `ptr` - The start address of the buffer.
+    /// * `len` - The size of the buffer in bytes.
+    /// * `access` - The access the kernel performs on the buffer.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the thread owns the buffer.
+    /// * `Err(Kerr::EFAULT)` otherwise.
+    fn check_user_buffer<T>(
+        thread: &Thread<'a, CPU>,
+        ptr: *const T,
+        len: usize,
+        access: Access,
+    ) -> KResult<()> {
+        let addr = ptr as usize;
+
+        // Empty slices (e.g. "") have a dangling pointer, which may lie outside
+        // of any region
+        if (len == 0 && !ptr.is_null())
+            || thread.owns_memory(addr, len, access)
+            || MemoryRegion::flash().allows(addr, len, access)
+        {
+            Ok(())
+        } else {
+            Err(Kerr::EFAULT)
+        }
+    }
+
+    /// Copies a structure to the memory of a thread.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the structure has been written.
+    /// * `Err(Kerr::InvalidArguments)` if `len` does not match the structure size.
+    /// * `Err(Kerr::EFAULT)` if the thread does not own the buffer.
+    unsafe fn copy_to_user<T>(
+        thread: &Thread<'a, CPU>,
+        ptr: *mut u8,
+        len: usize,
+        value: T,
+    ) -> KResult<()> {
+        if len != size_of::<T>() {
+            return Err(Kerr::InvalidArguments);
+        }
+        Self::check_user_buffer(thread, ptr, len, Access::ReadWrite)?;
+
+        ptr::write_unaligned(ptr as *mut T, value);
+        Ok(())
+    }
+
+    /// Copies items to an array in the memory of a thread, items which do not
+    /// fit in the array are dropped.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(count)` with the number of items written.
+    /// * `Err(Kerr::EFAULT)` if the thread does not own the buffer.
+    unsafe fn copy_array_to_user<T>(
+        thread: &Thread<'a, CPU>,
+        ptr: *mut u8,
+        len: usize,
+        items: impl Iterator<Item = T>,
+    ) -> KResult<usize> {
+        Self::check_user_buffer(thread, ptr, len, Access::ReadWrite)?;
+
+        let array = ptr as *mut T;
+        let capacity = len / size_of::<T>();
+        let mut count = 0;
+
+        for item in items.take(capacity) {
+            ptr::write_unaligned(array.add(count), item);
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
+    /// Checks that a code address passed by a thread to a syscall lies within
+    /// the flash or one of the thread executable memory regions.
+    fn is_user_code<T>(thread: &Thread<'a, CPU>, ptr: *const T) -> bool {
+        let addr = ptr as usize;
+
+        thread.can_execute(addr) || MemoryRegion::flash().contains(addr, 1)
+    }
+
+    /// Checks whether a thread shares the execution context of the work queue
+    /// thread, which runs the submitted handlers with its own `r9` and the
+    /// memory regions of the submitting thread.
+    ///
+    /// Loaded ELF images don't, their `r9` points to their GOT.
+    fn shares_workq_context(&self, thread: &Thread<'a, CPU>) -> bool {
+        !self.is_loaded_thread(thread)
+    }
+
+    /// Allocates zeroed memory owned by a thread.
+    ///
+    /// The memory is allocated from the heap of the thread if it has one, which
+    /// is already one of its memory regions. Otherwise it is allocated from the
+    /// kernel heap and becomes one of its memory regions, with the MPU it is a
+    /// power of two block aligned on its size so that a region covers it exactly.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ptr)` to the allocated memory.
+    /// * `Err(Kerr::NoMemory)` if the heap of the thread or the kernel heap is exhausted.
+    /// * Errors of `Thread::add_region` if the block cannot be mapped, it is freed then.
+    fn user_alloc(thread: &Thread<'a, CPU>, size: usize, align: usize) -> KResult<*mut u8> {
+        if thread.has_heap() {
+            return thread.heap_alloc(size, align);
+        }
+
+        let (size, align) = if cfg!(feature = "kernel-mpu") {
+            let block = size.next_power_of_two().max(32);
+            (block, block.max(align))
+        } else {
+            (size, align)
+        };
+        let layout = Layout::from_size_align(size, align).map_err(|_| Kerr::InvalidArguments)?;
+        let ptr = Global
+            .allocate_zeroed(layout)
+            .map_err(|_| Kerr::NoMemory)?
+            .cast::<u8>();
+
+        let region = MemoryRegion::new(ptr.as_ptr() as usize, size, Access::ReadWrite, false);
+        if let Err(err) = thread.add_region(region) {
+            unsafe { Global.deallocate(ptr, layout) };
+            return Err(err);
         }
+
+        Ok(ptr.as_ptr())
+    }
+
+    /// Creates the work queue thread and the kernel object it pends on when
+    /// no work is ready.
+    #[cfg(feature = "kernel-workqueue")]
+    fn workq_init(&mut self) {
+        // The last kernel object is reserved, so that the identifiers of the
+        // kernel objects created by the threads start from 0
+        self.workq.kobj = self.kobj_create_at(K - 1, Sync);
+
+        let thread = Box::leak(Box::new(WorkQueue::init_thread()));
+        self.workq.set_thread(thread);
+        self.register_thread(thread);
     }
 
     /// Converts milliseconds to system ticks based on the system tick frequency.
@@ -150,12 +446,66 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
         ms as u64 * F as u64 / 1000
     }
 
+    /// Computes the instant (in ticks) at which a timeout expires.
+    ///
+    /// Absolute timeouts are resolved relatively to the current time, if the
+    /// deadline has already been reached, the zero instant is returned so that
+    /// the caller does not wait.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The timeout to resolve.
+    ///
+    /// # Returns
+    ///
+    /// The `TimeoutInstant` at which the timeout expires.
+    pub fn timeout_instant(&mut self, timeout: Timeout) -> TimeoutInstant {
+        let now = self.get_ticks();
+
+        let at = match timeout {
+            Timeout::Forever => return TimeoutInstant::new_never(),
+            Timeout::Duration(ms) => return TimeoutInstant::new_at(now + Self::ms_to_ticks(ms)),
+            Timeout::DurationUs(us) => {
+                // Round up to the first tick after the deadline, taking into account
+                // the time already elapsed in the current tick period
+                let deadline_us = self.clock_us() + us as u64;
+                return TimeoutInstant::new_at((deadline_us * F as u64).div_ceil(1_000_000));
+            }
+            Timeout::AtTicks(ticks) => TimeoutInstant::extend(now, ticks),
+            Timeout::AtMs(ms) => {
+                let ms = TimeoutInstant::extend(Self::ticks_to_ms(now), ms);
+                // Round up, so that the thread never wakes up before the deadline
+                (ms * F as u64).div_ceil(1000)
+            }
+        };
+
+        if at <= now {
+            TimeoutInstant::new_at(0)
+        } else {
+            TimeoutInstant::new_at(at)
+        }
+    }
+
     /// Registers a new thread with the kernel and marks it as ready to run.
     ///
     /// # Arguments
     ///
     /// * `thread` - A reference to the thread to register.
+    ///
+    /// # Panics
+    ///
+    /// With the MPU enabled, if the thread stack cannot be covered exactly by
+    /// an MPU region (it must typically be aligned on its size).
     pub fn register_thread(&mut self, thread: &'a Thread<'a, CPU>) {
+        #[cfg(feature = "kernel-mpu")]
+        assert!(
+            MpuRegion::encode(&thread.stack_region()).is_ok(),
+            "thread stack not aligned for the MPU"
+        );
+
+        thread.set_id(self.next_thread_id);
+        self.next_thread_id += 1;
+
         self.tasks.push_front(thread);
         thread.state.set(super::thread::ThreadState::Running);
     }
@@ -163,6 +513,20 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
     /// Increments the system tick counter by one.
     fn increment_ticks(&mut self) {
         self.ticks += 1;
+
+        #[cfg(feature = "kernel-trace")]
+        self.trace(TraceEvent::Tick, self.ticks as u32);
+
+        #[cfg(feature = "kernel-stats")]
+        for thread in self
+            .tasks
+            .iter()
+            .filter(|thread| matches!(thread.state.get(), ThreadState::Pending(_)))
+        {
+            thread.stats.account_pending_tick();
+        }
+
+        unsafe { (*addr_of_mut!(Z_KERNEL_STATE)).ticks = self.ticks };
     }
 
     /// Retrieves the current value of the system tick counter.
@@ -174,6 +538,115 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
         self.ticks
     }
 
+    /// Converts system ticks to milliseconds based on the system tick frequency.
+    ///
+    /// # Arguments
+    ///
+    /// * `ticks` - The number of system ticks.
+    ///
+    /// # Returns
+    ///
+    /// The equivalent duration in milliseconds.
+    pub fn ticks_to_ms(ticks: u64) -> u64 {
+        ticks * 1000 / F as u64
+    }
+
+    /// Retrieves the time elapsed since the kernel started.
+    ///
+    /// # Returns
+    ///
+    /// The uptime in milliseconds.
+    pub fn get_uptime_ms(&self) -> u64 {
+        Self::ticks_to_ms(self.ticks)
+    }
+
+    /// Takes a consistent snapshot of the system tick counter and of the SysTick
+    /// timer current value.
+    ///
+    /// The SysTick timer may wrap while the clock is read, before the system tick
+    /// counter is incremented by `handle_interrupts`. The wrap is detected with the
+    /// COUNTFLAG bit, as reading it clears it, the tick is recorded as pending so
+    /// that it is accounted for on the next call to `handle_interrupts`.
+    ///
+    /// # Returns
+    ///
+    /// A tuple with the number of system ticks elapsed and the number of cycles
+    /// elapsed in the current tick period.
+    fn clock_snapshot(&mut self) -> (u64, u32) {
+        let before = self.systick.get_current_value();
+        let wrapped = self.systick.get_countflag();
+        let after = self.systick.get_current_value();
+
+        if wrapped {
+            self.tick_pending = true;
+        }
+
+        // The counter counts down, if it increased the timer wrapped after
+        // COUNTFLAG was read, the flag remains set for handle_interrupts.
+        let unaccounted = self.tick_pending || after > before;
+
+        (
+            self.ticks + unaccounted as u64,
+            self.systick.get_reload_value() - after,
+        )
+    }
+
+    /// Retrieves the number of CPU cycles elapsed since the kernel started,
+    /// derived from the system tick counter and the SysTick timer current value.
+    ///
+    /// # Returns
+    ///
+    /// The number of CPU cycles elapsed since the kernel started.
+    pub fn clock_cycles(&mut self) -> u64 {
+        let (ticks, cycles) = self.clock_snapshot();
+
+        ticks * self.systick.get_period_cycles() as u64 + cycles as u64
+    }
+
+    /// Retrieves the time elapsed since the kernel started with a microsecond
+    /// resolution.
+    ///
+    /// # Returns
+    ///
+    /// The uptime in microseconds.
+    pub fn clock_us(&mut self) -> u64 {
+        let (ticks, cycles) = self.clock_snapshot();
+
+        ticks * 1_000_000 / F as u64
+            + cycles as u64 * 1_000_000 / (F as u64 * self.systick.get_period_cycles() as u64)
+    }
+
+    /// Retrieves the value of the hardware cycle counter.
+    ///
+    /// # Returns
+    ///
+    /// The number of CPU cycles elapsed since the kernel started, wrapping around
+    /// on 32 bits.
+    pub fn get_cycles(&mut self) -> u32 {
+        self.clock_cycles() as u32
+    }
+
+    /// Records an event in the kernel trace buffer, timestamped with the cycle counter.
+    #[cfg(feature = "kernel-trace")]
+    fn trace(&mut self, event: TraceEvent, arg: u32) {
+        let timestamp = self.get_cycles();
+
+        trace::record(timestamp, event, arg);
+    }
+
+    /// Argument of the `Interrupt` trace event: the exception number of the
+    /// device interrupt, 0 for the SysTick.
+    #[cfg(feature = "kernel-trace")]
+    fn irq_trace_arg(irqn: Option<u16>) -> u32 {
+        irqn.map_or(0, |irqn| irqn as u32 + 16)
+    }
+
+    /// Writes the kernel trace buffer to the stdio UART, then empties it.
+    #[cfg(feature = "kernel-trace")]
+    pub fn trace_dump(&self) {
+        trace::dump(self.systick.get_period_cycles() * F);
+    }
+
     /// The main kernel loop that handles scheduling and dispatching threads.
     ///
     /// This function selects the next thread to run, switches context to it, and handles any
@@ -185,37 +658,142 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
         match scheduler_verdict {
             // Switch to chosen user process
             // when returning from user process, we need to handle various events
-            SchedulerVerdict::RunProcess(process) => match Self::switch_to_process(process) {
+            SchedulerVerdict::RunProcess(process) => match self.run_process(process) {
                 SupervisorCallReason::Syscall(syscall_params) => unsafe {
                     #[cfg(feature = "kernel-debug")]
                     println!("process: {:#x?}", process.context.get());
 
+                    #[cfg(feature = "kernel-stats")]
+                    self.stats
+                        .count_syscall(syscall_params.syscall_id, syscall_params.r3);
+
+                    #[cfg(feature = "kernel-trace")]
+                    self.trace(
+                        TraceEvent::SyscallEntry,
+                        syscall_params.syscall_id as u32 | syscall_params.r3 << 8,
+                    );
+
                     let ret = if let Some(syscall) = Syscall::from_svc_params(syscall_params) {
                         self.do_syscall(process, syscall)
                     } else {
                         SyscallOutcome::Completed(Kerr::NoSuchSyscall as i32)
                     };
 
+                    #[cfg(feature = "kernel-trace")]
+                    match ret {
+                        SyscallOutcome::Completed(result) => {
+                            self.trace(TraceEvent::SyscallExit, result as u32)
+                        }
+                        SyscallOutcome::Completed64(result) => {
+                            self.trace(TraceEvent::SyscallExit, result as u32)
+                        }
+                        SyscallOutcome::Pending => self.trace(TraceEvent::SyscallPending, 0),
+                    }
+
                     // Syscall completed, return value in user process stack in r0 register
-                    if let SyscallOutcome::Completed(result) = ret {
-                        process.set_syscall_return_value_unchecked(result);
+                    // (and r1 register for 64 bits values)
+                    match ret {
+                        SyscallOutcome::Completed(result) => {
+                            process.set_syscall_return_value_unchecked(result)
+                        }
+                        SyscallOutcome::Completed64(result) => {
+                            process.set_syscall_return_value64_unchecked(result)
+                        }
+                        SyscallOutcome::Pending => {}
                     }
+
+                    // Call the handlers of the device interrupts taken while the
+                    // thread or the kernel was running, as on the other returns
+                    #[cfg(not(feature = "hosted"))]
+                    self.dispatch_irqs();
                 },
-                SupervisorCallReason::Interrupted => {
+                SupervisorCallReason::Interrupted(_irqn) => {
+                    #[cfg(feature = "kernel-trace")]
+                    self.trace(TraceEvent::Interrupt, Self::irq_trace_arg(_irqn));
+
                     self.handle_interrupts();
 
                     // TODO: If current thread is cooperative, we must return to it
                 }
+                SupervisorCallReason::StackOverflow => {
+                    // Kill the thread before it corrupts anything else
+                    println!(
+                        "thread {:p} stack overflow (sp: {:p}, stack: {:p}, size: {}), killed",
+                        process,
+                        process.stack_ptr.get(),
+                        process.stack.stack_start(),
+                        process.stack_size(),
+                    );
+
+                    self.stop_thread(process);
+
+                    self.handle_interrupts();
+                }
+                SupervisorCallReason::Fault(exception) => {
+                    let info = FaultInfo::read(exception);
+
+                    println!("thread {:p} killed by fault", process);
+                    info.report(process.stack_ptr.get());
+
+                    // Clear fault status bits, so that next faults are reported accurately
+                    info.clear();
+
+                    self.stop_thread(process);
+
+                    self.handle_interrupts();
+                }
             },
 
-            SchedulerVerdict::Idle => match Self::switch_to_process(&self.idle) {
-                SupervisorCallReason::Interrupted => self.handle_interrupts(),
+            SchedulerVerdict::Idle => match self.run_idle() {
+                SupervisorCallReason::Interrupted(_irqn) => {
+                    #[cfg(feature = "kernel-trace")]
+                    self.trace(TraceEvent::Interrupt, Self::irq_trace_arg(_irqn));
+
+                    self.handle_interrupts()
+                }
+                SupervisorCallReason::StackOverflow => panic!("IDLE stack overflow"),
+                SupervisorCallReason::Fault(_) => panic!("IDLE fault"),
                 // Idle thread should never use syscalls
                 _ => panic!("IDLE fired syscall"),
             },
         };
     }
 
+    /// Switches to the given thread, accounting the CPU time it used.
+    fn run_process(&mut self, process: &'a Thread<'a, CPU>) -> SupervisorCallReason {
+        #[cfg(feature = "kernel-trace")]
+        self.trace(TraceEvent::ThreadSwitch, process.id());
+
+        #[cfg(feature = "kernel-stats")]
+        let start = self.clock_cycles();
+
+        let reason = Self::switch_to_process(process);
+
+        #[cfg(feature = "kernel-stats")]
+        process.stats.account_run(self.clock_cycles() - start);
+
+        reason
+    }
+
+    /// Switches to the idle thread, accounting the idle time.
+    fn run_idle(&mut self) -> SupervisorCallReason {
+        #[cfg(feature = "kernel-trace")]
+        self.trace(TraceEvent::ThreadSwitch, self.idle.id());
+
+        #[cfg(feature = "kernel-stats")]
+        let start = self.clock_cycles();
+
+        let reason = Self::switch_to_process(&self.idle);
+
+        #[cfg(feature = "kernel-stats")]
+        {
+            let end = self.clock_cycles();
+            self.idle.stats.account_run(end - start);
+        }
+
+        reason
+    }
+
     /// Chooses the next thread to run based on scheduling policy.
     ///
     /// This scheduler picks the ready thread with the highest priority. If no threads are ready,
@@ -261,6 +839,14 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
         // Retrieve process last context
         let process_context = current.context.as_ptr();
 
+        unsafe {
+            (*addr_of_mut!(Z_KERNEL_STATE)).current_thread = current as *const _ as *const ()
+        };
+
+        // Restrict the memory the user process can access
+        #[cfg(feature = "kernel-mpu")]
+        Mpu::instance().configure_thread(&current.stack_region(), &current.regions());
+
         // Switch to user process
         let new_process_sp = unsafe { CPU::switch_to_user(process_sp, process_context) };
 
@@ -269,6 +855,19 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
         // to save the position of the stack pointer for next execution
         current.stack_ptr.set(new_process_sp);
 
+        // The user process faulted, its exception frame cannot be trusted
+        let exception = unsafe { read_volatile(&*addr_of_mut!(Z_FAULT_FLAG)) };
+        if exception != 0 {
+            unsafe { write_volatile(&mut *addr_of_mut!(Z_FAULT_FLAG), 0) };
+
+            return SupervisorCallReason::Fault(exception);
+        }
+
+        // Check the stack before reading anything from it
+        if current.has_overflowed_stack() {
+            return SupervisorCallReason::StackOverflow;
+        }
+
         unsafe {
             // If the flag is set it means, the current process called a syscall,
             // otherwise the switch was triggered by an interrupt
@@ -284,41 +883,18 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
             write_volatile(&mut *addr_of_mut!(Z_SYSCALL_FLAG), 0);
 
             if syscall_flag != 0 {
-                // At this point, the process exception frame looks like this
-                // sp + 00: r0 (syscall arg 0)
-                // sp + 04: r1 (syscall arg 1)
-                // sp + 08: r2 (syscall arg 2)
-                // sp + 0C: r3 (syscall arg 3)
-                // sp + 10: r12
-                // sp + 14: lr
-                // sp + 18: return address (instruction following the svc)
-                // sp + 1C: xPSR
-
-                // Read syscall arguments from stack
-                let r0 = ptr::read(new_process_sp.add(0));
-                let r1 = ptr::read(new_process_sp.add(1));
-                let r2 = ptr::read(new_process_sp.add(2));
-                let r3 = ptr::read(new_process_sp.add(3));
-
-                // Read syscall main id from yielded PC
-                // "svc 0xbb" is encoded as the following 16bits instruction: 0xdfbb
-                let pc_svc = ptr::read(new_process_sp.add(6)) as *const u16;
-                // Subtract 1 because return address (RA) includes the "thumb" flag that
-                // needs to be removed to get the actual instruction
-                let svc_instruction = ptr::read(pc_svc.sub(1));
-                let syscall_id = (svc_instruction & 0xFF) as u8;
-
-                let syscall_params = SVCCallParams {
-                    r0,
-                    r1,
-                    r2,
-                    r3,
-                    syscall_id,
-                };
+                // Read the syscall arguments and identifier from the exception
+                // frame at the top of the process stack
+                let syscall_params = CPU::InitStackFrame::syscall_params(new_process_sp);
 
                 SupervisorCallReason::Syscall(syscall_params)
             } else {
-                SupervisorCallReason::Interrupted
+                // Device interrupts (exception number 16 and above) set the
+                // flag, the SysTick does not
+                let exception = read_volatile(&*addr_of_mut!(Z_IRQ_FLAG));
+                write_volatile(&mut *addr_of_mut!(Z_IRQ_FLAG), 0);
+
+                SupervisorCallReason::Interrupted(exception.checked_sub(16).map(|irqn| irqn as u16))
             }
         }
     }
@@ -340,21 +916,37 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
     where
         S: SyncPrimitive<'a, CPU> + 'a,
     {
-        self.kobj
-            .iter_mut()
-            .enumerate()
-            .find(|(_, slot)| slot.is_none())
-            .and_then(|(index, slot)| {
-                Box::<KernelObject<'a, S, CPU>, Global>::try_new(KernelObject::new(
-                    index as u32,
-                    initialized_sync,
-                ))
-                .map(|kobj| {
-                    *slot = Some(kobj);
-                    index as i32
-                })
-                .ok()
-            })
+        let index = self.kobj.iter().position(Option::is_none)?;
+
+        self.kobj_create_at(index, initialized_sync)
+    }
+
+    /// Creates a new kernel object at the given index, which must be free.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the kernel object.
+    /// * `initialized_sync` - The initialized synchronization primitive to wrap in a kernel object.
+    ///
+    /// # Returns
+    ///
+    /// An `Option<i32>` containing the index of the created kernel object, or `None` if the
+    /// index is taken or allocation failed.
+    fn kobj_create_at<S>(&mut self, index: usize, initialized_sync: S) -> Option<i32>
+    where
+        S: SyncPrimitive<'a, CPU> + 'a,
+    {
+        let slot = self.kobj.get_mut(index).filter(|slot| slot.is_none())?;
+
+        Box::<KernelObject<'a, S, CPU>, Global>::try_new(KernelObject::new(
+            index as u32,
+            initialized_sync,
+        ))
+        .map(|kobj| {
+            *slot = Some(kobj);
+            index as i32
+        })
+        .ok()
     }
 
     /// Creates a new kernel object with a default-initialized synchronization primitive.
@@ -390,24 +982,58 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
         thread: &'a Thread<'a, CPU>,
         timeout: Timeout,
     ) -> SyscallOutcome {
-        let ticks = self.get_ticks();
+        // Calculate the instant when the thread should be woken up
+        let timeout_instant = self.timeout_instant(timeout);
+
+        self.kobj_acquire_until(kobj, thread, timeout_instant)
+    }
+
+    /// Attempts to acquire a kernel object for the given thread, waiting at most
+    /// until the given instant.
+    ///
+    /// # Arguments
+    ///
+    /// * `kobj` - The index of the kernel object to acquire.
+    /// * `thread` - A reference to the thread attempting to acquire the object.
+    /// * `timeout_instant` - The instant (in ticks) when the thread should stop waiting.
+    ///
+    /// # Returns
+    ///
+    /// A `SyscallOutcome` indicating the result of the acquisition attempt.
+    fn kobj_acquire_until(
+        &mut self,
+        kobj: i32,
+        thread: &'a Thread<'a, CPU>,
+        timeout_instant: TimeoutInstant,
+    ) -> SyscallOutcome {
+        // Waiting on the kernel object would close a cycle in the wait-for graph
+        #[cfg(feature = "kernel-deadlock-detection")]
+        if !timeout_instant.is_zero() && self.kobj_would_deadlock(kobj, thread) {
+            #[cfg(feature = "kernel-debug")]
+            self.kobj_print_deadlock(kobj, thread);
+
+            return SyscallOutcome::Completed(Kerr::EDEADLK as i32);
+        }
+
         if let Some(obj_ref) = self
             .kobj
             .get_mut(kobj as usize)
             .and_then(|slot| slot.as_mut())
         {
-            // Calculate the instant when the thread should be woken up
-            let timeout_instant = match timeout {
-                Timeout::Forever => TimeoutInstant::new_never(),
-                Timeout::Duration(ms) => TimeoutInstant::new_at(ticks + Self::ms_to_ticks(ms)),
-            };
-
             match obj_ref.acquire(thread, timeout_instant) {
                 AcquireOutcome::Obtained(swap_data) => {
                     SyscallOutcome::Completed(swap_data.to_syscall_ret())
                 }
                 AcquireOutcome::NotObtained => SyscallOutcome::Completed(Kerr::TryAgain as i32),
-                AcquireOutcome::Pending => SyscallOutcome::Pending,
+                AcquireOutcome::Pending => {
+                    #[cfg(feature = "kernel-stats")]
+                    self.stats.count_contention(kobj);
+
+                    #[cfg(feature = "kernel-trace")]
+                    self.trace(TraceEvent::KobjPend, kobj as u32);
+
+                    SyscallOutcome::Pending
+                }
             }
         } else {
             // Invalid kernel object
@@ -415,34 +1041,464 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
         }
     }
 
+    /// Removes a thread from the waitqueue of the kernel object it pends on, if any.
+    fn kobj_remove_waiter(&mut self, thread: &'a Thread<'a, CPU>) {
+        if let Some(kobj_index) = thread.lives_in_waitqueue() {
+            if let Some(kobj) = self
+                .kobj
+                .get_mut(kobj_index as usize)
+                .and_then(|obj_ref| obj_ref.as_mut())
+            {
+                kobj.remove_thread(thread)
+            }
+        }
+    }
+
+    /// Releases the kernel objects owned by a thread (e.g. locked mutexes),
+    /// handing them over to their first waiter if any.
+    fn kobj_release_owned(&mut self, thread: &'a Thread<'a, CPU>) {
+        for index in 0..K {
+            let Some(obj_ref) = self.kobj[index].as_mut() else {
+                continue;
+            };
+
+            // Only mutexes have an owner
+            if obj_ref.owner().is_some_and(|owner| ptr::eq(owner, thread))
+                && obj_ref.release_by(thread, SwapData::Ownership).is_ok()
+            {
+                #[cfg(feature = "kernel-trace")]
+                self.trace(TraceEvent::KobjRelease, index as u32);
+            }
+        }
+    }
+
+    /// Stops a thread for good (stack overflow, fault, killed, ...).
+    ///
+    /// The thread no longer waits on any kernel object and the kernel objects it
+    /// owns are released, so that other threads don't wait for them forever.
+    fn stop_thread(&mut self, thread: &'a Thread<'a, CPU>) {
+        self.kobj_remove_waiter(thread);
+        self.kobj_release_owned(thread);
+        thread.state.set(ThreadState::Stopped);
+
+        #[cfg(feature = "kernel-trace")]
+        self.trace(TraceEvent::ThreadStop, thread.id());
+    }
+
+    /// Stops the thread with the given identifier.
+    ///
+    /// Kernel objects owned by the thread (e.g. locked mutexes) are released.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the thread has been stopped.
+    /// * `Err(Kerr::ESRCH)` if there is no thread with this identifier.
+    /// * `Err(Kerr::EPERM)` if the thread is a kernel thread (work queue).
+    pub fn kill_thread(&mut self, tid: u32) -> KResult<()> {
+        let thread = self
+            .tasks
+            .iter()
+            .find(|thread| thread.id() == tid)
+            .ok_or(Kerr::ESRCH)?;
+
+        #[cfg(feature = "kernel-workqueue")]
+        if self.workq.is_workq_thread(thread) {
+            return Err(Kerr::EPERM);
+        }
+
+        self.stop_thread(thread);
+
+        Ok(())
+    }
+
+    /// Returns the thread owning a kernel object, if any.
+    #[cfg(feature = "kernel-deadlock-detection")]
+    fn kobj_owner(&self, kobj: i32) -> Option<&'a Thread<'a, CPU>> {
+        self.kobj
+            .get(kobj as usize)
+            .and_then(|slot| slot.as_ref())
+            .and_then(|obj_ref| obj_ref.owner())
+    }
+
+    /// Checks whether waiting on a kernel object would deadlock the thread.
+    ///
+    /// Follows the wait-for graph from the kernel object: its owner, the kernel
+    /// object this owner is pending on, and so on. A deadlock is detected if the
+    /// chain leads back to the thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `kobj` - The index of the kernel object the thread is about to wait on.
+    /// * `thread` - The thread about to wait.
+    ///
+    /// # Returns
+    ///
+    /// `true` if waiting would close a cycle, `false` otherwise.
+    #[cfg(feature = "kernel-deadlock-detection")]
+    fn kobj_would_deadlock(&self, kobj: i32, thread: &'a Thread<'a, CPU>) -> bool {
+        let mut kobj = kobj;
+
+        // A thread waits on a single kernel object, so the chain is at most K long
+        for _ in 0..K {
+            match self.kobj_owner(kobj) {
+                Some(owner) if core::ptr::eq(owner, thread) => return true,
+                Some(owner) => match owner.lives_in_waitqueue() {
+                    Some(next) => kobj = next as i32,
+                    None => return false,
+                },
+                None => return false,
+            }
+        }
+
+        false
+    }
+
+    /// Prints the cycle of the wait-for graph detected by `kobj_would_deadlock`.
+    #[cfg(all(feature = "kernel-deadlock-detection", feature = "kernel-debug"))]
+    fn kobj_print_deadlock(&self, kobj: i32, thread: &'a Thread<'a, CPU>) {
+        println!(
+            "deadlock detected: thread {:p} waits on kobj {}",
+            thread, kobj
+        );
+
+        let mut kobj = kobj;
+        while let Some(owner) = self.kobj_owner(kobj) {
+            if core::ptr::eq(owner, thread) {
+                println!("  kobj {} owned by thread {:p}", kobj, owner);
+                break;
+            }
+
+            match owner.lives_in_waitqueue() {
+                Some(next) => {
+                    println!(
+                        "  kobj {} owned by thread {:p} waiting on kobj {}",
+                        kobj, owner, next
+                    );
+                    kobj = next as i32;
+                }
+                None => break,
+            }
+        }
+    }
+
     /// Releases a kernel object and notifies any waiting threads.
     ///
     /// # Arguments
     ///
     /// * `kobj` - The index of the kernel object to release.
+    /// * `releaser` - The thread releasing the kernel object, `None` for the kernel.
     /// * `swap_data` - The data to swap with the kernel object (if applicable).
     ///
     /// # Returns
     ///
-    /// A `SyscallOutcome` indicating the result of the release operation.
-    fn kobj_release_notify(&mut self, kobj: i32, swap_data: SwapData) -> SyscallOutcome {
+    /// A `SyscallOutcome` indicating the result of the release operation,
+    /// `Kerr::EPERM` if the kernel object is owned by another thread.
+    fn kobj_release_notify(
+        &mut self,
+        kobj: i32,
+        releaser: Option<&'a Thread<'a, CPU>>,
+        swap_data: SwapData,
+    ) -> SyscallOutcome {
         let ret = if let Some(obj_ref) = self
             .kobj
             .get_mut(kobj as usize)
             .and_then(|slot| slot.as_mut())
         {
-            match obj_ref.release(swap_data) {
-                Ok(_) => Kerr::Success,
-                Err(_) => Kerr::NotSupported,
+            let released = match releaser {
+                Some(thread) => obj_ref.release_by(thread, swap_data),
+                None => obj_ref.release(swap_data).map_err(|_| Kerr::NotSupported),
+            };
+            match released {
+                Ok(()) => Kerr::Success,
+                Err(err) => err,
             }
         } else {
             // Invalid kernel object
             Kerr::NoEntry
         };
 
+        #[cfg(feature = "kernel-trace")]
+        if matches!(ret, Kerr::Success) {
+            self.trace(TraceEvent::KobjRelease, kobj as u32);
+        }
+
         SyscallOutcome::Completed(ret as i32)
     }
 
+    /// Submits a work item to the work queue, to be executed once `delay` has elapsed.
+    ///
+    /// This function can be called from the kernel context (e.g. while handling
+    /// an interrupt) to defer processing to the work queue thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `work` - The work item to execute.
+    /// * `delay` - The delay before the work item can be executed, cannot be `Forever`.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the work item has been queued.
+    /// * `Err(Kerr::InvalidArguments)` if the delay is `Forever`.
+    /// * `Err(Kerr::NoMemory)` if the work queue is full.
+    /// * `Err(Kerr::NotSupported)` if the work queue is not enabled.
+    pub fn work_submit(&mut self, work: Work, delay: Timeout) -> KResult<()> {
+        self.work_submit_with_regions(work, [None; MAX_THREAD_REGIONS], delay)
+    }
+
+    /// Submits a work item whose handler runs with the given memory regions,
+    /// those of the thread which submitted it.
+    ///
+    /// See `work_submit` for the arguments and the returned value.
+    fn work_submit_with_regions(
+        &mut self,
+        work: Work,
+        regions: [Option<MemoryRegion>; MAX_THREAD_REGIONS],
+        delay: Timeout,
+    ) -> KResult<()> {
+        #[cfg(feature = "kernel-workqueue")]
+        {
+            let at = match self.timeout_instant(delay) {
+                TimeoutInstant::At(at) => at,
+                TimeoutInstant::Never => return Err(Kerr::InvalidArguments),
+            };
+
+            self.workq
+                .push(work, regions, at)
+                .map_err(|_| Kerr::NoMemory)?;
+
+            // Wake up the work queue thread, so that it either executes the
+            // work or waits until the new earliest delayed work item is due.
+            if let Some(kobj) = self.workq.kobj {
+                self.kobj_release_notify(kobj, None, SwapData::Empty);
+            }
+
+            Ok(())
+        }
+
+        #[cfg(not(feature = "kernel-workqueue"))]
+        {
+            let _ = (work, regions, delay);
+            Err(Kerr::NotSupported)
+        }
+    }
+
+    /// Hands the next ready work item to the work queue thread, or makes it
+    /// pend until new work is submitted or the next delayed work item is due.
+    ///
+    /// # Arguments
+    ///
+    /// * `thread` - The thread that invoked the syscall, must be the work queue thread.
+    /// * `slot` - Where to write the work item in the thread memory.
+    ///
+    /// # Returns
+    ///
+    /// A `SyscallOutcome` indicating the result of the syscall.
+    #[cfg(feature = "kernel-workqueue")]
+    unsafe fn workq_next(
+        &mut self,
+        thread: &'a Thread<'a, CPU>,
+        slot: *mut Option<Work>,
+    ) -> SyscallOutcome {
+        if !self.workq.is_workq_thread(thread) {
+            return SyscallOutcome::Completed(Kerr::EPERM as i32);
+        }
+
+        if let Err(err) =
+            Self::check_user_buffer(thread, slot, size_of::<Option<Work>>(), Access::ReadWrite)
+        {
+            return SyscallOutcome::Completed(err as i32);
+        }
+
+        // The thread is done with the previous work item, if any
+        thread.set_regions([None; MAX_THREAD_REGIONS]);
+
+        if let Some((work, regions)) = self.workq.pop_ready(self.get_ticks()) {
+            // The handler runs with the memory regions of the submitter
+            thread.set_regions(regions);
+            slot.write(Some(work));
+            SyscallOutcome::Completed(0)
+        } else if let Some(kobj) = self.workq.kobj {
+            let next_instant = self.workq.next_instant();
+            self.kobj_acquire_until(kobj, thread, next_instant)
+        } else {
+            SyscallOutcome::Completed(Kerr::NoEntry as i32)
+        }
+    }
+
+    /// Copies the statistics selected by a query to the memory of a thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `thread` - The thread which invoked the syscall.
+    /// * `query` - The statistics to read.
+    /// * `index` - The syscall counter or kernel object index, depending on the query.
+    /// * `ptr` - Where to write the statistics in the thread memory.
+    /// * `len` - The size of the buffer, must match the statistics structure size.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the statistics have been written.
+    /// * `Err(Kerr::NoEntry)` if there is no syscall counter or kernel object at `index`.
+    /// * `Err(Kerr::InvalidArguments)` if `len` does not match the structure size.
+    /// * `Err(Kerr::EFAULT)` if the thread does not own the buffer.
+    #[cfg(feature = "kernel-stats")]
+    unsafe fn stats_query(
+        &self,
+        thread: &'a Thread<'a, CPU>,
+        query: StatsQuery,
+        index: u32,
+        ptr: *mut u8,
+        len: usize,
+    ) -> KResult<()> {
+        let period = self.systick.get_period_cycles();
+
+        match query {
+            StatsQuery::Thread => Self::copy_to_user(thread, ptr, len, thread.stats.info(period)),
+            StatsQuery::Kernel => Self::copy_to_user(thread, ptr, len, self.kernel_stats_info()),
+            StatsQuery::Syscall => {
+                let info = self.stats.syscall(index as usize).ok_or(Kerr::NoEntry)?;
+                Self::copy_to_user(thread, ptr, len, info)
+            }
+            StatsQuery::Kobj => {
+                let contention = self
+                    .kobj
+                    .get(index as usize)
+                    .and_then(|slot| slot.as_ref())
+                    .and(self.stats.contention(index as usize))
+                    .ok_or(Kerr::NoEntry)?;
+                Self::copy_to_user(thread, ptr, len, KobjStatsInfo { contention })
+            }
+        }
+    }
+
+    /// Copies a snapshot of the kernel, its threads or its kernel objects to the
+    /// memory of a thread.
+    ///
+    /// # Arguments
+    ///
+    /// * `thread` - The thread which invoked the syscall.
+    /// * `query` - The snapshot to take.
+    /// * `ptr` - Where to write the snapshot in the thread memory.
+    /// * `len` - The size of the buffer in bytes.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(count)` with the number of threads or kernel objects written, 0 for
+    ///   the version.
+    /// * `Err(Kerr::InvalidArguments)` if `len` does not match the version structure size.
+    /// * `Err(Kerr::EFAULT)` if the thread does not own the buffer.
+    unsafe fn sysinfo_query(
+        &self,
+        thread: &'a Thread<'a, CPU>,
+        query: SysInfoQuery,
+        ptr: *mut u8,
+        len: usize,
+    ) -> KResult<usize> {
+        match query {
+            SysInfoQuery::Version => {
+                Self::copy_to_user(thread, ptr, len, VersionInfo::current()).map(|_| 0)
+            }
+            SysInfoQuery::Threads => {
+                let threads = self
+                    .tasks
+                    .iter()
+                    .map(|thread| thread.info())
+                    .chain(core::iter::once(self.idle.info()));
+
+                Self::copy_array_to_user(thread, ptr, len, threads)
+            }
+            SysInfoQuery::Kobjs => {
+                let kobjs = self.kobj.iter().enumerate().filter_map(|(index, slot)| {
+                    slot.as_ref().map(|obj| KobjInfo {
+                        kobj: index as u32,
+                        kind: obj.kind(),
+                        waiters: obj.waiters() as u32,
+                        owner: obj.owner().map_or(0, |owner| owner.id()),
+                    })
+                });
+
+                Self::copy_array_to_user(thread, ptr, len, kobjs)
+            }
+            SysInfoQuery::Memory => {
+                let info = MemoryInfo {
+                    heap_size: KERNEL_ALLOCATOR.size() as u32,
+                    heap_free: KERNEL_ALLOCATOR.remaining() as u32,
+                };
+
+                Self::copy_to_user(thread, ptr, len, info).map(|_| 0)
+            }
+        }
+    }
+
+    /// Collects the kernel-wide statistics.
+    #[cfg(feature = "kernel-stats")]
+    fn kernel_stats_info(&self) -> KernelStatsInfo {
+        let (idle_ticks, idle_cycles) = split_cycles(
+            self.idle.stats.cpu_cycles.get(),
+            self.systick.get_period_cycles(),
+        );
+
+        KernelStatsInfo {
+            idle_ticks,
+            idle_cycles,
+            switches: self.tasks.iter().map(|t| t.stats.switches.get()).sum(),
+            syscalls: self.stats.syscalls().map(|counter| counter.count).sum(),
+        }
+    }
+
+    /// Prints the statistics of the threads, the syscalls and the kernel objects
+    /// as tables.
+    #[cfg(feature = "kernel-stats")]
+    pub fn print_stats(&self) {
+        let period = self.systick.get_period_cycles();
+
+        println!(
+            "{:<12} {:>5} {:>9} {:>9} {:>10} {:>10} {:>8}",
+            "thread", "prio", "switches", "syscalls", "cpu ticks", "cycles", "pending"
+        );
+        for thread in self.tasks.iter() {
+            let info = thread.stats.info(period);
+            println!(
+                "{:<12p} {:>5} {:>9} {:>9} {:>10} {:>10} {:>8}",
+                thread,
+                thread.priority.raw_priority(),
+                info.switches,
+                info.syscalls,
+                info.cpu_ticks,
+                info.cpu_cycles,
+                info.pending_ticks
+            );
+        }
+
+        let kernel = self.kernel_stats_info();
+        println!(
+            "idle: {} ticks {} cycles, switches: {}, syscalls: {}",
+            kernel.idle_ticks, kernel.idle_cycles, kernel.switches, kernel.syscalls
+        );
+
+        println!("{:<8} {:>8} {:>8}", "syscall", "function", "count");
+        for counter in self.stats.syscalls() {
+            println!(
+                "{:<8} {:>8} {:>8}",
+                counter.syscall_id, counter.sub_id, counter.count
+            );
+        }
+
+        println!("{:<8} {:>10}", "kobj", "contention");
+        for (index, _) in self
+            .kobj
+            .iter()
+            .enumerate()
+            .filter(|(_, slot)| slot.is_some())
+        {
+            println!(
+                "{:<8} {:>10}",
+                index,
+                self.stats.contention(index).unwrap_or_default()
+            );
+        }
+    }
+
     /// Handles a syscall from the given thread.
     ///
     /// Executes the syscall and returns the outcome.
@@ -464,7 +1520,7 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
         println!("{:?}", syscall);
 
         #[cfg(feature = "kernel-stats")]
-        thread.stats.syscalls.set(thread.stats.syscalls.get() + 1);
+        thread.stats.account_syscall();
 
         match syscall {
             Syscall::Test { r0, r1, r2, r3 } => {
@@ -476,25 +1532,29 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
                 SyscallOutcome::Completed(0)
             }
             Syscall::Kernel(KernelSyscall::Yield) => SyscallOutcome::Completed(0),
-            Syscall::Kernel(KernelSyscall::Sleep { ms }) => {
-                let timeout = Timeout::from(ms);
-                match timeout {
-                    Timeout::Forever => {
-                        thread.state.set(ThreadState::Stopped);
-                        SyscallOutcome::Completed(0)
-                    }
-                    Timeout::Duration(0) => SyscallOutcome::Completed(0),
-                    Timeout::Duration(ms) => {
-                        thread
-                            .state
-                            .set(ThreadState::Pending(PendingContext::new_timeout(
-                                TimeoutInstant::new_at(self.get_ticks() + Self::ms_to_ticks(ms)),
-                            )));
-
-                        SyscallOutcome::Pending
+            Syscall::Kernel(KernelSyscall::Sleep { timeout }) => match timeout {
+                Timeout::Forever => {
+                    self.stop_thread(thread);
+                    SyscallOutcome::Completed(0)
+                }
+                Timeout::Duration(0) | Timeout::DurationUs(0) => SyscallOutcome::Completed(0),
+                timeout => {
+                    let timeout_instant = self.timeout_instant(timeout);
+
+                    // Absolute deadline already reached (e.g. periodic thread overrun)
+                    if timeout_instant.is_zero() {
+                        return SyscallOutcome::Completed(0);
                     }
+
+                    thread
+                        .state
+                        .set(ThreadState::Pending(PendingContext::new_timeout(
+                            timeout_instant,
+                        )));
+
+                    SyscallOutcome::Pending
                 }
-            }
+            },
             Syscall::Kernel(KernelSyscall::SyncCreate { prim }) => SyscallOutcome::Completed(
                 match prim {
                     SyncPrimitiveCreate::Sync => self.kobj_create_default::<Sync>(),
@@ -510,7 +1570,10 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
                 prim: _, // sync_prim_type
                 kobj,
                 timeout,
-            }) => self.kobj_acquire(kobj, thread, timeout),
+            }) => match timeout {
+                Some(timeout) => self.kobj_acquire(kobj, thread, timeout),
+                None => SyscallOutcome::Completed(Kerr::InvalidArguments as i32),
+            },
             Syscall::Kernel(KernelSyscall::Sync { arg, prim, kobj }) => {
                 let swap_data = match prim {
                     SyncPrimitiveType::Sync => SwapData::Empty,
@@ -518,33 +1581,29 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
                     SyncPrimitiveType::Semaphore => SwapData::Empty,
                     SyncPrimitiveType::Mutex => SwapData::Ownership,
                 };
-                self.kobj_release_notify(kobj, swap_data)
+                self.kobj_release_notify(kobj, Some(thread), swap_data)
             }
             Syscall::Kernel(KernelSyscall::Stop) => {
-                thread.state.set(ThreadState::Stopped);
+                self.stop_thread(thread);
                 SyscallOutcome::Completed(0)
             }
             Syscall::Kernel(KernelSyscall::MemoryAlloc { size, mut align }) => {
                 // If align is 0, use default alignment
                 if align == 0 {
                     align = USER_MALLOC_DEFAULT_ALIGN;
-                } else if align >= USER_MALLOC_MIN_ALIGN {
+                } else if align < USER_MALLOC_MIN_ALIGN || !align.is_power_of_two() {
                     return SyscallOutcome::Completed(Kerr::InvalidArguments as i32);
                 }
 
                 // For alignments greater than or equal to 2, the returned memory pointer
                 // can safely be shifted right by one bit (ptr >> 1), as the least significant
                 // bit of the aligned pointer will always be zero.
-                match alloc::Layout::from_size_align(size, align) {
-                    Ok(memory_layout) => match Global.allocate_zeroed(memory_layout) {
-                        Ok(ptr) => {
-                            let ptr = ptr.as_ptr() as *const u8 as u32;
-                            let shifted_ptr = ptr >> 1;
-                            SyscallOutcome::Completed(shifted_ptr as i32)
-                        }
-                        Err(_) => SyscallOutcome::Completed(Kerr::NoMemory as i32),
-                    },
-                    Err(_) => SyscallOutcome::Completed(Kerr::InvalidArguments as i32),
+                match Self::user_alloc(thread, size, align) {
+                    Ok(ptr) => {
+                        let shifted_ptr = ptr as u32 >> 1;
+                        SyscallOutcome::Completed(shifted_ptr as i32)
+                    }
+                    Err(err) => SyscallOutcome::Completed(err as i32),
                 }
             }
             Syscall::Kernel(KernelSyscall::MemoryFree { ptr }) => {
@@ -569,7 +1628,138 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
 
                 SyscallOutcome::Completed(Kerr::NotSupported as i32)
             }
+            Syscall::Kernel(KernelSyscall::WorkSubmit {
+                handler,
+                arg,
+                delay,
+            }) => match Work::from_raw(handler, arg) {
+                // The handler would run without the context of the thread
+                Some(_) if !self.shares_workq_context(thread) => {
+                    SyscallOutcome::Completed(Kerr::EPERM as i32)
+                }
+                // The handler must be code the thread is allowed to execute
+                Some(_) if !Self::is_user_code(thread, handler) => {
+                    SyscallOutcome::Completed(Kerr::EFAULT as i32)
+                }
+                Some(work) => SyscallOutcome::Completed(
+                    match self.work_submit_with_regions(work, thread.regions(), delay) {
+                        Ok(()) => 0,
+                        Err(err) => err as i32,
+                    },
+                ),
+                None => SyscallOutcome::Completed(Kerr::InvalidArguments as i32),
+            },
+            #[cfg(feature = "kernel-workqueue")]
+            Syscall::Kernel(KernelSyscall::WorkNext { slot }) => self.workq_next(thread, slot),
+            Syscall::Kernel(KernelSyscall::Stats {
+                query,
+                index,
+                ptr,
+                len,
+            }) => {
+                #[cfg(feature = "kernel-stats")]
+                let ret = self.stats_query(thread, query, index, ptr, len);
+                #[cfg(not(feature = "kernel-stats"))]
+                let ret: KResult<()> = {
+                    let _ = (query, index, ptr, len);
+                    Err(Kerr::NotSupported)
+                };
+
+                SyscallOutcome::Completed(match ret {
+                    Ok(()) => 0,
+                    Err(err) => err as i32,
+                })
+            }
+            Syscall::Kernel(KernelSyscall::SysInfo { query, ptr, len }) => {
+                SyscallOutcome::Completed(match self.sysinfo_query(thread, query, ptr, len) {
+                    Ok(count) => count as i32,
+                    Err(err) => err as i32,
+                })
+            }
+            // System syscalls are reserved to trusted threads (e.g. the shell)
+            Syscall::Kernel(
+                KernelSyscall::Kill { .. }
+                | KernelSyscall::Load { .. }
+                | KernelSyscall::Reboot
+                | KernelSyscall::Exit { .. },
+            ) if !thread.is_trusted() => SyscallOutcome::Completed(Kerr::EPERM as i32),
+            Syscall::Kernel(KernelSyscall::Kill { tid }) => {
+                SyscallOutcome::Completed(match self.kill_thread(tid) {
+                    Ok(()) => 0,
+                    Err(err) => err as i32,
+                })
+            }
+            Syscall::Kernel(KernelSyscall::Load { name, len }) => {
+                if let Err(err) = Self::check_user_buffer(thread, name, len, Access::ReadOnly) {
+                    return SyscallOutcome::Completed(err as i32);
+                }
+
+                let name = core::slice::from_raw_parts(name, len);
+                SyscallOutcome::Completed(
+                    match core::str::from_utf8(name)
+                        .map_err(|_| Kerr::InvalidArguments)
+                        .and_then(|name| self.load_elf_image(name))
+                    {
+                        Ok(tid) => tid as i32,
+                        Err(err) => err as i32,
+                    },
+                )
+            }
+            Syscall::Kernel(KernelSyscall::TraceDump) => {
+                #[cfg(feature = "kernel-trace")]
+                {
+                    self.trace_dump();
+                    SyscallOutcome::Completed(0)
+                }
+                #[cfg(not(feature = "kernel-trace"))]
+                SyscallOutcome::Completed(Kerr::NotSupported as i32)
+            }
+            Syscall::Kernel(KernelSyscall::Exit { status }) => {
+                #[cfg(feature = "kernel-exit")]
+                {
+                    println!("exiting with status {}", status);
+                    semihosting::exit_with_status(status)
+                }
+                #[cfg(not(feature = "kernel-exit"))]
+                {
+                    let _ = status;
+                    SyscallOutcome::Completed(Kerr::NotSupported as i32)
+                }
+            }
+            Syscall::Kernel(KernelSyscall::Reboot) => {
+                println!("rebooting ...");
+
+                // The hosted process cannot reset, it exits with a status
+                // telling the reboot apart
+                #[cfg(feature = "hosted")]
+                {
+                    hosted::exit(hosted::REBOOT_EXIT_STATUS)
+                }
+                #[cfg(not(feature = "hosted"))]
+                {
+                    SCB::instance().system_reset()
+                }
+            }
+            Syscall::Kernel(KernelSyscall::UptimeMs) => {
+                SyscallOutcome::Completed64(self.get_uptime_ms())
+            }
+            Syscall::Kernel(KernelSyscall::UptimeTicks) => {
+                SyscallOutcome::Completed64(self.get_ticks())
+            }
+            Syscall::Kernel(KernelSyscall::StackUsage) => {
+                SyscallOutcome::Completed(thread.stack_high_water_mark() as i32)
+            }
+            Syscall::Kernel(KernelSyscall::UptimeUs) => {
+                SyscallOutcome::Completed64(self.clock_us())
+            }
+            Syscall::Kernel(KernelSyscall::CycleGet) => {
+                SyscallOutcome::Completed(self.get_cycles() as i32)
+            }
             Syscall::Io(IoSyscall::Print { ptr, len, newline }) => {
+                if let Err(err) = Self::check_user_buffer(thread, ptr, len, Access::ReadOnly) {
+                    return SyscallOutcome::Completed(err as i32);
+                }
+
                 // Rebuild &[u8] from (string and len)
                 let slice = core::slice::from_raw_parts(ptr, len);
 
@@ -583,6 +1773,10 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
                 SyscallOutcome::Completed(0)
             }
             Syscall::Io(IoSyscall::HexPrint { ptr, len }) => {
+                if let Err(err) = Self::check_user_buffer(thread, ptr, len, Access::ReadOnly) {
+                    return SyscallOutcome::Completed(err as i32);
+                }
+
                 // Rebuild &[u8] from (string and len)
                 let slice = core::slice::from_raw_parts(ptr, len);
 
@@ -603,9 +1797,18 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
     /// This function checks for interrupts that have occurred and updates the kernel's state
     /// accordingly, such as incrementing the tick counter and managing timed-out threads.
     fn handle_interrupts(&mut self) {
-        // 1. Handle systick interrupt if it occurred
-        if self.systick.get_countflag() {
-            self.increment_ticks();
+        // 1. Handle systick interrupt if it occurred, or if a tick was detected
+        // while reading the clock
+        let countflag = self.systick.get_countflag();
+        let deferred = core::mem::take(&mut self.tick_pending);
+
+        if countflag || deferred {
+            if deferred {
+                self.increment_ticks();
+            }
+            if countflag {
+                self.increment_ticks();
+            }
 
             let sys_ticks = self.get_ticks();
 
@@ -615,19 +1818,171 @@ impl<'a, CPU: CpuVariant, const K: usize, const F: u32> Kernel<'a, CPU, K, F> {
                 .iter()
                 .filter(|thread| thread.has_timed_out(sys_ticks))
             {
-                // Remove the thread from the kobj waitqueue
-                if let Some(kobj_index) = thread.lives_in_waitqueue() {
-                    if let Some(kobj) = self
-                        .kobj
-                        .get_mut(kobj_index as usize)
-                        .and_then(|obj_ref| obj_ref.as_mut())
-                    {
-                        kobj.remove_thread(thread)
-                    }
-                }
+                self.kobj_remove_waiter(thread);
+
+                #[cfg(feature = "kernel-trace")]
+                self.trace(TraceEvent::Timeout, thread.id());
 
                 thread.unpend_timeout();
             }
         }
+
+        // 2. Call the handlers of the device interrupts that occurred
+        #[cfg(not(feature = "hosted"))]
+        self.dispatch_irqs();
+    }
+
+    /// Registers the handler of a device interrupt and enables it in the NVIC.
+    ///
+    /// The interrupt is masked when it occurs and the handler is called later
+    /// by the kernel loop, in the kernel context: it may wake up threads or
+    /// submit work. The interrupt is enabled again once the handler returned.
+    ///
+    /// # Arguments
+    ///
+    /// * `irqn` - The device interrupt number.
+    /// * `handler` - The function called with the kernel and the interrupt number.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the handler has been registered.
+    /// * `Err(Kerr::InvalidArguments)` if the SoC has no such interrupt.
+    /// * `Err(Kerr::EEXIST)` if a handler is already registered for it.
+    /// * `Err(Kerr::ENOSPC)` if `MAX_IRQ_HANDLERS` handlers are registered.
+    #[cfg(not(feature = "hosted"))]
+    pub fn register_irq_handler<I: InterruptNumber>(
+        &mut self,
+        irqn: I,
+        handler: IrqHandler<'a, CPU, K, F>,
+    ) -> KResult<()> {
+        let number = irqn.number();
+        if number as usize >= crate::soc::IRQ_COUNT {
+            return Err(Kerr::InvalidArguments);
+        }
+
+        if self
+            .irq_handlers
+            .iter()
+            .flatten()
+            .any(|(n, _)| *n == number)
+        {
+            return Err(Kerr::EEXIST);
+        }
+
+        let slot = self
+            .irq_handlers
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(Kerr::ENOSPC)?;
+        *slot = Some((number, handler));
+
+        NVIC::instance().enable(irqn);
+
+        Ok(())
+    }
+
+    /// Disables a device interrupt in the NVIC and unregisters its handler.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the handler has been unregistered.
+    /// * `Err(Kerr::NoEntry)` if no handler is registered for the interrupt.
+    #[cfg(not(feature = "hosted"))]
+    pub fn unregister_irq_handler<I: InterruptNumber>(&mut self, irqn: I) -> KResult<()> {
+        let number = irqn.number();
+        let slot = self
+            .irq_handlers
+            .iter_mut()
+            .find(|slot| matches!(slot, Some((n, _)) if *n == number))
+            .ok_or(Kerr::NoEntry)?;
+
+        NVIC::instance().disable(irqn);
+        *slot = None;
+
+        Ok(())
+    }
+
+    /// Calls the handlers of the device interrupts recorded by `z_irq`, then
+    /// enables the interrupts which are still registered.
+    ///
+    /// Called on every return to the kernel, syscalls included.
+    #[cfg(not(feature = "hosted"))]
+    fn dispatch_irqs(&mut self) {
+        let pending = irq::take_pending();
+        if pending.iter().all(|word| *word == 0) {
+            return;
+        }
+
+        // Handlers may (un)register handlers
+        let handlers = self.irq_handlers;
+
+        for (number, handler) in handlers.iter().flatten() {
+            let (word, bit) = (*number as usize / 32, *number % 32);
+            if pending[word] & (1 << bit) == 0 {
+                continue;
+            }
+
+            handler(self, *number);
+
+            if self.irq_handlers.iter().flatten().any(|(n, _)| n == number) {
+                NVIC::instance().enable(*number);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::{
+        mock::{mock_thread, MockCpu, MOCK_STACK_SIZE},
+        stack::Stack,
+    };
+
+    type MockKernel<'a> = Kernel<'a, MockCpu, 4, 100>;
+
+    #[test]
+    fn test_check_user_buffer() {
+        let mut stack = Stack::<MOCK_STACK_SIZE>::zeroed();
+        let thread = mock_thread(&mut stack);
+        let stack = thread.stack_region();
+        let flash = MemoryRegion::flash();
+        let other = 0u32;
+
+        let check = |addr: usize, len: usize, access: Access| {
+            MockKernel::check_user_buffer(&thread, addr as *const u8, len, access).is_ok()
+        };
+
+        assert!(check(stack.start, stack.size, Access::ReadWrite));
+        assert!(!check(stack.start + 4, stack.size, Access::ReadOnly));
+        assert!(check(flash.start, flash.size, Access::ReadOnly));
+        assert!(!check(flash.start, 4, Access::ReadWrite));
+        assert!(!check(&other as *const u32 as usize, 4, Access::ReadOnly));
+
+        // Empty slices have a dangling pointer, but not a null one
+        let dangling = NonNull::<u64>::dangling().as_ptr() as usize;
+        assert!(check(dangling, 0, Access::ReadWrite));
+        assert!(!check(0, 0, Access::ReadOnly));
+    }
+
+    #[test]
+    fn test_user_alloc_without_heap() {
+        let mut stack = Stack::<MOCK_STACK_SIZE>::zeroed();
+        let thread = mock_thread(&mut stack);
+
+        // Threads without a heap allocate from the kernel heap and own the block
+        let ptr = MockKernel::user_alloc(&thread, 24, 8).expect("memory should be allocated");
+        assert_eq!(ptr as usize % 8, 0);
+        assert!(thread.owns_memory(ptr as usize, 24, Access::ReadWrite));
+        assert!(unsafe { core::slice::from_raw_parts(ptr, 24) }
+            .iter()
+            .all(|byte| *byte == 0));
+
+        // Until they run out of memory regions
+        while MockKernel::user_alloc(&thread, 8, 8).is_ok() {}
+        assert!(matches!(
+            MockKernel::user_alloc(&thread, 8, 8),
+            Err(Kerr::ENOSPC)
+        ));
     }
 }
@@ -0,0 +1,74 @@
+//! Host stand-in for the CPU, to unit test the architecture independent parts
+//! of the kernel (timeouts, syscalls decoding, kernel objects).
+//!
+//! Threads can be created and moved between states, but never run: they keep
+//! the exception stack frame of the Cortex-M, in which syscall return values
+//! are written.
+
+use core::ffi::c_void;
+
+#[cfg(feature = "kernel-loadable-pie")]
+use super::elf_loader::{Lex, PICRegImpl};
+use super::{stack::Stack, thread::Thread, CpuVariant};
+use crate::cortex_m::arch::{__callee_context, __exception_sf};
+
+pub const MOCK_STACK_SIZE: usize = 256;
+
+// Flash boundaries, defined by the linker script on the target: a small
+// read-only area on the host
+#[cfg(not(feature = "hosted"))]
+core::arch::global_asm!(
+    ".pushsection .rodata.mock_flash, \"a\"",
+    ".globl _flash_start",
+    ".globl _flash_end",
+    "_flash_start:",
+    ".space 64",
+    "_flash_end:",
+    ".popsection",
+);
+
+pub struct MockCpu;
+
+impl CpuVariant for MockCpu {
+    const FCPU: u32 = 25_000_000;
+
+    type CalleeContext = __callee_context;
+    type InitStackFrame = __exception_sf;
+
+    #[cfg(feature = "kernel-loadable-pie")]
+    type PICRegImpl = MockPICReg;
+
+    unsafe fn switch_to_user(
+        _stack_ptr: *mut u32,
+        _process_regs: *mut Self::CalleeContext,
+    ) -> *mut u32 {
+        panic!("threads do not run on the host")
+    }
+}
+
+#[cfg(feature = "kernel-loadable-pie")]
+pub struct MockPICReg;
+
+#[cfg(feature = "kernel-loadable-pie")]
+impl PICRegImpl for MockPICReg {
+    unsafe fn invoke_loadable_entry(_lex: &Lex) -> u32 {
+        panic!("loadable ELF do not run on the host")
+    }
+}
+
+extern "C" fn mock_entry(_arg: *mut c_void) -> ! {
+    unreachable!("threads do not run on the host")
+}
+
+/// Creates a ready thread on the given stack, which must outlive it.
+pub fn mock_thread<'a>(stack: &mut Stack<MOCK_STACK_SIZE>) -> Thread<'a, MockCpu> {
+    let thread = Thread::init(&stack.get_info(), mock_entry, core::ptr::null_mut(), 0);
+    thread.set_ready();
+    thread
+}
+
+/// Returns the value the thread syscall returns with (r0 of its exception
+/// stack frame).
+pub fn syscall_return_value(thread: &Thread<MockCpu>) -> i32 {
+    unsafe { thread.stack_ptr.get().read() as i32 }
+}
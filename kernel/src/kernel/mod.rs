@@ -1,17 +1,25 @@
 use core::{ffi::c_void, fmt::Debug};
 
 use elf_loader::PICRegImpl;
+use stack::StackInfo;
+use syscalls::SVCCallParams;
 
 pub mod elf_loader;
 pub mod errno;
 pub mod idle;
 pub mod kernel;
+#[cfg(test)]
+pub(crate) mod mock;
 pub mod stack;
+pub mod stats;
 pub mod sync;
 pub mod syscalls;
+pub mod sysinfo;
 pub mod thread;
 pub mod timeout;
+pub mod trace;
 pub mod userspace;
+pub mod workqueue;
 
 // This function can be naked as it will never return !
 pub type ThreadEntry = extern "C" fn(*mut c_void) -> !;
@@ -21,6 +29,14 @@ pub trait ExceptionStackFrame: Sized {
     const SIZE_WORDS: usize = size_of::<Self>() / 4;
 
     fn initialize_at(stack_ptr: *mut u32, entry: ThreadEntry, arg0: *mut c_void);
+
+    /// Reads the arguments and the identifier of the syscall a thread called,
+    /// from the frame at the top of its stack.
+    ///
+    /// # Safety
+    ///
+    /// The thread must have left to the kernel by calling a syscall.
+    unsafe fn syscall_params(stack_ptr: *const u32) -> SVCCallParams;
 }
 
 pub trait CpuVariant {
@@ -36,4 +52,8 @@ pub trait CpuVariant {
         stack_ptr: *mut u32,
         process_regs: *mut Self::CalleeContext,
     ) -> *mut u32;
+
+    /// Initializes the context of a new thread running on the given stack
+    /// (e.g. its stack limit), the default context is kept otherwise.
+    fn init_context(_context: &mut Self::CalleeContext, _stack: &StackInfo) {}
 }
@@ -1,5 +1,13 @@
 use core::mem::MaybeUninit;
 
+/// Value written at the lowest word of a thread stack, an overwritten canary
+/// means the thread overflowed its stack.
+pub const STACK_CANARY: u32 = 0xDEADC0DE;
+
+/// Value the unused part of a thread stack is painted with, to compute the
+/// stack high-water mark.
+pub const STACK_PAINT: u32 = 0xAAAAAAAA;
+
 /// A fixed-size stack suitable for use as a task stack.
 ///
 /// The `Stack` struct represents a memory region that can be used as a stack for
@@ -9,7 +17,7 @@ use core::mem::MaybeUninit;
 ///
 /// # Examples
 ///
-/// ```rust
+/// ```rust,ignore
 /// use your_crate::{Stack, StackInfo};
 ///
 /// // Initialize a thread stack with 32 KB size in the `.noinit` section.
@@ -52,12 +60,13 @@ impl<const Z: usize> Stack<Z> {
 
     pub fn get_info(&mut self) -> StackInfo {
         StackInfo {
-            size: Z,
+            size: Z - (Z % 8),
             stack_end: self.stack_end_ptr(),
         }
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct StackInfo {
     pub size: usize,
     pub stack_end: *mut u32,
@@ -85,4 +94,46 @@ impl StackInfo {
             None
         }
     }
+
+    /// Lowest address of the stack, where the canary lives.
+    pub fn stack_start(&self) -> *mut u32 {
+        unsafe { self.stack_end.byte_sub(self.size) }
+    }
+
+    /// Paints the stack from its lowest address up to `sp` (excluded) and
+    /// writes the canary at the lowest word.
+    ///
+    /// # Safety
+    ///
+    /// The stack must not be in use below `sp`.
+    pub unsafe fn paint(&self, sp: *mut u32) {
+        let start = self.stack_start();
+
+        let mut ptr = start.add(1);
+        while ptr < sp {
+            ptr.write_volatile(STACK_PAINT);
+            ptr = ptr.add(1);
+        }
+
+        start.write_volatile(STACK_CANARY);
+    }
+
+    /// Checks whether the canary at the lowest word of the stack is intact.
+    pub fn canary_intact(&self) -> bool {
+        unsafe { self.stack_start().read_volatile() == STACK_CANARY }
+    }
+
+    /// Returns the maximum number of bytes the stack has been used, based on
+    /// the part of the stack still painted.
+    pub fn high_water_mark(&self) -> usize {
+        let start = self.stack_start();
+        let words = self.size / size_of::<u32>();
+
+        // Skip the canary
+        let untouched = (1..words)
+            .take_while(|&i| unsafe { start.add(i).read_volatile() } == STACK_PAINT)
+            .count();
+
+        self.size - (untouched + 1) * size_of::<u32>()
+    }
 }
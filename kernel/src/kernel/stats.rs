@@ -0,0 +1,191 @@
+//! Kernel statistics (`kernel-stats`).
+//!
+//! Per-thread statistics (CPU time, context switches, time spent pending,
+//! syscalls) are stored in the threads, kernel-wide statistics (syscalls per
+//! ID, kernel objects contention) are stored in the kernel. The idle time is
+//! the CPU time of the idle thread.
+//!
+//! Statistics are read by user threads with the `Stats` syscall, which copies
+//! one of the `*StatsInfo` structures below to the thread memory.
+
+#[cfg(feature = "kernel-stats")]
+use core::cell::Cell;
+
+use num_derive::FromPrimitive;
+
+/// Stats syscall r0 register layout: query and index
+pub const STATS_QUERY_MASK: u32 = 0xff;
+pub const STATS_INDEX_SHIFT: u32 = 8;
+
+/// Maximum number of distinct syscalls (ID and function) counted.
+pub const SYSCALL_STATS_SLOTS: usize = 24;
+
+#[repr(u32)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Clone, Copy)]
+pub enum StatsQuery {
+    /// Statistics of the current thread (`ThreadStatsInfo`)
+    Thread = 0,
+    /// Kernel-wide statistics (`KernelStatsInfo`)
+    Kernel = 1,
+    /// Counter of the n-th distinct syscall invoked (`SyscallStatsInfo`)
+    Syscall = 2,
+    /// Contention of the kernel object at the given index (`KobjStatsInfo`)
+    Kobj = 3,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadStatsInfo {
+    pub syscalls: u32,
+    /// Number of times the thread has been switched to
+    pub switches: u32,
+    /// CPU time: number of whole ticks and remaining cycles
+    pub cpu_ticks: u32,
+    pub cpu_cycles: u32,
+    /// Number of ticks the thread spent pending
+    pub pending_ticks: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KernelStatsInfo {
+    /// Time spent in the idle thread: number of whole ticks and remaining cycles
+    pub idle_ticks: u32,
+    pub idle_cycles: u32,
+    /// Total number of context switches to user threads
+    pub switches: u32,
+    /// Total number of syscalls
+    pub syscalls: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SyscallStatsInfo {
+    pub syscall_id: u32,
+    /// Function within the syscall ID (r3)
+    pub sub_id: u32,
+    pub count: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KobjStatsInfo {
+    /// Number of times a thread had to pend on the kernel object
+    pub contention: u32,
+}
+
+#[cfg(feature = "kernel-stats")]
+#[derive(Default)]
+pub struct ThreadStats {
+    pub(super) syscalls: Cell<u32>,
+    pub(super) switches: Cell<u32>,
+    /// CPU time in cycles
+    pub(super) cpu_cycles: Cell<u64>,
+    pub(super) pending_ticks: Cell<u32>,
+}
+
+#[cfg(feature = "kernel-stats")]
+impl ThreadStats {
+    /// Accounts a run of the thread, which lasted `cycles` CPU cycles.
+    pub(super) fn account_run(&self, cycles: u64) {
+        self.switches.set(self.switches.get() + 1);
+        self.cpu_cycles.set(self.cpu_cycles.get() + cycles);
+    }
+
+    pub(super) fn account_syscall(&self) {
+        self.syscalls.set(self.syscalls.get() + 1);
+    }
+
+    pub(super) fn account_pending_tick(&self) {
+        self.pending_ticks.set(self.pending_ticks.get() + 1);
+    }
+
+    /// Converts the statistics, splitting the CPU time with the given number
+    /// of cycles per tick.
+    pub fn info(&self, period_cycles: u32) -> ThreadStatsInfo {
+        let (cpu_ticks, cpu_cycles) = split_cycles(self.cpu_cycles.get(), period_cycles);
+
+        ThreadStatsInfo {
+            syscalls: self.syscalls.get(),
+            switches: self.switches.get(),
+            cpu_ticks,
+            cpu_cycles,
+            pending_ticks: self.pending_ticks.get(),
+        }
+    }
+}
+
+/// Splits a number of cycles into whole ticks and remaining cycles.
+pub fn split_cycles(cycles: u64, period_cycles: u32) -> (u32, u32) {
+    let period = period_cycles.max(1) as u64;
+
+    ((cycles / period) as u32, (cycles % period) as u32)
+}
+
+/// Kernel-wide statistics, `K` is the maximum number of kernel objects.
+#[cfg(feature = "kernel-stats")]
+pub struct KernelStats<const K: usize> {
+    /// Distinct syscalls counters, in order of first invocation
+    syscalls: [Option<SyscallStatsInfo>; SYSCALL_STATS_SLOTS],
+    kobj_contention: [u32; K],
+}
+
+#[cfg(feature = "kernel-stats")]
+impl<const K: usize> KernelStats<K> {
+    pub const fn new() -> Self {
+        KernelStats {
+            syscalls: [None; SYSCALL_STATS_SLOTS],
+            kobj_contention: [0; K],
+        }
+    }
+
+    /// Counts a syscall invocation, syscalls are ignored once all slots are used.
+    pub fn count_syscall(&mut self, syscall_id: u8, sub_id: u32) {
+        let syscall_id = syscall_id as u32;
+
+        for slot in self.syscalls.iter_mut() {
+            match slot {
+                Some(counter) if counter.syscall_id == syscall_id && counter.sub_id == sub_id => {
+                    counter.count += 1;
+                    return;
+                }
+                Some(_) => {}
+                None => {
+                    *slot = Some(SyscallStatsInfo {
+                        syscall_id,
+                        sub_id,
+                        count: 1,
+                    });
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Counts a thread pending on a kernel object.
+    pub fn count_contention(&mut self, kobj: i32) {
+        if let Some(count) = self.kobj_contention.get_mut(kobj as usize) {
+            *count += 1;
+        }
+    }
+
+    /// Returns the counter of the n-th distinct syscall invoked.
+    pub fn syscall(&self, index: usize) -> Option<SyscallStatsInfo> {
+        self.syscalls.get(index).copied().flatten()
+    }
+
+    pub fn syscalls(&self) -> impl Iterator<Item = &SyscallStatsInfo> {
+        self.syscalls.iter().flatten()
+    }
+
+    pub fn contention(&self, kobj: usize) -> Option<u32> {
+        self.kobj_contention.get(kobj).copied()
+    }
+}
+
+#[cfg(feature = "kernel-stats")]
+impl<const K: usize> Default for KernelStats<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
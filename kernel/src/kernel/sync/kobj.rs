@@ -6,6 +6,8 @@
 
 use crate::{
     kernel::{
+        errno::{KResult, Kerr},
+        syscalls::SyncPrimitiveType,
         thread::{Thread, Waitqueue},
         timeout::TimeoutInstant,
         CpuVariant,
@@ -75,6 +77,40 @@ pub trait KernelObjectTrait<'a, CPU: CpuVariant> {
     /// * `Err(SwapData)` if the provided `swap_data` could not be used, returning it back.
     fn release(&mut self, swap_data: SwapData) -> Result<(), SwapData>;
 
+    /// Releases the synchronization primitive on behalf of a thread.
+    ///
+    /// A primitive with an owner (e.g. a locked mutex) can only be released by it.
+    ///
+    /// # Arguments
+    ///
+    /// * `thread` - The thread releasing the primitive.
+    /// * `swap_data` - The data to released to the primitive.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the release was successful.
+    /// * `Err(Kerr::EPERM)` if the primitive is owned by another thread.
+    /// * `Err(Kerr::NotSupported)` if the provided `swap_data` could not be used.
+    fn release_by(&mut self, thread: &'a Thread<'a, CPU>, swap_data: SwapData) -> KResult<()> {
+        if self
+            .owner()
+            .is_some_and(|owner| !core::ptr::eq(owner, thread))
+        {
+            return Err(Kerr::EPERM);
+        }
+
+        self.release(swap_data).map_err(|_| Kerr::NotSupported)
+    }
+
+    /// Returns the thread owning the synchronization primitive, if any.
+    fn owner(&self) -> Option<&'a Thread<'a, CPU>>;
+
+    /// Returns the type of the synchronization primitive.
+    fn kind(&self) -> SyncPrimitiveType;
+
+    /// Returns the number of threads waiting on the kernel object.
+    fn waiters(&self) -> usize;
+
     // TODO: Cancel all threads waiting on the kernel object.
     // fn cancel(&mut self);
 }
@@ -185,11 +221,19 @@ impl<'a, S: SyncPrimitive<'a, CPU>, CPU: CpuVariant> KernelObjectTrait<'a, CPU>
 
             // Try to release or notify the primitive.
             swap = match self.primitive.release(swap).map_err(|s| s.into())? {
-                ReleaseOutcome::Released => break, // The primitive has been released.
+                ReleaseOutcome::Released => {
+                    // The primitive has been released to the unpended thread.
+                    self.primitive.handover(unpended_thread);
+                    return Ok(());
+                }
                 ReleaseOutcome::Notified(swap) => swap, // Continue notifying next thread.
             }
         }
 
+        // No thread left waiting, the primitive keeps the released value
+        // (e.g. unlocked mutex, semaphore count, signal value).
+        self.primitive.release(swap).map_err(|s| s.into())?;
+
         Ok(())
     }
 
@@ -203,4 +247,259 @@ impl<'a, S: SyncPrimitive<'a, CPU>, CPU: CpuVariant> KernelObjectTrait<'a, CPU>
     fn remove_thread(&mut self, thread: &'a Thread<'a, CPU>) {
         self.waitqueue.remove(thread);
     }
+
+    /// Returns the thread owning the synchronization primitive, if any.
+    fn owner(&self) -> Option<&'a Thread<'a, CPU>> {
+        self.primitive.owner()
+    }
+
+    fn kind(&self) -> SyncPrimitiveType {
+        S::KIND
+    }
+
+    fn waiters(&self) -> usize {
+        self.waitqueue.iter().count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::kernel::{
+        mock::{mock_thread, syscall_return_value, MockCpu, MOCK_STACK_SIZE},
+        stack::Stack,
+        sync::{Mutex, Ownership, Semaphore, Signal, SignalValue},
+    };
+
+    const NO_WAIT: TimeoutInstant = TimeoutInstant::At(0);
+    const FOREVER: TimeoutInstant = TimeoutInstant::Never;
+
+    fn stacks<const N: usize>() -> [Stack<MOCK_STACK_SIZE>; N] {
+        core::array::from_fn(|_| Stack::zeroed())
+    }
+
+    #[test]
+    fn test_mutex() {
+        let mut stacks = stacks::<3>();
+        let [s1, s2, s3] = &mut stacks;
+        let (t1, t2, t3) = (mock_thread(s1), mock_thread(s2), mock_thread(s3));
+
+        let mut kobj = KernelObject::<_, MockCpu>::new(7, Mutex::new());
+        assert_eq!(kobj.kind(), SyncPrimitiveType::Mutex);
+
+        assert!(matches!(
+            kobj.acquire(&t1, FOREVER),
+            AcquireOutcome::Obtained(SwapData::Ownership)
+        ));
+        assert!(kobj.owner().is_some_and(|owner| core::ptr::eq(owner, &t1)));
+
+        assert!(matches!(
+            kobj.acquire(&t2, NO_WAIT),
+            AcquireOutcome::NotObtained
+        ));
+        assert!(matches!(
+            kobj.acquire(&t2, FOREVER),
+            AcquireOutcome::Pending
+        ));
+        assert!(matches!(
+            kobj.acquire(&t3, TimeoutInstant::At(100)),
+            AcquireOutcome::Pending
+        ));
+        assert_eq!(kobj.waiters(), 2);
+        assert_eq!(t2.lives_in_waitqueue(), Some(7));
+        assert!(!t2.is_ready());
+        assert!(t3.has_timed_out(100));
+
+        // Ownership is handed over to the first waiter
+        assert!(kobj.release(Ownership.into()).is_ok());
+        assert!(t2.is_ready());
+        assert_eq!(syscall_return_value(&t2), 0);
+        assert!(kobj.owner().is_some_and(|owner| core::ptr::eq(owner, &t2)));
+        assert_eq!(kobj.waiters(), 1);
+
+        // The second waiter times out
+        kobj.remove_thread(&t3);
+        t3.unpend_timeout();
+        assert_eq!(kobj.waiters(), 0);
+        assert_eq!(syscall_return_value(&t3), Kerr::TimedOut as i32);
+
+        assert!(kobj.release(Ownership.into()).is_ok());
+        assert!(kobj.owner().is_none());
+
+        // Not locked anymore
+        assert!(kobj.release(Ownership.into()).is_err());
+        // Wrong kind of data
+        assert!(kobj.release(SwapData::Empty).is_err());
+    }
+
+    #[test]
+    fn test_mutex_release_by_owner() {
+        let mut stacks = stacks::<3>();
+        let [s1, s2, s3] = &mut stacks;
+        let (t1, t2, t3) = (mock_thread(s1), mock_thread(s2), mock_thread(s3));
+
+        let mut kobj = KernelObject::<_, MockCpu>::new(2, Mutex::new());
+        assert!(matches!(
+            kobj.acquire(&t1, FOREVER),
+            AcquireOutcome::Obtained(_)
+        ));
+        assert!(matches!(
+            kobj.acquire(&t2, FOREVER),
+            AcquireOutcome::Pending
+        ));
+
+        // Only the owner can release the mutex, the waiter stays pending
+        assert!(matches!(
+            kobj.release_by(&t3, Ownership.into()),
+            Err(Kerr::EPERM)
+        ));
+        assert!(kobj.owner().is_some_and(|owner| core::ptr::eq(owner, &t1)));
+        assert!(!t2.is_ready());
+
+        assert!(matches!(kobj.release_by(&t1, Ownership.into()), Ok(())));
+        assert!(kobj.owner().is_some_and(|owner| core::ptr::eq(owner, &t2)));
+        assert!(matches!(
+            kobj.release_by(&t1, Ownership.into()),
+            Err(Kerr::EPERM)
+        ));
+
+        // Released once by the new owner, not locked anymore
+        assert!(matches!(kobj.release_by(&t2, Ownership.into()), Ok(())));
+        assert!(kobj.owner().is_none());
+        assert!(matches!(
+            kobj.release_by(&t2, Ownership.into()),
+            Err(Kerr::NotSupported)
+        ));
+    }
+
+    #[test]
+    fn test_release_handover() {
+        let mut stacks = stacks::<3>();
+        let [s1, s2, s3] = &mut stacks;
+        let (t1, t2, t3) = (mock_thread(s1), mock_thread(s2), mock_thread(s3));
+
+        // A mutex released to a waiter is not released a second time
+        let mut kobj = KernelObject::<_, MockCpu>::new(3, Mutex::new());
+        assert!(matches!(
+            kobj.acquire(&t1, FOREVER),
+            AcquireOutcome::Obtained(_)
+        ));
+        for thread in [&t2, &t3] {
+            assert!(matches!(
+                kobj.acquire(thread, FOREVER),
+                AcquireOutcome::Pending
+            ));
+        }
+        assert!(kobj.release(Ownership.into()).is_ok());
+        assert!(t2.is_ready());
+        assert!(!t3.is_ready());
+        assert_eq!(kobj.waiters(), 1);
+        assert!(kobj.owner().is_some_and(|owner| core::ptr::eq(owner, &t2)));
+
+        // A semaphore give handed over to a waiter doesn't increase the count
+        let mut kobj = KernelObject::<_, MockCpu>::new(4, Semaphore::new(0, 1));
+        assert!(matches!(
+            kobj.acquire(&t1, FOREVER),
+            AcquireOutcome::Pending
+        ));
+        assert!(kobj.release(SwapData::Empty).is_ok());
+        assert!(t1.is_ready());
+        assert!(matches!(
+            kobj.acquire(&t2, NO_WAIT),
+            AcquireOutcome::NotObtained
+        ));
+
+        // With no waiter, the give is kept by the semaphore
+        assert!(kobj.release(SwapData::Empty).is_ok());
+        assert!(matches!(
+            kobj.acquire(&t2, NO_WAIT),
+            AcquireOutcome::Obtained(_)
+        ));
+    }
+
+    #[test]
+    fn test_semaphore() {
+        let mut stacks = stacks::<2>();
+        let [s1, s2] = &mut stacks;
+        let (t1, t2) = (mock_thread(s1), mock_thread(s2));
+
+        let mut kobj = KernelObject::<_, MockCpu>::new(0, Semaphore::new(1, 2));
+        assert_eq!(kobj.kind(), SyncPrimitiveType::Semaphore);
+
+        assert!(matches!(
+            kobj.acquire(&t1, NO_WAIT),
+            AcquireOutcome::Obtained(SwapData::Empty)
+        ));
+        assert!(matches!(
+            kobj.acquire(&t1, NO_WAIT),
+            AcquireOutcome::NotObtained
+        ));
+
+        // The count saturates at the maximum
+        for _ in 0..3 {
+            assert!(kobj.release(SwapData::Empty).is_ok());
+        }
+        for _ in 0..2 {
+            assert!(matches!(
+                kobj.acquire(&t1, NO_WAIT),
+                AcquireOutcome::Obtained(_)
+            ));
+        }
+        assert!(matches!(
+            kobj.acquire(&t1, NO_WAIT),
+            AcquireOutcome::NotObtained
+        ));
+
+        // The released count is taken by the waiter
+        assert!(matches!(
+            kobj.acquire(&t2, FOREVER),
+            AcquireOutcome::Pending
+        ));
+        assert!(kobj.release(SwapData::Empty).is_ok());
+        assert!(t2.is_ready());
+        assert_eq!(syscall_return_value(&t2), 0);
+        assert!(matches!(
+            kobj.acquire(&t1, NO_WAIT),
+            AcquireOutcome::NotObtained
+        ));
+    }
+
+    #[test]
+    fn test_signal() {
+        let mut stacks = stacks::<3>();
+        let [s1, s2, s3] = &mut stacks;
+        let (t1, t2, t3) = (mock_thread(s1), mock_thread(s2), mock_thread(s3));
+
+        let mut kobj = KernelObject::<_, MockCpu>::new(1, Signal::new());
+        assert_eq!(kobj.kind(), SyncPrimitiveType::Signal);
+
+        assert!(matches!(
+            kobj.acquire(&t1, NO_WAIT),
+            AcquireOutcome::NotObtained
+        ));
+        assert!(matches!(
+            kobj.acquire(&t1, FOREVER),
+            AcquireOutcome::Pending
+        ));
+        assert!(matches!(
+            kobj.acquire(&t2, FOREVER),
+            AcquireOutcome::Pending
+        ));
+
+        // All waiters are notified
+        assert!(kobj.release(SignalValue::new(42).into()).is_ok());
+        for thread in [&t1, &t2] {
+            assert!(thread.is_ready());
+            assert_eq!(syscall_return_value(thread), 42);
+        }
+        assert_eq!(kobj.waiters(), 0);
+
+        // The signal keeps its value
+        assert!(matches!(
+            kobj.acquire(&t3, NO_WAIT),
+            AcquireOutcome::Obtained(SwapData::Signal(value)) if value == SignalValue::new(42)
+        ));
+
+        assert!(kobj.release(SwapData::Ownership).is_err());
+    }
 }
@@ -1,7 +1,7 @@
 //! Provides a mutual exclusion primitive (`Mutex`) for synchronizing access to shared data
 //! between threads in a kernel environment.
 
-use crate::kernel::{thread::Thread, CpuVariant};
+use crate::kernel::{syscalls::SyncPrimitiveType, thread::Thread, CpuVariant};
 
 use super::{traits::ReleaseOutcome, SwapData, Swappable, SyncPrimitive};
 
@@ -58,6 +58,7 @@ impl<'a, CPU: CpuVariant> Default for Mutex<'a, CPU> {
 
 impl<'a, CPU: CpuVariant> SyncPrimitive<'a, CPU> for Mutex<'a, CPU> {
     type Swap = Ownership;
+    const KIND: SyncPrimitiveType = SyncPrimitiveType::Mutex;
 
     /// Releases the mutex, making it available for other threads to acquire.
     ///
@@ -98,4 +99,14 @@ impl<'a, CPU: CpuVariant> SyncPrimitive<'a, CPU> for Mutex<'a, CPU> {
         }
         // TODO, what to do if the mutex is already owned by the same thread?
     }
+
+    /// Transfers the mutex ownership to the thread unpended on release.
+    fn handover(&mut self, thread: &'a Thread<'a, CPU>) {
+        self.owner = Some(thread);
+    }
+
+    /// Returns the thread owning the mutex, if any.
+    fn owner(&self) -> Option<&'a Thread<'a, CPU>> {
+        self.owner
+    }
 }
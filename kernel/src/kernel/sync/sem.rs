@@ -1,4 +1,4 @@
-use crate::kernel::{sync::SyncPrimitive, thread::Thread, CpuVariant};
+use crate::kernel::{sync::SyncPrimitive, syscalls::SyncPrimitiveType, thread::Thread, CpuVariant};
 
 use super::traits::ReleaseOutcome;
 
@@ -15,9 +15,10 @@ impl Semaphore {
 
 impl<'a, CPU: CpuVariant> SyncPrimitive<'a, CPU> for Semaphore {
     type Swap = ();
+    const KIND: SyncPrimitiveType = SyncPrimitiveType::Semaphore;
 
     fn release(&mut self, _released: ()) -> Result<ReleaseOutcome<()>, ()> {
-        self.cur = (self.cur + 1).max(self.max);
+        self.cur = (self.cur + 1).min(self.max);
 
         Ok(ReleaseOutcome::Released)
     }
@@ -30,4 +31,9 @@ impl<'a, CPU: CpuVariant> SyncPrimitive<'a, CPU> for Semaphore {
             Some(())
         }
     }
+
+    fn handover(&mut self, _thread: &'a Thread<'a, CPU>) {
+        // The released count is taken by the unpended thread
+        self.cur = self.cur.saturating_sub(1);
+    }
 }
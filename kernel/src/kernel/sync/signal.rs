@@ -1,4 +1,4 @@
-use crate::kernel::{thread::Thread, CpuVariant};
+use crate::kernel::{syscalls::SyncPrimitiveType, thread::Thread, CpuVariant};
 
 use super::{traits::ReleaseOutcome, SwapData, Swappable, SyncPrimitive};
 
@@ -47,6 +47,7 @@ impl Signal {
 
 impl<'a, CPU: CpuVariant> SyncPrimitive<'a, CPU> for Signal {
     type Swap = SignalValue;
+    const KIND: SyncPrimitiveType = SyncPrimitiveType::Signal;
 
     fn release(
         &mut self,
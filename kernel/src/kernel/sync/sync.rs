@@ -1,4 +1,4 @@
-use crate::kernel::{thread::Thread, CpuVariant};
+use crate::kernel::{syscalls::SyncPrimitiveType, thread::Thread, CpuVariant};
 
 use super::traits::{ReleaseOutcome, SyncPrimitive};
 
@@ -7,6 +7,7 @@ pub struct Sync;
 
 impl<'a, CPU: CpuVariant> SyncPrimitive<'a, CPU> for Sync {
     type Swap = ();
+    const KIND: SyncPrimitiveType = SyncPrimitiveType::Sync;
 
     fn release(&mut self, _released: ()) -> Result<ReleaseOutcome<()>, ()> {
         Ok(ReleaseOutcome::Notified(()))
@@ -1,7 +1,7 @@
 //! Provides traits and implementations for synchronization primitives.
 
 use super::SwapData;
-use crate::kernel::{thread::Thread, CpuVariant};
+use crate::kernel::{syscalls::SyncPrimitiveType, thread::Thread, CpuVariant};
 
 /// A trait for types that can be passed between threads during synchronization.
 ///
@@ -54,6 +54,9 @@ pub trait SyncPrimitive<'a, CPU: CpuVariant> {
     /// This type must implement the `Swappable` trait.
     type Swap: Swappable;
 
+    /// The type of the primitive, as created by userspace.
+    const KIND: SyncPrimitiveType;
+
     // type Init: Default + Clone + Copy;
 
     // fn init(&self) -> Self::Init {
@@ -94,6 +97,20 @@ pub trait SyncPrimitive<'a, CPU: CpuVariant> {
     /// - `Err(Self::Swap)` if the primitive was not released successfully.
     fn release(&mut self, released: Self::Swap) -> Result<ReleaseOutcome<Self::Swap>, Self::Swap>;
 
+    /// Hands the primitive over to a thread which was waiting for it and has
+    /// been unpended with the released swap value.
+    ///
+    /// # Parameters
+    ///
+    /// - `thread`: The thread now holding the primitive.
+    fn handover(&mut self, _thread: &'a Thread<'a, CPU>) {}
+
+    /// Returns the thread currently holding the primitive, if the primitive
+    /// has a notion of ownership (e.g. mutex).
+    fn owner(&self) -> Option<&'a Thread<'a, CPU>> {
+        None
+    }
+
     // fn cancel(&mut self) {
     //     todo!()
     // }
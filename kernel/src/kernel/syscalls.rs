@@ -1,7 +1,14 @@
+use core::ffi::c_void;
+
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 
-use super::timeout::Timeout;
+use super::{
+    stats::{StatsQuery, STATS_INDEX_SHIFT, STATS_QUERY_MASK},
+    sysinfo::SysInfoQuery,
+    timeout::Timeout,
+    workqueue::Work,
+};
 
 #[derive(Debug)]
 pub struct SVCCallParams {
@@ -44,8 +51,34 @@ pub enum KernelSyscallId {
     MemoryFree = 9,
     // Clone the current thread into a new thread
     Fork = 10,
-    // // Uptime
-    // Uptime = 100,
+    // Submit a work item to the work queue
+    WorkSubmit = 11,
+    // Retrieve the next work item to execute (work queue thread only)
+    WorkNext = 12,
+    // Stack high-water mark of the current thread (in bytes)
+    StackUsage = 13,
+    // Read kernel statistics (kernel-stats feature)
+    Stats = 14,
+    // Copy a snapshot of the kernel, its threads or its kernel objects
+    SysInfo = 15,
+    // Stop a thread given its identifier
+    Kill = 16,
+    // Load a registered ELF image and start its thread
+    Load = 17,
+    // Reset the system
+    Reboot = 18,
+    // Write the kernel trace buffer to the stdio UART (kernel-trace feature)
+    TraceDump = 19,
+    // Exit the emulator through semihosting with a status code
+    Exit = 20,
+    // Time elapsed since boot in milliseconds (64 bits)
+    UptimeMs = 100,
+    // Time elapsed since boot in system ticks (64 bits)
+    UptimeTicks = 101,
+    // Hardware cycle counter (32 bits)
+    CycleGet = 102,
+    // Time elapsed since boot in microseconds (64 bits)
+    UptimeUs = 103,
 }
 
 #[repr(u32)]
@@ -77,7 +110,8 @@ impl Syscall {
             SyscallId::Kernel => KernelSyscallId::from_u32(params.r3).and_then(|kernel_syscall| {
                 match kernel_syscall {
                     KernelSyscallId::Yield => Some(KernelSyscall::Yield),
-                    KernelSyscallId::Sleep => Some(KernelSyscall::Sleep { ms: params.r0 }),
+                    KernelSyscallId::Sleep => Timeout::from_raw(params.r0, params.r1)
+                        .map(|timeout| KernelSyscall::Sleep { timeout }),
                     KernelSyscallId::SyncCreate => {
                         SyncPrimitiveType::from_u32(params.r2).map(|sync_prim_type| {
                             KernelSyscall::SyncCreate {
@@ -105,13 +139,17 @@ impl Syscall {
                         })
                     }
                     KernelSyscallId::Pend => {
-                        SyncPrimitiveType::from_u32(params.r2).map(|sync_prim_type| {
-                            KernelSyscall::Pend {
-                                timeout: Timeout::try_from(params.r0 as i32).unwrap_or_default(),
+                        // r2 holds the primitive type in the low byte and the timeout kind above
+                        SyncPrimitiveType::from_u32(params.r2 & PEND_PRIM_MASK).map(
+                            |sync_prim_type| KernelSyscall::Pend {
+                                timeout: Timeout::from_raw(
+                                    params.r0,
+                                    params.r2 >> PEND_TIMEOUT_KIND_SHIFT,
+                                ),
                                 kobj: params.r1 as i32,
                                 prim: sync_prim_type,
-                            }
-                        })
+                            },
+                        )
                     }
                     KernelSyscallId::Cancel => {
                         SyncPrimitiveType::from_u32(params.r2).map(|sync_prim_type| {
@@ -130,6 +168,45 @@ impl Syscall {
                         ptr: params.r0 as *mut u8,
                     }),
                     KernelSyscallId::Fork => Some(KernelSyscall::Fork),
+                    KernelSyscallId::WorkSubmit => Some(KernelSyscall::WorkSubmit {
+                        handler: params.r0 as *const c_void,
+                        arg: params.r1 as *mut c_void,
+                        delay: Timeout::try_from(params.r2 as i32).unwrap_or_default(),
+                    }),
+                    KernelSyscallId::WorkNext => Some(KernelSyscall::WorkNext {
+                        slot: params.r0 as *mut Option<Work>,
+                    }),
+                    KernelSyscallId::StackUsage => Some(KernelSyscall::StackUsage),
+                    KernelSyscallId::Stats => {
+                        // r0 holds the query in the low byte and the index above
+                        StatsQuery::from_u32(params.r0 & STATS_QUERY_MASK).map(|query| {
+                            KernelSyscall::Stats {
+                                query,
+                                index: params.r0 >> STATS_INDEX_SHIFT,
+                                ptr: params.r1 as *mut u8,
+                                len: params.r2 as usize,
+                            }
+                        })
+                    }
+                    KernelSyscallId::SysInfo => {
+                        SysInfoQuery::from_u32(params.r0).map(|query| KernelSyscall::SysInfo {
+                            query,
+                            ptr: params.r1 as *mut u8,
+                            len: params.r2 as usize,
+                        })
+                    }
+                    KernelSyscallId::Kill => Some(KernelSyscall::Kill { tid: params.r0 }),
+                    KernelSyscallId::Load => Some(KernelSyscall::Load {
+                        name: params.r0 as *const u8,
+                        len: params.r1 as usize,
+                    }),
+                    KernelSyscallId::Reboot => Some(KernelSyscall::Reboot),
+                    KernelSyscallId::TraceDump => Some(KernelSyscall::TraceDump),
+                    KernelSyscallId::Exit => Some(KernelSyscall::Exit { status: params.r0 }),
+                    KernelSyscallId::UptimeMs => Some(KernelSyscall::UptimeMs),
+                    KernelSyscallId::UptimeTicks => Some(KernelSyscall::UptimeTicks),
+                    KernelSyscallId::CycleGet => Some(KernelSyscall::CycleGet),
+                    KernelSyscallId::UptimeUs => Some(KernelSyscall::UptimeUs),
                 }
                 .map(Syscall::Kernel)
             }),
@@ -168,6 +245,10 @@ impl Syscall {
     }
 }
 
+/// Pend syscall r2 register layout: primitive type and timeout kind
+pub const PEND_PRIM_MASK: u32 = 0xff;
+pub const PEND_TIMEOUT_KIND_SHIFT: u32 = 8;
+
 #[repr(u32)]
 #[derive(Debug, FromPrimitive, PartialEq, Eq, Clone, Copy)]
 pub enum SyncPrimitiveType {
@@ -189,7 +270,7 @@ pub enum SyncPrimitiveCreate {
 pub enum KernelSyscall {
     Yield,
     Sleep {
-        ms: u32,
+        timeout: Timeout,
     },
     SyncCreate {
         prim: SyncPrimitiveCreate,
@@ -202,7 +283,7 @@ pub enum KernelSyscall {
     Pend {
         prim: SyncPrimitiveType,
         kobj: i32,
-        timeout: Timeout,
+        timeout: Option<Timeout>, // None if the timeout kind is invalid
     },
     Cancel {
         prim: SyncPrimitiveType,
@@ -217,6 +298,42 @@ pub enum KernelSyscall {
     },
     Fork,
     Stop,
+    WorkSubmit {
+        handler: *const c_void,
+        arg: *mut c_void,
+        delay: Timeout,
+    },
+    WorkNext {
+        slot: *mut Option<Work>,
+    },
+    StackUsage,
+    Stats {
+        query: StatsQuery,
+        index: u32,
+        ptr: *mut u8,
+        len: usize,
+    },
+    SysInfo {
+        query: SysInfoQuery,
+        ptr: *mut u8,
+        len: usize,
+    },
+    Kill {
+        tid: u32,
+    },
+    Load {
+        name: *const u8,
+        len: usize,
+    },
+    Reboot,
+    TraceDump,
+    Exit {
+        status: u32,
+    },
+    UptimeMs,
+    UptimeTicks,
+    CycleGet,
+    UptimeUs,
 }
 
 #[derive(Debug)]
@@ -237,3 +354,144 @@ pub enum IoSyscall {
     },
     Read1,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kernel_params(r0: u32, r1: u32, r2: u32, id: KernelSyscallId) -> SVCCallParams {
+        SVCCallParams {
+            r0,
+            r1,
+            r2,
+            r3: id as u32,
+            syscall_id: SyscallId::Kernel as u8,
+        }
+    }
+
+    #[test]
+    fn test_unknown_ids() {
+        let params = SVCCallParams {
+            r0: 0,
+            r1: 0,
+            r2: 0,
+            r3: 0,
+            syscall_id: 42,
+        };
+        assert!(Syscall::from_svc_params(params).is_none());
+
+        let params = SVCCallParams {
+            r0: 0,
+            r1: 0,
+            r2: 0,
+            r3: 99,
+            syscall_id: SyscallId::Kernel as u8,
+        };
+        assert!(Syscall::from_svc_params(params).is_none());
+
+        let params = SVCCallParams {
+            r0: 0,
+            r1: 0,
+            r2: 0,
+            r3: 2,
+            syscall_id: SyscallId::Io as u8,
+        };
+        assert!(Syscall::from_svc_params(params).is_none());
+    }
+
+    #[test]
+    fn test_sleep() {
+        let (r0, r1) = Timeout::at_ms(500).to_raw();
+        let syscall = Syscall::from_svc_params(kernel_params(r0, r1, 0, KernelSyscallId::Sleep));
+        assert!(matches!(
+            syscall,
+            Some(Syscall::Kernel(KernelSyscall::Sleep {
+                timeout: Timeout::AtMs(500)
+            }))
+        ));
+
+        // Invalid timeout kind
+        let syscall = Syscall::from_svc_params(kernel_params(10, 7, 0, KernelSyscallId::Sleep));
+        assert!(syscall.is_none());
+    }
+
+    #[test]
+    fn test_sync_create() {
+        let params = kernel_params(
+            1,
+            4,
+            SyncPrimitiveType::Semaphore as u32,
+            KernelSyscallId::SyncCreate,
+        );
+        assert!(matches!(
+            Syscall::from_svc_params(params),
+            Some(Syscall::Kernel(KernelSyscall::SyncCreate {
+                prim: SyncPrimitiveCreate::Semaphore { init: 1, max: 4 }
+            }))
+        ));
+
+        let params = kernel_params(0, 0, 17, KernelSyscallId::SyncCreate);
+        assert!(Syscall::from_svc_params(params).is_none());
+    }
+
+    #[test]
+    fn test_pend() {
+        let (value, kind) = Timeout::from_us(250).to_raw();
+        let r2 = SyncPrimitiveType::Mutex as u32 | kind << PEND_TIMEOUT_KIND_SHIFT;
+        let params = kernel_params(value, 3, r2, KernelSyscallId::Pend);
+        assert!(matches!(
+            Syscall::from_svc_params(params),
+            Some(Syscall::Kernel(KernelSyscall::Pend {
+                prim: SyncPrimitiveType::Mutex,
+                kobj: 3,
+                timeout: Some(Timeout::DurationUs(250)),
+            }))
+        ));
+
+        // Invalid timeout kinds are decoded, the kernel rejects them
+        let r2 = SyncPrimitiveType::Signal as u32 | 9 << PEND_TIMEOUT_KIND_SHIFT;
+        let params = kernel_params(10, 1, r2, KernelSyscallId::Pend);
+        assert!(matches!(
+            Syscall::from_svc_params(params),
+            Some(Syscall::Kernel(KernelSyscall::Pend {
+                prim: SyncPrimitiveType::Signal,
+                kobj: 1,
+                timeout: None,
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_stats() {
+        let r0 = StatsQuery::Kobj as u32 | 5 << STATS_INDEX_SHIFT;
+        let params = kernel_params(r0, 0x2000_0000, 16, KernelSyscallId::Stats);
+        assert!(matches!(
+            Syscall::from_svc_params(params),
+            Some(Syscall::Kernel(KernelSyscall::Stats {
+                query: StatsQuery::Kobj,
+                index: 5,
+                len: 16,
+                ..
+            }))
+        ));
+    }
+
+    #[test]
+    fn test_io_write() {
+        let params = SVCCallParams {
+            r0: 0x2000_0000,
+            r1: 12,
+            r2: 1,
+            r3: IoSyscallId::Write as u32,
+            syscall_id: SyscallId::Io as u8,
+        };
+        assert!(matches!(
+            Syscall::from_svc_params(params),
+            Some(Syscall::Io(IoSyscall::Print {
+                len: 12,
+                newline: true,
+                ..
+            }))
+        ));
+    }
+}
@@ -0,0 +1,149 @@
+//! System introspection: kernel version and features, threads and kernel
+//! objects snapshots.
+//!
+//! User threads read them with the `SysInfo` syscall, which copies the
+//! `VersionInfo` structure or an array of `ThreadInfo` / `KobjInfo` to the
+//! thread memory.
+
+use num_derive::FromPrimitive;
+
+use super::syscalls::SyncPrimitiveType;
+
+/// Maximum length of a thread name, longer names are truncated.
+pub const THREAD_NAME_LEN: usize = 16;
+
+/// Length of the kernel version string.
+pub const VERSION_LEN: usize = 16;
+
+/// Kernel features (`VersionInfo::features` bits)
+pub const FEATURE_WORKQUEUE: u32 = 1 << 0;
+pub const FEATURE_DEADLOCK_DETECTION: u32 = 1 << 1;
+pub const FEATURE_MPU: u32 = 1 << 2;
+pub const FEATURE_STATS: u32 = 1 << 3;
+pub const FEATURE_DEBUG: u32 = 1 << 4;
+pub const FEATURE_LOADABLE_PIE: u32 = 1 << 5;
+pub const FEATURE_TRACE: u32 = 1 << 6;
+
+#[repr(u32)]
+#[derive(Debug, FromPrimitive, PartialEq, Eq, Clone, Copy)]
+pub enum SysInfoQuery {
+    /// Kernel version and features (`VersionInfo`)
+    Version = 0,
+    /// All threads (array of `ThreadInfo`)
+    Threads = 1,
+    /// All live kernel objects (array of `KobjInfo`)
+    Kobjs = 2,
+    /// Kernel heap usage (`MemoryInfo`)
+    Memory = 3,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct VersionInfo {
+    /// Kernel version, NUL padded
+    pub version: [u8; VERSION_LEN],
+    /// Enabled kernel features (`FEATURE_*` bits)
+    pub features: u32,
+}
+
+impl VersionInfo {
+    pub fn current() -> Self {
+        let features = [
+            (cfg!(feature = "kernel-workqueue"), FEATURE_WORKQUEUE),
+            (
+                cfg!(feature = "kernel-deadlock-detection"),
+                FEATURE_DEADLOCK_DETECTION,
+            ),
+            (cfg!(feature = "kernel-mpu"), FEATURE_MPU),
+            (cfg!(feature = "kernel-stats"), FEATURE_STATS),
+            (cfg!(feature = "kernel-debug"), FEATURE_DEBUG),
+            (cfg!(feature = "kernel-loadable-pie"), FEATURE_LOADABLE_PIE),
+            (cfg!(feature = "kernel-trace"), FEATURE_TRACE),
+        ]
+        .iter()
+        .filter(|(enabled, _)| *enabled)
+        .fold(0, |features, (_, feature)| features | feature);
+
+        VersionInfo {
+            version: to_padded(env!("CARGO_PKG_VERSION")),
+            features,
+        }
+    }
+
+    pub fn version(&self) -> &str {
+        from_padded(&self.version)
+    }
+}
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ThreadInfoState {
+    #[default]
+    Stopped = 0,
+    Ready = 1,
+    Pending = 2,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ThreadInfo {
+    pub id: u32,
+    /// Thread name, NUL padded
+    pub name: [u8; THREAD_NAME_LEN],
+    pub state: ThreadInfoState,
+    pub priority: i8,
+    /// Kernel object the thread is pending on, -1 if none
+    pub pending_kobj: i32,
+    /// Instant (in ticks) the thread pends until, `u64::MAX` if none
+    pub timeout: u64,
+    /// Stack high-water mark in bytes
+    pub stack_used: u32,
+    pub stack_size: u32,
+}
+
+impl ThreadInfo {
+    pub fn name(&self) -> &str {
+        from_padded(&self.name)
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct KobjInfo {
+    /// Index of the kernel object
+    pub kobj: u32,
+    pub kind: SyncPrimitiveType,
+    /// Number of threads pending on the kernel object
+    pub waiters: u32,
+    /// Identifier of the owner thread (e.g. mutex), 0 if none
+    pub owner: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MemoryInfo {
+    /// Size of the kernel heap in bytes
+    pub heap_size: u32,
+    /// Bytes never allocated, the kernel heap does not reclaim freed memory
+    pub heap_free: u32,
+}
+
+/// Copies a string to a NUL padded buffer, truncating it if necessary.
+pub fn to_padded<const N: usize>(s: &str) -> [u8; N] {
+    let mut buf = [0; N];
+    let len = s.len().min(N);
+
+    buf[..len].copy_from_slice(&s.as_bytes()[..len]);
+    buf
+}
+
+/// Retrieves the string of a NUL padded buffer, up to the first invalid
+/// UTF-8 character.
+pub fn from_padded(buf: &[u8]) -> &str {
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+
+    match core::str::from_utf8(&buf[..len]) {
+        Ok(s) => s,
+        Err(err) => unsafe { core::str::from_utf8_unchecked(&buf[..err.valid_up_to()]) },
+    }
+}
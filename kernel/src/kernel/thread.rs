@@ -1,13 +1,24 @@
+#[cfg(feature = "kernel-stats")]
+use super::stats::ThreadStats;
 use super::{
-    errno::Kerr,
+    errno::{KResult, Kerr},
     stack::StackInfo,
     sync::{SwapData, Swappable},
+    sysinfo::{to_padded, ThreadInfo, ThreadInfoState},
     timeout::TimeoutInstant,
     CpuVariant, ExceptionStackFrame, ThreadEntry,
 };
-use crate::list::{self, singly_linked as sl};
+use crate::{
+    list::{self, singly_linked as sl},
+    mem::region::{Access, MemoryRegion, MAX_THREAD_REGIONS},
+};
 use core::{cell::Cell, cmp::Ordering, ffi::c_void, fmt::Display, ptr};
 
+#[cfg(all(feature = "kernel-mpu", not(feature = "cm33")))]
+use crate::cortex_m::mpu::MpuRegion;
+#[cfg(all(feature = "kernel-mpu", feature = "cm33"))]
+use crate::cortex_m::mpu_v8m::MpuRegion;
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub enum ThreadState {
     Stopped,
@@ -54,12 +65,6 @@ impl PendingContext {
     }
 }
 
-#[cfg(feature = "kernel-stats")]
-#[derive(Default)]
-pub struct ThreadStats {
-    pub(super) syscalls: Cell<u32>,
-}
-
 // Thread priority model is the same as Zephyr RTOS:
 // read: <https://docs.zephyrproject.org/latest/kernel/services/threads/index.html#id12>
 #[repr(i32)]
@@ -135,6 +140,29 @@ pub struct Thread<'a, CPU: CpuVariant> {
     /// Thread priority (preemptive/cooperative)
     pub priority: ThreadPriority,
 
+    /// Identifier of the thread, assigned by the kernel when the thread is
+    /// registered (0 until then).
+    id: Cell<u32>,
+
+    /// Name of the thread, for introspection.
+    name: Cell<&'static str>,
+
+    /// Whether the thread can use the system syscalls (kill, load, reboot).
+    trusted: Cell<bool>,
+
+    /// Stack the thread runs on, used to detect stack overflows.
+    pub(super) stack: StackInfo,
+
+    /// Memory regions the thread is allowed to access in addition to its stack.
+    regions: Cell<[Option<MemoryRegion>; MAX_THREAD_REGIONS]>,
+
+    /// Memory the thread allocates from (`MemoryAlloc` syscall), also one of
+    /// its memory regions.
+    heap: Cell<Option<MemoryRegion>>,
+
+    /// Number of bytes allocated from the heap.
+    heap_used: Cell<usize>,
+
     /// This link is used to organize threads in kernel list of known threads
     runqueue_next: sl::Link<'a, Thread<'a, CPU>, Runqueue>,
 
@@ -145,7 +173,6 @@ pub struct Thread<'a, CPU: CpuVariant> {
     /// Stats for the current thread
     #[cfg(feature = "kernel-stats")]
     pub stats: ThreadStats,
-    // TODO: Add thread name and id
 }
 
 impl<'a, CPU: CpuVariant> sl::Node<'a, Thread<'a, CPU>, Runqueue> for Thread<'a, CPU> {
@@ -165,20 +192,30 @@ impl<'a, CPU: CpuVariant> Thread<'a, CPU> {
         !self.stack_ptr.get().is_null()
     }
 
+    /// Initializes a thread running `entry` on the given stack.
     ///
-    ///
-    /// Note: Function only update end of the stack, the beginning of the stack
-    /// remains unchanged.
+    /// Note: The stack is painted and a canary is written at its lowest word,
+    /// in order to detect stack overflows and compute the stack high-water mark.
     pub fn init(
         stack: &StackInfo,
         entry: ThreadEntry,
         arg0: *mut c_void,
         raw_priority: i8,
     ) -> Self {
+        let mut context = CPU::CalleeContext::default();
+        CPU::init_context(&mut context, stack);
+
         let thread = Thread {
             stack_ptr: Cell::new(unsafe { stack.stack_end.sub(CPU::InitStackFrame::SIZE_WORDS) }),
-            context: Cell::new(CPU::CalleeContext::default()),
+            context: Cell::new(context),
             priority: ThreadPriority::from(raw_priority),
+            id: Cell::new(0),
+            name: Cell::new(""),
+            trusted: Cell::new(false),
+            stack: *stack,
+            regions: Cell::new([None; MAX_THREAD_REGIONS]),
+            heap: Cell::new(None),
+            heap_used: Cell::new(0),
             state: Cell::new(ThreadState::Stopped),
             runqueue_next: sl::Link::empty(),
             waitqueue_next: sl::Link::empty(),
@@ -186,11 +223,201 @@ impl<'a, CPU: CpuVariant> Thread<'a, CPU> {
             stats: ThreadStats::default(),
         };
 
+        unsafe { stack.paint(thread.stack_ptr.get()) };
+
         CPU::InitStackFrame::initialize_at(thread.stack_ptr.get(), entry, arg0);
 
         thread
     }
 
+    pub fn id(&self) -> u32 {
+        self.id.get()
+    }
+
+    pub(super) fn set_id(&self, id: u32) {
+        self.id.set(id);
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name.get()
+    }
+
+    /// Names the thread, names longer than `THREAD_NAME_LEN` are truncated
+    /// in introspection snapshots.
+    pub fn set_name(&self, name: &'static str) {
+        self.name.set(name);
+    }
+
+    /// Allows the thread to use the system syscalls (kill, load, reboot), which
+    /// fail with `Kerr::EPERM` for the other threads. Typically set for a shell.
+    pub fn set_trusted(&self) {
+        self.trusted.set(true);
+    }
+
+    /// Checks whether the thread can use the system syscalls.
+    pub fn is_trusted(&self) -> bool {
+        self.trusted.get()
+    }
+
+    /// Takes a snapshot of the thread for introspection.
+    pub fn info(&self) -> ThreadInfo {
+        let (state, pending_kobj) = match self.state.get() {
+            ThreadState::Stopped => (ThreadInfoState::Stopped, -1),
+            ThreadState::Running => (ThreadInfoState::Ready, -1),
+            ThreadState::Pending(context) => (
+                ThreadInfoState::Pending,
+                context.sync_kobj_index.map_or(-1, |kobj| kobj as i32),
+            ),
+        };
+
+        ThreadInfo {
+            id: self.id(),
+            name: to_padded(self.name()),
+            state,
+            priority: self.priority.raw_priority(),
+            pending_kobj,
+            timeout: match self.get_timeout_instant() {
+                Some(TimeoutInstant::At(instant)) => instant,
+                _ => u64::MAX,
+            },
+            stack_used: self.stack_high_water_mark() as u32,
+            stack_size: self.stack_size() as u32,
+        }
+    }
+
+    /// Checks whether the thread overflowed its stack: either the stack pointer
+    /// went below the stack or the canary has been overwritten.
+    pub fn has_overflowed_stack(&self) -> bool {
+        self.stack_ptr.get() <= self.stack.stack_start() || !self.stack.canary_intact()
+    }
+
+    /// Returns the maximum number of bytes of its stack the thread has used.
+    pub fn stack_high_water_mark(&self) -> usize {
+        self.stack.high_water_mark()
+    }
+
+    /// Returns the size of the thread stack in bytes.
+    pub fn stack_size(&self) -> usize {
+        self.stack.size
+    }
+
+    /// Returns the memory region of the thread stack.
+    pub fn stack_region(&self) -> MemoryRegion {
+        MemoryRegion::new(
+            self.stack.stack_start() as usize,
+            self.stack.size,
+            Access::ReadWrite,
+            false,
+        )
+    }
+
+    /// Grants the thread access to a memory region.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the region has been added.
+    /// * `Err(Kerr::ENOSPC)` if the thread already has `MAX_THREAD_REGIONS` regions.
+    /// * `Err(Kerr::InvalidArguments)` if the MPU cannot cover exactly the region.
+    pub fn add_region(&self, region: MemoryRegion) -> KResult<()> {
+        #[cfg(feature = "kernel-mpu")]
+        MpuRegion::encode(&region)?;
+
+        let mut regions = self.regions.get();
+
+        let slot = regions
+            .iter_mut()
+            .find(|slot| slot.is_none())
+            .ok_or(Kerr::ENOSPC)?;
+        *slot = Some(region);
+
+        self.regions.set(regions);
+        Ok(())
+    }
+
+    /// Replaces the memory regions of the thread, e.g. with those of the
+    /// thread which submitted the work item the work queue thread executes.
+    pub(super) fn set_regions(&self, regions: [Option<MemoryRegion>; MAX_THREAD_REGIONS]) {
+        self.regions.set(regions);
+    }
+
+    /// Gives the thread a heap to allocate memory from, mapped once as one of
+    /// its memory regions.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` if the heap has been set.
+    /// * `Err(Kerr::EEXIST)` if the thread already has a heap.
+    /// * Errors of `add_region` if the heap cannot be mapped.
+    pub fn set_heap(&self, heap: &'a mut [u8]) -> KResult<()> {
+        if self.heap.get().is_some() {
+            return Err(Kerr::EEXIST);
+        }
+
+        let region = MemoryRegion::new(
+            heap.as_mut_ptr() as usize,
+            heap.len(),
+            Access::ReadWrite,
+            false,
+        );
+        self.add_region(region)?;
+
+        self.heap.set(Some(region));
+        self.heap_used.set(0);
+        Ok(())
+    }
+
+    /// Returns whether the thread has been given a heap (`set_heap`).
+    pub fn has_heap(&self) -> bool {
+        self.heap.get().is_some()
+    }
+
+    /// Allocates zeroed memory from the heap of the thread.
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(ptr)` to the allocated memory.
+    /// * `Err(Kerr::NoMemory)` if the thread has no heap or it is exhausted.
+    pub fn heap_alloc(&self, size: usize, align: usize) -> KResult<*mut u8> {
+        let heap = self.heap.get().ok_or(Kerr::NoMemory)?;
+
+        let start = (heap.start + self.heap_used.get()).next_multiple_of(align);
+        if !heap.contains(start, size) {
+            return Err(Kerr::NoMemory);
+        }
+        self.heap_used.set(start + size - heap.start);
+
+        let ptr = start as *mut u8;
+        unsafe { ptr.write_bytes(0, size) };
+        Ok(ptr)
+    }
+
+    /// Checks whether the range `[addr, addr + len)` lies within the thread
+    /// stack or one of its memory regions granting the requested access.
+    pub fn owns_memory(&self, addr: usize, len: usize, access: Access) -> bool {
+        self.stack_region().allows(addr, len, access)
+            || self
+                .regions
+                .get()
+                .iter()
+                .flatten()
+                .any(|region| region.allows(addr, len, access))
+    }
+
+    /// Checks whether the thread is allowed to execute code at `addr` from
+    /// one of its memory regions.
+    pub fn can_execute(&self, addr: usize) -> bool {
+        self.regions
+            .get()
+            .iter()
+            .flatten()
+            .any(|region| region.executable && region.contains(addr, 1))
+    }
+
+    /// Returns the memory regions of the thread, in addition to its stack.
+    pub fn regions(&self) -> [Option<MemoryRegion>; MAX_THREAD_REGIONS] {
+        self.regions.get()
+    }
+
     pub fn set_ready(&self) {
         self.state.set(ThreadState::Running);
     }
@@ -253,6 +480,17 @@ impl<'a, CPU: CpuVariant> Thread<'a, CPU> {
         ptr::write(self.stack_ptr.get().add(0), ret as u32);
     }
 
+    /// Writes a 64 bits syscall return value in the thread exception frame,
+    /// the low word in r0 and the high word in r1.
+    ///
+    /// # Safety
+    ///
+    /// The thread must have a pending syscall, otherwise its stack is corrupted.
+    pub unsafe fn set_syscall_return_value64_unchecked(&self, ret: u64) {
+        ptr::write(self.stack_ptr.get().add(0), ret as u32);
+        ptr::write(self.stack_ptr.get().add(1), (ret >> 32) as u32);
+    }
+
     pub fn unpend<S: Swappable>(&self, swap: &S) {
         self.set_ready();
         unsafe {
@@ -273,3 +511,42 @@ impl<'a, CPU: CpuVariant> Display for Thread<'a, CPU> {
         write!(f, "Thread sp=0x{:08x}", self.stack_ptr.get() as u32)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::kernel::{
+        errno::Kerr,
+        mock::{mock_thread, MOCK_STACK_SIZE},
+        stack::Stack,
+    };
+
+    /// Heap aligned on its size, so that the MPU covers it exactly
+    #[repr(align(64))]
+    struct Heap([u8; 64]);
+
+    #[test]
+    fn test_heap_alloc() {
+        let mut stack = Stack::<MOCK_STACK_SIZE>::zeroed();
+        let thread = mock_thread(&mut stack);
+        assert!(matches!(thread.heap_alloc(4, 4), Err(Kerr::NoMemory)));
+
+        let mut heap = Heap([0xff; 64]);
+        let start = heap.0.as_ptr() as usize;
+        assert!(thread.set_heap(&mut heap.0).is_ok());
+        assert!(thread.regions().iter().flatten().any(|r| r.start == start));
+
+        let mut other = [0u8; 8];
+        assert!(matches!(thread.set_heap(&mut other), Err(Kerr::EEXIST)));
+
+        // Allocations are aligned and zeroed, within the heap
+        let a = thread.heap_alloc(3, 1).unwrap() as usize;
+        let b = thread.heap_alloc(8, 8).unwrap() as usize;
+        assert_eq!(a, start);
+        assert!(b >= a + 3 && b % 8 == 0);
+        assert_eq!(unsafe { *(b as *const u64) }, 0);
+
+        assert!(matches!(thread.heap_alloc(64, 1), Err(Kerr::NoMemory)));
+        assert!(thread.heap_alloc(start + 64 - (b + 8), 1).is_ok());
+        assert!(matches!(thread.heap_alloc(1, 1), Err(Kerr::NoMemory)));
+    }
+}
@@ -2,22 +2,54 @@
 pub enum Timeout {
     // Duration in milliseconds
     Duration(u32),
+    // Duration in microseconds
+    DurationUs(u32),
+    // Absolute instant in system ticks since boot (low 32 bits)
+    AtTicks(u32),
+    // Absolute instant in milliseconds since boot (low 32 bits)
+    AtMs(u32),
     #[default]
     Forever,
 }
 
 impl Timeout {
+    /// Raw timeout kinds, as passed to the kernel in syscall registers.
+    pub const KIND_DURATION: u32 = 0;
+    pub const KIND_AT_TICKS: u32 = 1;
+    pub const KIND_AT_MS: u32 = 2;
+    pub const KIND_DURATION_US: u32 = 3;
+
     pub fn get_ms(&self) -> Option<u32> {
         match self {
             Timeout::Duration(ticks) => Some(*ticks),
-            Timeout::Forever => None,
+            _ => None,
         }
     }
 
+    /// Timeout expiring at the given absolute instant, in system ticks since boot.
+    ///
+    /// Only the low 32 bits of the instant are kept, the kernel resolves the
+    /// instant relatively to the current time, so it must be within 2^31 ticks
+    /// from now.
+    pub fn at_ticks(ticks: u64) -> Self {
+        Timeout::AtTicks(ticks as u32)
+    }
+
+    /// Timeout expiring at the given absolute instant, in milliseconds since boot.
+    ///
+    /// Same restrictions as `at_ticks` apply.
+    pub fn at_ms(ms: u64) -> Self {
+        Timeout::AtMs(ms as u32)
+    }
+
     pub fn from_ms(ms: u32) -> Self {
         Timeout::Duration(ms)
     }
 
+    pub fn from_us(us: u32) -> Self {
+        Timeout::DurationUs(us)
+    }
+
     pub fn from_seconds(seconds: u32) -> Self {
         Timeout::Duration(seconds * 1000)
     }
@@ -27,7 +59,33 @@ impl Timeout {
     }
 
     pub fn is_zero(&self) -> bool {
-        matches!(self, Timeout::Duration(0))
+        matches!(self, Timeout::Duration(0) | Timeout::DurationUs(0))
+    }
+
+    pub fn is_absolute(&self) -> bool {
+        matches!(self, Timeout::AtTicks(_) | Timeout::AtMs(_))
+    }
+
+    /// Encodes the timeout as a (value, kind) pair for syscall registers.
+    pub fn to_raw(&self) -> (u32, u32) {
+        match *self {
+            Timeout::Duration(ms) => (ms, Self::KIND_DURATION),
+            Timeout::DurationUs(us) => (us, Self::KIND_DURATION_US),
+            Timeout::AtTicks(ticks) => (ticks, Self::KIND_AT_TICKS),
+            Timeout::AtMs(ms) => (ms, Self::KIND_AT_MS),
+            Timeout::Forever => (u32::MAX, Self::KIND_DURATION),
+        }
+    }
+
+    /// Decodes a timeout from a (value, kind) pair passed in syscall registers.
+    pub fn from_raw(value: u32, kind: u32) -> Option<Self> {
+        match kind {
+            Self::KIND_DURATION => Timeout::try_from(value as i32).ok(),
+            Self::KIND_AT_TICKS => Some(Timeout::AtTicks(value)),
+            Self::KIND_AT_MS => Some(Timeout::AtMs(value)),
+            Self::KIND_DURATION_US => Some(Timeout::DurationUs(value)),
+            _ => None,
+        }
     }
 }
 
@@ -45,15 +103,6 @@ impl TryFrom<i32> for Timeout {
     }
 }
 
-impl From<Timeout> for i32 {
-    fn from(timeout: Timeout) -> i32 {
-        match timeout {
-            Timeout::Duration(ticks) => ticks as i32,
-            Timeout::Forever => -1,
-        }
-    }
-}
-
 impl From<u32> for Timeout {
     fn from(value: u32) -> Self {
         if value == u32::MAX {
@@ -93,4 +142,76 @@ impl TimeoutInstant {
             TimeoutInstant::Never => false,
         }
     }
+
+    /// Extends the low 32 bits of an instant to the full instant closest to `now`.
+    pub fn extend(now: u64, low: u32) -> u64 {
+        let delta = low.wrapping_sub(now as u32) as i32;
+        now.saturating_add_signed(delta as i64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raw_roundtrip() {
+        for timeout in [
+            Timeout::Duration(0),
+            Timeout::Duration(1234),
+            Timeout::DurationUs(50),
+            Timeout::AtTicks(0xdead_beef),
+            Timeout::AtMs(42),
+            Timeout::Forever,
+        ] {
+            let (value, kind) = timeout.to_raw();
+            assert_eq!(Timeout::from_raw(value, kind), Some(timeout));
+        }
+    }
+
+    #[test]
+    fn test_from_raw_invalid() {
+        // Negative durations other than -1 (forever)
+        assert_eq!(
+            Timeout::from_raw(-2i32 as u32, Timeout::KIND_DURATION),
+            None
+        );
+        assert_eq!(Timeout::from_raw(0, 4), None);
+    }
+
+    #[test]
+    fn test_kinds() {
+        assert!(Timeout::from_ms(0).is_zero());
+        assert!(Timeout::from_us(0).is_zero());
+        assert!(!Timeout::Forever.is_zero());
+        assert!(Timeout::default().is_forever());
+        assert!(Timeout::at_ms(10).is_absolute());
+        assert!(!Timeout::from_seconds(1).is_absolute());
+        assert_eq!(Timeout::from_seconds(2).get_ms(), Some(2000));
+        assert_eq!(Timeout::from(u32::MAX), Timeout::Forever);
+        assert_eq!(Timeout::at_ticks(0x1_0000_0005), Timeout::AtTicks(5));
+    }
+
+    #[test]
+    fn test_instant() {
+        assert!(TimeoutInstant::new_at(10).is_past(10));
+        assert!(!TimeoutInstant::new_at(11).is_past(10));
+        assert!(!TimeoutInstant::new_never().is_past(u64::MAX));
+        assert!(TimeoutInstant::new_at(0).is_zero());
+    }
+
+    #[test]
+    fn test_extend() {
+        assert_eq!(TimeoutInstant::extend(1000, 1500), 1500);
+        // Instant in the past
+        assert_eq!(TimeoutInstant::extend(1000, 500), 500);
+        // Low word wrapped around
+        assert_eq!(TimeoutInstant::extend(0x1_ffff_fff0, 0x10), 0x2_0000_0010);
+        assert_eq!(
+            TimeoutInstant::extend(0x2_0000_0010, 0xffff_fff0),
+            0x1_ffff_fff0
+        );
+        // Instants before boot saturate
+        assert_eq!(TimeoutInstant::extend(10, u32::MAX), 0);
+    }
 }
@@ -0,0 +1,188 @@
+//! Kernel event tracing (`kernel-trace`).
+//!
+//! Events (context switches, syscalls, interrupts, kernel objects pend and
+//! release, timeouts) are recorded with a cycle timestamp in a ring buffer,
+//! the oldest events being overwritten once it is full. Recording an event
+//! only takes a few stores, unlike `kernel-debug-syscalls` which prints every
+//! syscall.
+//!
+//! The buffer is exported as a binary stream on the stdio UART with the
+//! `TraceDump` syscall, and decoded on the host with `scripts/trace_decode.py`.
+//!
+//! Stream layout (little-endian):
+//!
+//! | offset | size | field                                       |
+//! |--------|------|---------------------------------------------|
+//! | 0      | 4    | magic `KTRC`                                |
+//! | 4      | 1    | format version                              |
+//! | 5      | 1    | record size in bytes                        |
+//! | 6      | 2    | reserved                                    |
+//! | 8      | 4    | CPU frequency in Hz (timestamps unit)       |
+//! | 12     | 4    | number of records following the header      |
+//! | 16     | 4    | number of records lost (overwritten)        |
+//! | 20     | ...  | records (`TraceRecord`), oldest first       |
+
+#[cfg(feature = "kernel-trace")]
+use core::ptr::addr_of_mut;
+
+/// Number of records kept in the ring buffer.
+pub const TRACE_BUFFER_LEN: usize = 512;
+
+pub const TRACE_MAGIC: [u8; 4] = *b"KTRC";
+pub const TRACE_VERSION: u8 = 1;
+pub const TRACE_HEADER_SIZE: usize = 20;
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceEvent {
+    /// The kernel switches to the thread
+    ThreadSwitch = 0,
+    /// The thread invoked a syscall, `arg` holds the syscall ID in the low
+    /// byte and the function (r3) above
+    SyscallEntry = 1,
+    /// The syscall completed, `arg` holds the return value (low word)
+    SyscallExit = 2,
+    /// The syscall made the thread pend
+    SyscallPending = 3,
+    /// The thread has been interrupted, `arg` holds the exception number of
+    /// the device interrupt, 0 for the SysTick
+    Interrupt = 4,
+    /// System tick, `arg` holds the tick counter (low word)
+    Tick = 5,
+    /// The thread pends on the kernel object `arg`
+    KobjPend = 6,
+    /// The thread released the kernel object `arg`
+    KobjRelease = 7,
+    /// The thread `arg` timed out
+    Timeout = 8,
+    /// The thread `arg` has been stopped (fault, stack overflow, killed)
+    ThreadStop = 9,
+}
+
+/// A recorded event, attributed to the thread the kernel last switched to.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct TraceRecord {
+    /// CPU cycles since the kernel started, wrapping around on 32 bits
+    pub timestamp: u32,
+    pub arg: u32,
+    /// Identifier of the current thread
+    pub tid: u16,
+    pub event: TraceEvent,
+    _reserved: u8,
+}
+
+pub const TRACE_RECORD_SIZE: usize = core::mem::size_of::<TraceRecord>();
+
+impl TraceRecord {
+    pub fn to_bytes(&self) -> [u8; TRACE_RECORD_SIZE] {
+        let mut bytes = [0; TRACE_RECORD_SIZE];
+
+        bytes[0..4].copy_from_slice(&self.timestamp.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.arg.to_le_bytes());
+        bytes[8..10].copy_from_slice(&self.tid.to_le_bytes());
+        bytes[10] = self.event as u8;
+        bytes
+    }
+}
+
+/// Ring buffer of trace records.
+pub struct TraceBuffer<const N: usize> {
+    records: [TraceRecord; N],
+    /// Index of the next record to write
+    head: usize,
+    /// Number of valid records
+    len: usize,
+    /// Number of records overwritten since the last dump
+    lost: u32,
+    /// Thread the kernel switched to last
+    current_tid: u16,
+}
+
+impl<const N: usize> TraceBuffer<N> {
+    pub const fn new() -> Self {
+        TraceBuffer {
+            records: [TraceRecord {
+                timestamp: 0,
+                arg: 0,
+                tid: 0,
+                event: TraceEvent::ThreadSwitch,
+                _reserved: 0,
+            }; N],
+            head: 0,
+            len: 0,
+            lost: 0,
+            current_tid: 0,
+        }
+    }
+
+    pub fn record(&mut self, timestamp: u32, event: TraceEvent, arg: u32) {
+        if event == TraceEvent::ThreadSwitch {
+            self.current_tid = arg as u16;
+        }
+
+        self.records[self.head] = TraceRecord {
+            timestamp,
+            arg,
+            tid: self.current_tid,
+            event,
+            _reserved: 0,
+        };
+        self.head = (self.head + 1) % N;
+
+        if self.len < N {
+            self.len += 1;
+        } else {
+            self.lost = self.lost.saturating_add(1);
+        }
+    }
+
+    /// Returns the records, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &TraceRecord> {
+        let start = (self.head + N - self.len) % N;
+
+        (0..self.len).map(move |i| &self.records[(start + i) % N])
+    }
+
+    /// Writes the stream header followed by the records, then empties the buffer.
+    pub fn dump(&mut self, fcpu: u32, mut write: impl FnMut(&[u8])) {
+        let mut header = [0; TRACE_HEADER_SIZE];
+
+        header[0..4].copy_from_slice(&TRACE_MAGIC);
+        header[4] = TRACE_VERSION;
+        header[5] = TRACE_RECORD_SIZE as u8;
+        header[8..12].copy_from_slice(&fcpu.to_le_bytes());
+        header[12..16].copy_from_slice(&(self.len as u32).to_le_bytes());
+        header[16..20].copy_from_slice(&self.lost.to_le_bytes());
+        write(&header);
+
+        for record in self.iter() {
+            write(&record.to_bytes());
+        }
+
+        self.len = 0;
+        self.lost = 0;
+    }
+}
+
+impl<const N: usize> Default for TraceBuffer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The buffer is not part of the kernel structure, which lives on the main stack.
+#[cfg(feature = "kernel-trace")]
+static mut Z_TRACE: TraceBuffer<TRACE_BUFFER_LEN> = TraceBuffer::new();
+
+/// Records an event in the kernel trace buffer.
+#[cfg(feature = "kernel-trace")]
+pub(super) fn record(timestamp: u32, event: TraceEvent, arg: u32) {
+    unsafe { (*addr_of_mut!(Z_TRACE)).record(timestamp, event, arg) }
+}
+
+/// Writes the kernel trace buffer to the stdio UART, then empties it.
+#[cfg(feature = "kernel-trace")]
+pub fn dump(fcpu: u32) {
+    unsafe { (*addr_of_mut!(Z_TRACE)).dump(fcpu, crate::stdio::write_bytes) }
+}
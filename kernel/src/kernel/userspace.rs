@@ -1,16 +1,28 @@
+#[cfg(target_arch = "arm")]
+use core::arch::asm;
 use core::{
-    arch::asm,
+    ffi::c_void,
     fmt::{Arguments, Write},
 };
 
 use super::{
-    syscalls::{IoSyscallId, KernelSyscallId, SyncPrimitiveType, SyscallId},
+    errno::Kerr,
+    stats::{
+        KernelStatsInfo, KobjStatsInfo, StatsQuery, SyscallStatsInfo, ThreadStatsInfo,
+        STATS_INDEX_SHIFT,
+    },
+    syscalls::{
+        IoSyscallId, KernelSyscallId, SyncPrimitiveType, SyscallId, PEND_TIMEOUT_KIND_SHIFT,
+    },
+    sysinfo::{KobjInfo, MemoryInfo, SysInfoQuery, ThreadInfo, VersionInfo},
     timeout::Timeout,
+    workqueue::{Work, WorkHandler},
 };
 
 // Compiler update should do the job:
 //
 // Generic const in asm requires "#![feature(asm_const)]"
+#[cfg(target_arch = "arm")]
 pub unsafe fn z_call_svc_4<const SVC_NUM: u8>(mut r0: u32, r1: u32, r2: u32, r3: u32) -> i32 {
     asm!(
         "svc #{svc_num}",
@@ -19,22 +31,98 @@ pub unsafe fn z_call_svc_4<const SVC_NUM: u8>(mut r0: u32, r1: u32, r2: u32, r3:
         in("r1") r1,
         in("r2") r2,
         in("r3") r3,
-        options(nostack, nomem),
+        // The kernel reads and writes the memory the arguments point to
+        options(nostack),
     );
     r0 as i32
 }
 
+// Threads of the hosted port switch to the kernel instead
+#[cfg(all(not(target_arch = "arm"), feature = "hosted"))]
+pub unsafe fn z_call_svc_4<const SVC_NUM: u8>(r0: u32, r1: u32, r2: u32, r3: u32) -> i32 {
+    crate::hosted::arch::syscall(SVC_NUM, r0, r1, r2, r3).0 as i32
+}
+
+// Syscalls are only issued by user threads running on the target
+#[cfg(not(any(target_arch = "arm", feature = "hosted")))]
+pub unsafe fn z_call_svc_4<const SVC_NUM: u8>(_r0: u32, _r1: u32, _r2: u32, _r3: u32) -> i32 {
+    unimplemented!("svc #{}", SVC_NUM)
+}
+
 pub unsafe fn z_call_svc_kernel_4(r0: u32, r1: u32, r2: u32, r3: u32) -> i32 {
     z_call_svc_4::<{ SyscallId::Kernel as u8 }>(r0, r1, r2, r3)
 }
 
+/// Same as `z_call_svc_4` but for syscalls returning a 64 bits value,
+/// the low word is returned in r0 and the high word in r1.
+///
+/// # Safety
+///
+/// Must be called from a user thread, the arguments must be valid for the syscall.
+#[cfg(target_arch = "arm")]
+pub unsafe fn z_call_svc_4_ret64<const SVC_NUM: u8>(
+    mut r0: u32,
+    mut r1: u32,
+    r2: u32,
+    r3: u32,
+) -> u64 {
+    asm!(
+        "svc #{svc_num}",
+        svc_num = const SVC_NUM,
+        inlateout("r0") r0,
+        inlateout("r1") r1,
+        in("r2") r2,
+        in("r3") r3,
+        // The kernel reads and writes the memory the arguments point to
+        options(nostack),
+    );
+    ((r1 as u64) << 32) | r0 as u64
+}
+
+/// # Safety
+///
+/// See `z_call_svc_4_ret64`.
+#[cfg(all(not(target_arch = "arm"), feature = "hosted"))]
+pub unsafe fn z_call_svc_4_ret64<const SVC_NUM: u8>(r0: u32, r1: u32, r2: u32, r3: u32) -> u64 {
+    let (r0, r1) = crate::hosted::arch::syscall(SVC_NUM, r0, r1, r2, r3);
+    ((r1 as u64) << 32) | r0 as u64
+}
+
+/// # Safety
+///
+/// See `z_call_svc_4_ret64`.
+#[cfg(not(any(target_arch = "arm", feature = "hosted")))]
+pub unsafe fn z_call_svc_4_ret64<const SVC_NUM: u8>(_r0: u32, _r1: u32, _r2: u32, _r3: u32) -> u64 {
+    unimplemented!("svc #{}", SVC_NUM)
+}
+
+/// # Safety
+///
+/// See `z_call_svc_4_ret64`.
+pub unsafe fn z_call_svc_kernel_4_ret64(r0: u32, r1: u32, r2: u32, r3: u32) -> u64 {
+    z_call_svc_4_ret64::<{ SyscallId::Kernel as u8 }>(r0, r1, r2, r3)
+}
+
 pub fn k_yield() -> i32 {
     unsafe { z_call_svc_kernel_4(0, 0, 0, KernelSyscallId::Yield as u32) }
 }
 
 pub fn k_sleep(duration: Timeout) -> i32 {
-    let r0: i32 = duration.into();
-    unsafe { z_call_svc_kernel_4(r0 as u32, 0, 0, KernelSyscallId::Sleep as u32) }
+    let (r0, r1) = duration.to_raw();
+    unsafe { z_call_svc_kernel_4(r0, r1, 0, KernelSyscallId::Sleep as u32) }
+}
+
+/// Sleeps until the given absolute instant, in system ticks since boot.
+///
+/// Returns immediately if the instant is already reached, which allows
+/// periodic threads to wake up exactly every period without drift.
+pub fn k_sleep_until(ticks: u64) -> i32 {
+    k_sleep(Timeout::at_ticks(ticks))
+}
+
+/// Sleeps until the given absolute instant, in milliseconds since boot.
+pub fn k_sleep_until_ms(ms: u64) -> i32 {
+    k_sleep(Timeout::at_ms(ms))
 }
 
 pub fn k_print(string: &str, nl: bool) -> i32 {
@@ -93,12 +181,12 @@ pub fn k_mutex_create() -> i32 {
 }
 
 pub fn k_mutex_lock(mutex: i32, timeout: Timeout) -> i32 {
-    let r0: i32 = timeout.into();
+    let (r0, kind) = timeout.to_raw();
     unsafe {
         z_call_svc_kernel_4(
-            r0 as u32,
+            r0,
             mutex as u32,
-            SyncPrimitiveType::Mutex as u32,
+            SyncPrimitiveType::Mutex as u32 | kind << PEND_TIMEOUT_KIND_SHIFT,
             KernelSyscallId::Pend as u32,
         )
     }
@@ -115,6 +203,29 @@ pub fn k_mutex_unlock(mutex: i32) -> i32 {
     }
 }
 
+pub fn k_sem_take(sem: i32, timeout: Timeout) -> i32 {
+    let (r0, kind) = timeout.to_raw();
+    unsafe {
+        z_call_svc_kernel_4(
+            r0,
+            sem as u32,
+            SyncPrimitiveType::Semaphore as u32 | kind << PEND_TIMEOUT_KIND_SHIFT,
+            KernelSyscallId::Pend as u32,
+        )
+    }
+}
+
+pub fn k_sem_give(sem: i32) -> i32 {
+    unsafe {
+        z_call_svc_kernel_4(
+            0,
+            sem as u32,
+            SyncPrimitiveType::Semaphore as u32,
+            KernelSyscallId::Sync as u32,
+        )
+    }
+}
+
 pub fn k_sync(kobj: i32) -> i32 {
     unsafe {
         z_call_svc_kernel_4(
@@ -138,24 +249,24 @@ pub fn k_signal(kobj: i32, signal_value: u32) -> i32 {
 }
 
 pub fn k_signal_poll(kobj: i32, timeout: Timeout) -> i32 {
-    let r0: i32 = timeout.into();
+    let (r0, kind) = timeout.to_raw();
     unsafe {
         z_call_svc_kernel_4(
-            r0 as u32,
+            r0,
             kobj as u32,
-            SyncPrimitiveType::Signal as u32,
+            SyncPrimitiveType::Signal as u32 | kind << PEND_TIMEOUT_KIND_SHIFT,
             KernelSyscallId::Pend as u32,
         )
     }
 }
 
 pub fn k_pend(kobj: i32, timeout: Timeout) -> i32 {
-    let r0: i32 = timeout.into();
+    let (r0, kind) = timeout.to_raw();
     unsafe {
         z_call_svc_kernel_4(
-            r0 as u32,
+            r0,
             kobj as u32,
-            SyncPrimitiveType::Sync as u32,
+            SyncPrimitiveType::Sync as u32 | kind << PEND_TIMEOUT_KIND_SHIFT,
             KernelSyscallId::Pend as u32,
         )
     }
@@ -170,6 +281,188 @@ pub fn k_stop() -> ! {
     unreachable!()
 }
 
+/// Submits a work item to be executed as soon as possible by the work queue thread.
+pub fn k_work_submit(handler: WorkHandler, arg: *mut c_void) -> i32 {
+    k_work_submit_delayed(handler, arg, Timeout::Duration(0))
+}
+
+/// Submits a work item to be executed by the work queue thread once `delay` has elapsed.
+///
+/// Absolute timeouts are not supported (`Kerr::InvalidArguments` is returned).
+///
+/// The handler runs on the stack of the work queue thread, with the memory
+/// regions of the calling thread but the `r9` of the work queue thread: loaded
+/// ELF images, whose `r9` points to their GOT, get `Kerr::EPERM`.
+pub fn k_work_submit_delayed(handler: WorkHandler, arg: *mut c_void, delay: Timeout) -> i32 {
+    if delay.is_absolute() {
+        return Kerr::InvalidArguments as i32;
+    }
+
+    let (r2, _) = delay.to_raw();
+    unsafe {
+        z_call_svc_kernel_4(
+            handler as usize as u32,
+            arg as u32,
+            r2,
+            KernelSyscallId::WorkSubmit as u32,
+        )
+    }
+}
+
+/// Waits for the next work item, reserved to the work queue thread.
+///
+/// When a work item is ready it is written to `slot`, otherwise the thread
+/// pends and `slot` is left untouched.
+pub fn k_work_next(slot: &mut Option<Work>) -> i32 {
+    unsafe {
+        z_call_svc_kernel_4(
+            slot as *mut Option<Work> as u32,
+            0,
+            0,
+            KernelSyscallId::WorkNext as u32,
+        )
+    }
+}
+
+/// Returns the maximum number of bytes of its stack the current thread has used.
+pub fn k_stack_usage() -> usize {
+    unsafe { z_call_svc_kernel_4(0, 0, 0, KernelSyscallId::StackUsage as u32) as usize }
+}
+
+/// Reads statistics of the kernel into `info`, whose type depends on the query.
+///
+/// Returns `Kerr::NotSupported` if the kernel is built without `kernel-stats`.
+fn k_stats<T>(query: StatsQuery, index: u32, info: &mut T) -> i32 {
+    unsafe {
+        z_call_svc_kernel_4(
+            query as u32 | index << STATS_INDEX_SHIFT,
+            info as *mut T as u32,
+            size_of::<T>() as u32,
+            KernelSyscallId::Stats as u32,
+        )
+    }
+}
+
+/// Reads the statistics of the current thread.
+pub fn k_thread_stats(info: &mut ThreadStatsInfo) -> i32 {
+    k_stats(StatsQuery::Thread, 0, info)
+}
+
+/// Reads the kernel-wide statistics.
+pub fn k_kernel_stats(info: &mut KernelStatsInfo) -> i32 {
+    k_stats(StatsQuery::Kernel, 0, info)
+}
+
+/// Reads the counter of the n-th distinct syscall invoked since boot,
+/// `Kerr::NoEntry` is returned past the last one.
+pub fn k_syscall_stats(index: u32, info: &mut SyscallStatsInfo) -> i32 {
+    k_stats(StatsQuery::Syscall, index, info)
+}
+
+/// Reads the contention statistics of a kernel object.
+pub fn k_kobj_stats(kobj: i32, info: &mut KobjStatsInfo) -> i32 {
+    k_stats(StatsQuery::Kobj, kobj as u32, info)
+}
+
+fn k_sysinfo<T>(query: SysInfoQuery, buf: *mut T, len: usize) -> i32 {
+    unsafe {
+        z_call_svc_kernel_4(
+            query as u32,
+            buf as u32,
+            len as u32,
+            KernelSyscallId::SysInfo as u32,
+        )
+    }
+}
+
+/// Reads the kernel version and enabled features.
+pub fn k_sysinfo_version(info: &mut VersionInfo) -> i32 {
+    k_sysinfo(SysInfoQuery::Version, info, size_of::<VersionInfo>())
+}
+
+/// Takes a snapshot of the threads, returns the number of threads written
+/// (threads which do not fit in `threads` are omitted).
+pub fn k_sysinfo_threads(threads: &mut [ThreadInfo]) -> i32 {
+    k_sysinfo(
+        SysInfoQuery::Threads,
+        threads.as_mut_ptr(),
+        size_of_val(threads),
+    )
+}
+
+/// Takes a snapshot of the live kernel objects, returns the number of kernel
+/// objects written (kernel objects which do not fit in `kobjs` are omitted).
+pub fn k_sysinfo_kobjs(kobjs: &mut [KobjInfo]) -> i32 {
+    k_sysinfo(SysInfoQuery::Kobjs, kobjs.as_mut_ptr(), size_of_val(kobjs))
+}
+
+/// Reads the kernel heap usage.
+pub fn k_sysinfo_memory(info: &mut MemoryInfo) -> i32 {
+    k_sysinfo(SysInfoQuery::Memory, info, size_of::<MemoryInfo>())
+}
+
+/// Stops the thread with the given identifier, reserved to trusted threads
+/// (`Kerr::EPERM` is returned otherwise).
+pub fn k_kill(tid: u32) -> i32 {
+    unsafe { z_call_svc_kernel_4(tid, 0, 0, KernelSyscallId::Kill as u32) }
+}
+
+/// Loads the ELF image registered with the given name and starts its thread,
+/// returns the identifier of the thread. Reserved to trusted threads
+/// (`Kerr::EPERM` is returned otherwise). `Kerr::EBUSY` is returned if
+/// `MAX_LOADED_THREADS` loaded threads are still running.
+pub fn k_load(name: &str) -> i32 {
+    unsafe {
+        z_call_svc_kernel_4(
+            name.as_ptr() as u32,
+            name.len() as u32,
+            0,
+            KernelSyscallId::Load as u32,
+        )
+    }
+}
+
+/// Resets the system, only returns on error (`Kerr::EPERM` if the thread is
+/// not trusted). The hosted process exits with `hosted::REBOOT_EXIT_STATUS`.
+pub fn k_reboot() -> i32 {
+    unsafe { z_call_svc_kernel_4(0, 0, 0, KernelSyscallId::Reboot as u32) }
+}
+
+/// Exits the emulator with the given status code (semihosting), e.g. the
+/// number of failed tests. Without a debugger attached, the kernel faults.
+///
+/// Only returns on error: `Kerr::NotSupported` without the `kernel-exit`
+/// feature, `Kerr::EPERM` if the thread is not trusted.
+pub fn k_exit(status: u32) -> i32 {
+    unsafe { z_call_svc_kernel_4(status, 0, 0, KernelSyscallId::Exit as u32) }
+}
+
+/// Writes the kernel trace buffer to the stdio UART as a binary stream, then
+/// empties it. Returns `Kerr::NotSupported` without the `kernel-trace` feature.
+pub fn k_trace_dump() -> i32 {
+    unsafe { z_call_svc_kernel_4(0, 0, 0, KernelSyscallId::TraceDump as u32) }
+}
+
+/// Returns the time elapsed since the kernel started, in milliseconds.
+pub fn k_uptime_ms() -> u64 {
+    unsafe { z_call_svc_kernel_4_ret64(0, 0, 0, KernelSyscallId::UptimeMs as u32) }
+}
+
+/// Returns the number of system ticks elapsed since the kernel started.
+pub fn k_uptime_ticks() -> u64 {
+    unsafe { z_call_svc_kernel_4_ret64(0, 0, 0, KernelSyscallId::UptimeTicks as u32) }
+}
+
+/// Returns the time elapsed since the kernel started, in microseconds.
+pub fn k_uptime_us() -> u64 {
+    unsafe { z_call_svc_kernel_4_ret64(0, 0, 0, KernelSyscallId::UptimeUs as u32) }
+}
+
+/// Returns the hardware cycle counter, wrapping around on 32 bits.
+pub fn k_cycle_get() -> u32 {
+    unsafe { z_call_svc_kernel_4(0, 0, 0, KernelSyscallId::CycleGet as u32) as u32 }
+}
+
 pub fn k_stdio_read1() -> Option<u8> {
     let ret =
         unsafe { z_call_svc_4::<{ SyscallId::Io as u8 }>(0, 0, 0, IoSyscallId::Read1 as u32) };
@@ -181,11 +474,11 @@ pub fn k_stdio_read1() -> Option<u8> {
 }
 
 pub fn z_user_print(args: Arguments<'_>, nl: bool) {
-    struct UserIo(bool); // Boolean parameter to print a newline
+    struct UserIo;
 
     impl Write for UserIo {
         fn write_str(&mut self, s: &str) -> core::fmt::Result {
-            let ret = k_print(s, self.0);
+            let ret = k_print(s, false);
             if ret < 0 {
                 Err(core::fmt::Error)
             } else {
@@ -194,7 +487,11 @@ pub fn z_user_print(args: Arguments<'_>, nl: bool) {
         }
     }
 
-    let _ = UserIo(nl).write_fmt(args);
+    // The arguments may be written in several parts, the newline is printed
+    // after the last one
+    if UserIo.write_fmt(args).is_ok() && nl {
+        k_print("", true);
+    }
 }
 
 #[macro_export]
@@ -0,0 +1,245 @@
+//! Work queue for deferred work.
+//!
+//! The work queue is serviced by a dedicated kernel-managed thread which executes
+//! submitted work items (a handler and its argument) one after the other. Work
+//! can be submitted by user threads through a syscall or directly by the kernel
+//! (e.g. while handling an interrupt), optionally after a delay.
+//!
+//! Handlers run on the stack of the work queue thread, with the memory regions
+//! of the thread which submitted them. Loaded ELF images cannot submit work
+//! through the syscall, their handlers would run without their `r9` (GOT).
+//!
+//! When no work is ready, the work queue thread pends on a `Sync` kernel object
+//! until new work is submitted or the next delayed work item is due.
+
+use core::{ffi::c_void, mem};
+
+#[cfg(feature = "kernel-workqueue")]
+use core::ptr::{self, addr_of_mut};
+
+#[cfg(feature = "kernel-workqueue")]
+use super::{stack::Stack, thread::Thread, timeout::TimeoutInstant, userspace, CpuVariant};
+#[cfg(feature = "kernel-workqueue")]
+use crate::mem::region::{MemoryRegion, MAX_THREAD_REGIONS};
+
+#[cfg(not(feature = "hosted"))]
+pub const WORKQ_STACK_SIZE: usize = 4096;
+#[cfg(feature = "hosted")]
+pub const WORKQ_STACK_SIZE: usize = crate::hosted::MIN_STACK_SIZE;
+
+/// Maximum number of work items waiting in the queue.
+pub const WORKQ_DEPTH: usize = 16;
+
+/// The work queue thread is cooperative so that deferred work is executed
+/// before any preemptive thread.
+pub const WORKQ_PRIORITY: i8 = -1;
+
+/// Work queue thread stack, aligned on its size so that the MPU covers it exactly.
+#[cfg(feature = "kernel-workqueue")]
+#[cfg_attr(not(feature = "hosted"), repr(C, align(4096)))]
+struct WorkqStack(Stack<WORKQ_STACK_SIZE>);
+
+#[cfg(feature = "kernel-workqueue")]
+#[link_section = ".noinit"]
+static mut WORKQ_STACK: WorkqStack = WorkqStack(Stack::uninit());
+
+/// Function executed by the work queue thread for a work item.
+pub type WorkHandler = extern "C" fn(*mut c_void);
+
+/// A unit of deferred work: a handler and the argument to call it with.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct Work {
+    pub handler: WorkHandler,
+    pub arg: *mut c_void,
+}
+
+impl Work {
+    pub const fn new(handler: WorkHandler, arg: *mut c_void) -> Self {
+        Work { handler, arg }
+    }
+
+    /// Builds a work item from raw syscall arguments.
+    ///
+    /// Returns `None` if the handler is a null pointer.
+    ///
+    /// # Safety
+    ///
+    /// `handler` must be the address of a function with the `WorkHandler` signature.
+    pub unsafe fn from_raw(handler: *const c_void, arg: *mut c_void) -> Option<Self> {
+        if handler.is_null() {
+            None
+        } else {
+            Some(Work {
+                handler: mem::transmute::<*const c_void, WorkHandler>(handler),
+                arg,
+            })
+        }
+    }
+
+    pub fn run(&self) {
+        (self.handler)(self.arg)
+    }
+}
+
+#[cfg(feature = "kernel-workqueue")]
+/// A work item waiting in the queue.
+#[derive(Clone, Copy)]
+struct WorkItem {
+    work: Work,
+    /// Instant (in ticks) from which the work can be executed.
+    at: u64,
+    /// Submission order, used to keep items due at the same instant FIFO.
+    seq: u32,
+    /// Memory regions the handler runs with, those of the submitting thread.
+    regions: [Option<MemoryRegion>; MAX_THREAD_REGIONS],
+}
+
+#[cfg(feature = "kernel-workqueue")]
+pub struct WorkQueue<'a, CPU: CpuVariant> {
+    items: [Option<WorkItem>; WORKQ_DEPTH],
+    seq: u32,
+
+    /// The thread executing the work items.
+    thread: Option<&'a Thread<'a, CPU>>,
+
+    /// Kernel object the work queue thread pends on when no work is ready.
+    pub(super) kobj: Option<i32>,
+}
+
+#[cfg(feature = "kernel-workqueue")]
+impl<'a, CPU: CpuVariant> WorkQueue<'a, CPU> {
+    pub const fn new() -> Self {
+        WorkQueue {
+            items: [None; WORKQ_DEPTH],
+            seq: 0,
+            thread: None,
+            kobj: None,
+        }
+    }
+
+    /// Creates the work queue thread.
+    pub fn init_thread() -> Thread<'a, CPU> {
+        let stack_info = unsafe { &mut (*addr_of_mut!(WORKQ_STACK)).0 }.get_info();
+
+        let thread = Thread::init(
+            &stack_info,
+            Self::workq_entry,
+            ptr::null_mut(),
+            WORKQ_PRIORITY,
+        );
+        thread.set_name("workq");
+
+        thread
+    }
+
+    extern "C" fn workq_entry(_arg0: *mut c_void) -> ! {
+        let mut slot: Option<Work> = None;
+
+        loop {
+            // Either returns with a work item written in the slot, or when
+            // the thread is woken up (new work submitted or delayed work due)
+            userspace::k_work_next(&mut slot);
+
+            if let Some(work) = slot.take() {
+                work.run();
+            }
+        }
+    }
+
+    pub(super) fn set_thread(&mut self, thread: &'a Thread<'a, CPU>) {
+        self.thread = Some(thread);
+    }
+
+    /// Returns whether the given thread is the work queue thread.
+    pub fn is_workq_thread(&self, thread: &Thread<'a, CPU>) -> bool {
+        self.thread.is_some_and(|workq| ptr::eq(workq, thread))
+    }
+
+    /// Appends a work item to execute from the tick instant `at`, with the
+    /// given memory regions.
+    ///
+    /// Returns the work back if the queue is full.
+    pub fn push(
+        &mut self,
+        work: Work,
+        regions: [Option<MemoryRegion>; MAX_THREAD_REGIONS],
+        at: u64,
+    ) -> Result<(), Work> {
+        let seq = self.seq;
+
+        match self.items.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot) => {
+                *slot = Some(WorkItem {
+                    work,
+                    at,
+                    seq,
+                    regions,
+                });
+                self.seq = seq.wrapping_add(1);
+                Ok(())
+            }
+            None => Err(work),
+        }
+    }
+
+    /// Removes and returns the oldest work item due at `now`, with the memory
+    /// regions its handler runs with.
+    pub fn pop_ready(
+        &mut self,
+        now: u64,
+    ) -> Option<(Work, [Option<MemoryRegion>; MAX_THREAD_REGIONS])> {
+        self.items
+            .iter_mut()
+            .filter(|slot| slot.is_some_and(|item| item.at <= now))
+            .min_by_key(|slot| slot.map(|item| (item.at, item.seq)))
+            .and_then(|slot| slot.take())
+            .map(|item| (item.work, item.regions))
+    }
+
+    /// Returns the instant at which the next delayed work item is due.
+    pub fn next_instant(&self) -> TimeoutInstant {
+        self.items
+            .iter()
+            .flatten()
+            .map(|item| item.at)
+            .min()
+            .map(TimeoutInstant::new_at)
+            .unwrap_or(TimeoutInstant::new_never())
+    }
+}
+
+#[cfg(feature = "kernel-workqueue")]
+impl<'a, CPU: CpuVariant> Default for WorkQueue<'a, CPU> {
+    fn default() -> Self {
+        WorkQueue::new()
+    }
+}
+
+#[cfg(all(test, feature = "kernel-workqueue"))]
+mod tests {
+    use super::*;
+    use crate::{kernel::mock::MockCpu, mem::region::Access};
+
+    extern "C" fn handler(_arg: *mut c_void) {}
+
+    #[test]
+    fn test_pop_ready_regions() {
+        let mut workq = WorkQueue::<MockCpu>::new();
+        let work = Work::new(handler, ptr::null_mut());
+        let kernel = [None; MAX_THREAD_REGIONS];
+        let mut user = [None; MAX_THREAD_REGIONS];
+        user[0] = Some(MemoryRegion::new(0x2000_0000, 32, Access::ReadWrite, false));
+
+        assert!(workq.push(work, user, 10).is_ok());
+        assert!(workq.push(work, kernel, 0).is_ok());
+
+        // Each handler runs with the memory regions of its submitter
+        assert_eq!(
+            workq.pop_ready(10).map(|(_, regions)| regions),
+            Some(kernel)
+        );
+        assert_eq!(workq.pop_ready(10).map(|(_, regions)| regions), Some(user));
+        assert!(workq.pop_ready(10).is_none());
+    }
+}
@@ -1,6 +1,6 @@
-#![no_std]
-#![feature(stdarch_arm_hints)]
-#![feature(stdarch_arm_neon_intrinsics)]
+#![cfg_attr(not(test), no_std)]
+#![cfg_attr(target_arch = "arm", feature(stdarch_arm_hints))]
+#![cfg_attr(target_arch = "arm", feature(stdarch_arm_neon_intrinsics))]
 #![feature(core_intrinsics)]
 #![feature(maybe_uninit_uninit_array)]
 #![feature(asm_const)]
@@ -9,6 +9,8 @@
 extern crate alloc;
 
 pub mod cortex_m;
+#[cfg(feature = "hosted")]
+pub mod hosted;
 pub mod kernel;
 pub mod list;
 pub mod mem;
@@ -29,14 +29,14 @@ pub trait Node<'a, T: 'a + Node<'a, T, M>, M: 'a + Marker> {
 /// Removing the Option<> will reduce the amount of .unwrap() and Some()
 ///
 /// Warning:
-/// ```
+/// ```text
 /// the type &T does not permit being left uninitialized
 /// references must be non-null
 /// #[warn(invalid_value)] on by defaultrustcClick for full compiler diagnostic
 /// ```
 ///
 /// Code illustration:
-/// ```rust
+/// ```rust,ignore
 /// Link(
 ///     Cell::new(unsafe { MaybeUninit::<&T>::uninit().assume_init() }),
 ///     PhantomData,
@@ -7,10 +7,15 @@ use core::{
     sync::atomic::{AtomicUsize, Ordering},
 };
 
-const MAX_SUPPORTED_ALIGN: usize = 8;
+#[cfg(not(target_has_atomic = "ptr"))]
+use crate::cortex_m::interrupts;
+
+#[cfg(not(feature = "microbit"))]
 const KERNEL_ALLOCATOR_SIZE: usize = 65536;
+// The nRF51822 only has 16 KiB of RAM
+#[cfg(feature = "microbit")]
+const KERNEL_ALLOCATOR_SIZE: usize = 4096;
 
-// Align must match MAX_SUPPORTED_ALIGN
 #[repr(C, align(8))]
 pub struct BumpAllocator<const SIZE: usize> {
     arena: UnsafeCell<[u8; SIZE]>,
@@ -23,7 +28,7 @@ impl Default for BumpAllocator<KERNEL_ALLOCATOR_SIZE> {
     }
 }
 
-#[global_allocator]
+#[cfg_attr(target_arch = "arm", global_allocator)]
 pub static KERNEL_ALLOCATOR: BumpAllocator<KERNEL_ALLOCATOR_SIZE> = BumpAllocator::new();
 
 impl<const SIZE: usize> BumpAllocator<SIZE> {
@@ -33,6 +38,16 @@ impl<const SIZE: usize> BumpAllocator<SIZE> {
             remaining: AtomicUsize::new(SIZE),
         }
     }
+
+    /// Size of the arena in bytes.
+    pub const fn size(&self) -> usize {
+        SIZE
+    }
+
+    /// Number of bytes which have not been allocated yet.
+    pub fn remaining(&self) -> usize {
+        self.remaining.load(Ordering::Relaxed)
+    }
 }
 
 unsafe impl<const SIZE: usize> Sync for BumpAllocator<SIZE> {}
@@ -42,27 +57,47 @@ unsafe impl<const SIZE: usize> GlobalAlloc for BumpAllocator<SIZE> {
         let size = layout.size();
         let align = layout.align();
 
-        if align > MAX_SUPPORTED_ALIGN {
-            return null_mut();
-        }
+        // Allocations are aligned on their absolute address, so that large
+        // alignments (e.g. MPU regions) are honored whatever the arena address
+        let arena = self.arena.get() as usize;
 
         let mut offset = 0;
-        if self
+        let reserve = |remaining: usize| {
+            if remaining < size {
+                return None;
+            }
+
+            let align_mask = !(align - 1);
+
+            let start = (arena + remaining - size) & align_mask;
+            if start < arena {
+                return None;
+            }
+
+            offset = start - arena;
+            Some(offset)
+        };
+
+        #[cfg(target_has_atomic = "ptr")]
+        let reserved = self
             .remaining
-            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |mut remaining| {
-                if remaining < size {
-                    return None;
-                }
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, reserve)
+            .is_ok();
 
-                let align_mask = !(align - 1);
+        // No compare-and-swap on ARMv6-M, interrupts are masked instead
+        #[cfg(not(target_has_atomic = "ptr"))]
+        let reserved = interrupts::atomic_restore(|_cs| {
+            let mut reserve = reserve;
+            match reserve(self.remaining.load(Ordering::Relaxed)) {
+                Some(remaining) => {
+                    self.remaining.store(remaining, Ordering::Relaxed);
+                    true
+                }
+                None => false,
+            }
+        });
 
-                remaining -= size;
-                remaining &= align_mask;
-                offset = remaining;
-                Some(remaining)
-            })
-            .is_err()
-        {
+        if !reserved {
             return null_mut();
         }
 
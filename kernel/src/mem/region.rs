@@ -1,8 +1,84 @@
-// use core::{marker::PhantomData, mem::MaybeUninit};
+//! Memory regions a thread is allowed to access.
+//!
+//! Regions are architecture agnostic, they are translated to memory protection
+//! unit (MPU) regions when the thread is switched to.
 
-// #[repr(align(4))]
-// pub struct MemoryRegion {
-//     buf: MaybeUninit<[u8;
+use core::ptr::addr_of;
 
-//     ]
-// }
+/// Maximum number of memory regions of a thread, in addition to its stack.
+pub const MAX_THREAD_REGIONS: usize = 6;
+
+/// Access permissions granted to a thread on a memory region.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    ReadOnly,
+    ReadWrite,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryRegion {
+    /// Start address of the region
+    pub start: usize,
+    /// Size of the region in bytes
+    pub size: usize,
+    pub access: Access,
+    /// Whether code can be executed from the region
+    pub executable: bool,
+}
+
+impl MemoryRegion {
+    pub const fn new(start: usize, size: usize, access: Access, executable: bool) -> Self {
+        MemoryRegion {
+            start,
+            size,
+            access,
+            executable,
+        }
+    }
+
+    /// Region covering an object, typically a static shared between threads.
+    pub fn of<T>(obj: &T, access: Access) -> Self {
+        MemoryRegion::new(obj as *const T as usize, size_of::<T>(), access, false)
+    }
+
+    /// Region covering the whole flash (code and read-only data), as defined
+    /// by the linker script.
+    pub fn flash() -> Self {
+        #[cfg(not(feature = "hosted"))]
+        extern "C" {
+            static _flash_start: u8;
+            static _flash_end: u8;
+        }
+
+        // Text and read-only data of the hosted executable (default GNU ld
+        // script), up to its data
+        #[cfg(feature = "hosted")]
+        extern "C" {
+            #[link_name = "__executable_start"]
+            static _flash_start: u8;
+            #[link_name = "__data_start"]
+            static _flash_end: u8;
+        }
+
+        let start = unsafe { addr_of!(_flash_start) } as usize;
+        let end = unsafe { addr_of!(_flash_end) } as usize;
+
+        MemoryRegion::new(start, end - start, Access::ReadOnly, true)
+    }
+
+    /// End address of the region (excluded).
+    pub fn end(&self) -> usize {
+        self.start + self.size
+    }
+
+    /// Checks whether the range `[addr, addr + len)` lies within the region.
+    pub fn contains(&self, addr: usize, len: usize) -> bool {
+        addr >= self.start && addr.checked_add(len).is_some_and(|end| end <= self.end())
+    }
+
+    /// Checks whether the range `[addr, addr + len)` lies within the region and
+    /// the region grants the requested access.
+    pub fn allows(&self, addr: usize, len: usize, access: Access) -> bool {
+        self.contains(addr, len) && (access == Access::ReadOnly || self.access == Access::ReadWrite)
+    }
+}
@@ -1,3 +1,5 @@
+//! CMSDK APB UART, shared by the MPS2 SoCs.
+
 use volatile_register::RW;
 
 use crate::serial::{SerialConfig, SerialTrait};
@@ -18,6 +20,15 @@ pub struct Uart {
     pub bauddiv: RW<u32>,
 }
 
+const CTRL_TXEN: u32 = 1 << 0;
+const CTRL_RXEN: u32 = 1 << 1;
+const CTRL_RXINTEN: u32 = 1 << 3;
+
+const STATE_TXFULL: u32 = 1 << 0;
+const STATE_RXFULL: u32 = 1 << 1;
+
+const INT_RX: u32 = 1 << 1;
+
 pub struct UartDevice<const FCPU: u32> {
     uart: *mut Uart,
 }
@@ -26,23 +37,38 @@ impl<const FCPU: u32> UartDevice<FCPU> {
     pub fn new(uart: *mut Uart) -> Self {
         UartDevice { uart }
     }
+
+    /// Raises the receive interrupt of the UART when a byte is received, it
+    /// must also be enabled in the NVIC (e.g. `Irqn::uart_rx`).
+    pub fn enable_rx_interrupt(&self) {
+        unsafe { (*self.uart).ctrl.modify(|ctrl| ctrl | CTRL_RXINTEN) }
+    }
+
+    pub fn disable_rx_interrupt(&self) {
+        unsafe { (*self.uart).ctrl.modify(|ctrl| ctrl & !CTRL_RXINTEN) }
+    }
+
+    /// Acknowledges the receive interrupt, from its handler.
+    pub fn clear_rx_interrupt(&self) {
+        unsafe { (*self.uart).int.write(INT_RX) }
+    }
 }
 
 impl<const FCPU: u32> SerialTrait for UartDevice<FCPU> {
     fn init(&self, config: &SerialConfig) {
         unsafe { (*self.uart).bauddiv.write(FCPU / config.baudrate) } // Set baudrate
-        unsafe { (*self.uart).ctrl.write(0x03) } // Enable RX and TX
+        unsafe { (*self.uart).ctrl.write(CTRL_TXEN | CTRL_RXEN) }
     }
 
     fn write_byte(&self, c: u8) {
-        while unsafe { (*self.uart).state.read() & 0x1 } != 0 {}
+        while unsafe { (*self.uart).state.read() & STATE_TXFULL } != 0 {}
         unsafe { (*self.uart).data.write(c as u32) }
     }
 
     fn read(&self) -> Option<u8> {
         let state = unsafe { (*self.uart).state.read() };
 
-        if state & 0x2 != 0 {
+        if state & STATE_RXFULL != 0 {
             let data = unsafe { (*self.uart).data.read() };
             Some(data as u8)
         } else {
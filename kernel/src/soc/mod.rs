@@ -1,2 +1,35 @@
+#[cfg(any(
+    feature = "mps2-an385",
+    feature = "mps2-an386",
+    feature = "mps2-an505"
+))]
+pub mod cmsdk_uart;
 #[cfg(any(feature = "mps2-an385", feature = "mps2-an386"))]
 pub mod mps2_an38x;
+#[cfg(feature = "mps2-an505")]
+pub mod mps2_an505;
+#[cfg(feature = "microbit")]
+pub mod nrf51;
+#[cfg(feature = "stm32f405")]
+pub mod stm32f4;
+
+#[cfg(all(
+    feature = "stm32f405",
+    any(
+        feature = "mps2-an385",
+        feature = "mps2-an386",
+        feature = "mps2-an505",
+        feature = "microbit"
+    )
+))]
+compile_error!("only one SoC can be selected, disable the default features to select `stm32f405`");
+
+// UART of the stdio console and number of device interrupts
+#[cfg(any(feature = "mps2-an385", feature = "mps2-an386"))]
+pub use mps2_an38x::{UartDevice, IRQ_COUNT, UART0};
+#[cfg(feature = "mps2-an505")]
+pub use mps2_an505::{UartDevice, IRQ_COUNT, UART0};
+#[cfg(feature = "microbit")]
+pub use nrf51::{UartDevice, IRQ_COUNT, UART0};
+#[cfg(feature = "stm32f405")]
+pub use stm32f4::{UartDevice, IRQ_COUNT, UART0};
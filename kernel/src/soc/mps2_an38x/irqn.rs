@@ -0,0 +1,69 @@
+use crate::cortex_m::nvic::InterruptNumber;
+
+use super::{Uart, UART0, UART1, UART2, UART3, UART4};
+
+/*
+ * Device interrupts of the MPS2 AN385/AN386 (CMSDK peripherals), see the
+ * interrupt map of the application notes.
+ */
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Irqn {
+    UART0RX = 0,      /*  0 UART 0 receive interrupt */
+    UART0TX = 1,      /*  1 UART 0 transmit interrupt */
+    UART1RX = 2,      /*  2 UART 1 receive interrupt */
+    UART1TX = 3,      /*  3 UART 1 transmit interrupt */
+    UART2RX = 4,      /*  4 UART 2 receive interrupt */
+    UART2TX = 5,      /*  5 UART 2 transmit interrupt */
+    GPIO0 = 6,        /*  6 GPIO 0 combined interrupt */
+    GPIO1 = 7,        /*  7 GPIO 1 combined interrupt */
+    TIMER0 = 8,       /*  8 Timer 0 interrupt */
+    TIMER1 = 9,       /*  9 Timer 1 interrupt */
+    DUALTIMER = 10,   /* 10 Dual timer interrupt */
+    SPI = 11,         /* 11 SPI interrupt */
+    UARTOVF = 12,     /* 12 UART 0, 1, 2, 3 and 4 overflow interrupt */
+    ETHERNET = 13,    /* 13 Ethernet interrupt */
+    I2S = 14,         /* 14 Audio I2S interrupt */
+    TOUCHSCREEN = 15, /* 15 Touch screen interrupt */
+    GPIO2 = 16,       /* 16 GPIO 2 combined interrupt */
+    GPIO3 = 17,       /* 17 GPIO 3 combined interrupt */
+    UART3RX = 18,     /* 18 UART 3 receive interrupt */
+    UART3TX = 19,     /* 19 UART 3 transmit interrupt */
+    UART4RX = 20,     /* 20 UART 4 receive interrupt */
+    UART4TX = 21,     /* 21 UART 4 transmit interrupt */
+    SPI2 = 22,        /* 22 SPI 2 interrupt */
+    SPI3SPI4 = 23,    /* 23 SPI 3 and 4 interrupt */
+}
+
+impl InterruptNumber for Irqn {
+    #[inline(always)]
+    fn number(self) -> u16 {
+        self as u16
+    }
+}
+
+impl Irqn {
+    /// Receive interrupt of the UART.
+    pub fn uart_rx(uart: *mut Uart) -> Option<Self> {
+        match uart {
+            UART0 => Some(Irqn::UART0RX),
+            UART1 => Some(Irqn::UART1RX),
+            UART2 => Some(Irqn::UART2RX),
+            UART3 => Some(Irqn::UART3RX),
+            UART4 => Some(Irqn::UART4RX),
+            _ => None,
+        }
+    }
+
+    /// Transmit interrupt of the UART.
+    pub fn uart_tx(uart: *mut Uart) -> Option<Self> {
+        match uart {
+            UART0 => Some(Irqn::UART0TX),
+            UART1 => Some(Irqn::UART1TX),
+            UART2 => Some(Irqn::UART2TX),
+            UART3 => Some(Irqn::UART3TX),
+            UART4 => Some(Irqn::UART4TX),
+            _ => None,
+        }
+    }
+}
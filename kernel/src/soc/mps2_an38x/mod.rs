@@ -1,6 +1,10 @@
-pub mod uart;
+pub mod irqn;
 
-pub use uart::{Uart, UartDevice};
+pub use super::cmsdk_uart::{Uart, UartDevice};
+pub use irqn::Irqn;
+
+/// Number of device interrupts (vector table entries after SysTick)
+pub const IRQ_COUNT: usize = 32;
 
 pub const APB_BASE: usize = 0x4000_0000;
 
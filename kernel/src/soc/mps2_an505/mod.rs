@@ -0,0 +1,21 @@
+//! MPS2 AN505 (Cortex-M33, ARMv8-M Mainline), the kernel runs in the secure
+//! state and accesses the peripherals through their secure aliases.
+
+pub use super::cmsdk_uart::{Uart, UartDevice};
+
+/// Number of device interrupts (vector table entries after SysTick)
+pub const IRQ_COUNT: usize = 96;
+
+pub const APB_BASE: usize = 0x5020_0000;
+
+pub const UART0_BASE: usize = APB_BASE;
+pub const UART1_BASE: usize = APB_BASE + 0x1000;
+pub const UART2_BASE: usize = APB_BASE + 0x2000;
+pub const UART3_BASE: usize = APB_BASE + 0x3000;
+pub const UART4_BASE: usize = APB_BASE + 0x4000;
+
+pub const UART0: *mut Uart = UART0_BASE as *mut Uart;
+pub const UART1: *mut Uart = UART1_BASE as *mut Uart;
+pub const UART2: *mut Uart = UART2_BASE as *mut Uart;
+pub const UART3: *mut Uart = UART3_BASE as *mut Uart;
+pub const UART4: *mut Uart = UART4_BASE as *mut Uart;
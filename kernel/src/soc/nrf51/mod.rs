@@ -0,0 +1,14 @@
+//! nRF51822 (Cortex-M0) of the BBC micro:bit.
+
+pub mod uart;
+
+pub use uart::{Uart, UartDevice};
+
+/// Number of device interrupts (vector table entries after SysTick)
+pub const IRQ_COUNT: usize = 32;
+
+pub const APB_BASE: usize = 0x4000_0000;
+
+pub const UART0_BASE: usize = APB_BASE + 0x2000;
+
+pub const UART0: *mut Uart = UART0_BASE as *mut Uart;
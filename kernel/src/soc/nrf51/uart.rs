@@ -0,0 +1,105 @@
+use volatile_register::{RO, RW, WO};
+
+use crate::serial::{SerialConfig, SerialTrait};
+
+/// Universal Asynchronous Receiver Transmitter (UART)
+#[repr(C)]
+pub struct Uart {
+    /// Offset: 0x000 ( /W) Start UART receiver
+    pub tasks_startrx: WO<u32>,
+    /// Offset: 0x004 ( /W) Stop UART receiver
+    pub tasks_stoprx: WO<u32>,
+    /// Offset: 0x008 ( /W) Start UART transmitter
+    pub tasks_starttx: WO<u32>,
+    /// Offset: 0x00C ( /W) Stop UART transmitter
+    pub tasks_stoptx: WO<u32>,
+    _reserved0: [u32; 62],
+    /// Offset: 0x108 (R/W) Data received in RXD
+    pub events_rxdrdy: RW<u32>,
+    _reserved1: [u32; 4],
+    /// Offset: 0x11C (R/W) Data sent from TXD
+    pub events_txdrdy: RW<u32>,
+    _reserved2: [u32; 248],
+    /// Offset: 0x500 (R/W) Enable UART
+    pub enable: RW<u32>,
+    _reserved3: [u32; 2],
+    /// Offset: 0x50C (R/W) Pin select for TXD
+    pub pseltxd: RW<u32>,
+    _reserved4: u32,
+    /// Offset: 0x514 (R/W) Pin select for RXD
+    pub pselrxd: RW<u32>,
+    /// Offset: 0x518 (R/ ) RXD register
+    pub rxd: RO<u32>,
+    /// Offset: 0x51C ( /W) TXD register
+    pub txd: WO<u32>,
+    _reserved5: u32,
+    /// Offset: 0x524 (R/W) Baud rate
+    pub baudrate: RW<u32>,
+}
+
+const ENABLE_ENABLED: u32 = 4;
+
+// Pins of the USB interface chip on the micro:bit
+const PIN_TXD: u32 = 24;
+const PIN_RXD: u32 = 25;
+
+pub struct UartDevice<const FCPU: u32> {
+    uart: *mut Uart,
+}
+
+impl<const FCPU: u32> UartDevice<FCPU> {
+    pub fn new(uart: *mut Uart) -> Self {
+        UartDevice { uart }
+    }
+
+    /// BAUDRATE register value: baudrate * 2^32 / 16 MHz, rounded to the
+    /// 4096 multiple (values of the reference manual, e.g. 0x01D7E000 for
+    /// 115200 bauds).
+    fn baudrate_reg(baudrate: u32) -> u32 {
+        let reg = ((baudrate as u64) << 32) / FCPU as u64;
+        ((reg + 0x800) as u32) & !0xFFF
+    }
+}
+
+impl<const FCPU: u32> SerialTrait for UartDevice<FCPU> {
+    fn init(&self, config: &SerialConfig) {
+        unsafe {
+            (*self.uart).pseltxd.write(PIN_TXD);
+            (*self.uart).pselrxd.write(PIN_RXD);
+            (*self.uart)
+                .baudrate
+                .write(Self::baudrate_reg(config.baudrate));
+            (*self.uart).enable.write(ENABLE_ENABLED);
+
+            (*self.uart).events_txdrdy.write(0);
+            (*self.uart).events_rxdrdy.write(0);
+            (*self.uart).tasks_starttx.write(1);
+            (*self.uart).tasks_startrx.write(1);
+        }
+    }
+
+    fn write_byte(&self, c: u8) {
+        unsafe { (*self.uart).txd.write(c as u32) }
+        while unsafe { (*self.uart).events_txdrdy.read() } == 0 {}
+        unsafe { (*self.uart).events_txdrdy.write(0) }
+    }
+
+    fn read(&self) -> Option<u8> {
+        if unsafe { (*self.uart).events_rxdrdy.read() } != 0 {
+            unsafe { (*self.uart).events_rxdrdy.write(0) }
+            let data = unsafe { (*self.uart).rxd.read() };
+            Some(data as u8)
+        } else {
+            None
+        }
+    }
+}
+
+impl<const FCPU: u32> core::fmt::Write for UartDevice<FCPU> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for b in s.as_bytes() {
+            self.write_byte(*b);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,32 @@
+//! STM32F405 (Cortex-M4F), e.g. QEMU's `olimex-stm32-h405` board.
+//!
+//! The system clock is configured by `rcc::init` from the reset handler, the
+//! console is USART1 (first serial port of QEMU).
+
+pub mod rcc;
+pub mod usart;
+
+pub use usart::{Usart, UartDevice};
+
+/// Number of device interrupts (vector table entries after SysTick)
+pub const IRQ_COUNT: usize = 82;
+
+pub const APB1_BASE: usize = 0x4000_0000;
+pub const APB2_BASE: usize = 0x4001_0000;
+pub const AHB1_BASE: usize = 0x4002_0000;
+
+pub const USART2_BASE: usize = APB1_BASE + 0x4400;
+pub const USART3_BASE: usize = APB1_BASE + 0x4800;
+pub const USART1_BASE: usize = APB2_BASE + 0x1000;
+pub const USART6_BASE: usize = APB2_BASE + 0x1400;
+
+pub const RCC_BASE: usize = AHB1_BASE + 0x3800;
+pub const FLASH_BASE: usize = AHB1_BASE + 0x3C00;
+
+pub const USART1: *mut Usart = USART1_BASE as *mut Usart;
+pub const USART2: *mut Usart = USART2_BASE as *mut Usart;
+pub const USART3: *mut Usart = USART3_BASE as *mut Usart;
+pub const USART6: *mut Usart = USART6_BASE as *mut Usart;
+
+/// UART of the console
+pub const UART0: *mut Usart = USART1;
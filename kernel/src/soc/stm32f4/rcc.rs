@@ -0,0 +1,189 @@
+//! Reset and Clock Control (RCC).
+//!
+//! The system clock is derived from the internal 16 MHz oscillator (HSI)
+//! through the main PLL: SYSCLK = 168 MHz, AHB = 168 MHz, APB1 = 42 MHz and
+//! APB2 = 84 MHz.
+//!
+//! The waits for the ready flags are bounded, a clock which does not start is
+//! reported as an error. QEMU does not model the RCC (its registers read as
+//! zero), its system clock already runs at 168 MHz.
+
+use volatile_register::RW;
+
+use super::{APB2_BASE, FLASH_BASE, RCC_BASE, USART1_BASE, USART2_BASE, USART3_BASE, USART6_BASE};
+
+#[repr(C)]
+pub struct RccRegs {
+    /// Offset: 0x000 (R/W) Clock Control Register
+    pub cr: RW<u32>,
+    /// Offset: 0x004 (R/W) PLL Configuration Register
+    pub pllcfgr: RW<u32>,
+    /// Offset: 0x008 (R/W) Clock Configuration Register
+    pub cfgr: RW<u32>,
+    /// Offset: 0x00C (R/W) Clock Interrupt Register
+    pub cir: RW<u32>,
+    /// Offset: 0x010 (R/W) AHB1 Peripheral Reset Register
+    pub ahb1rstr: RW<u32>,
+    /// Offset: 0x014 (R/W) AHB2 Peripheral Reset Register
+    pub ahb2rstr: RW<u32>,
+    /// Offset: 0x018 (R/W) AHB3 Peripheral Reset Register
+    pub ahb3rstr: RW<u32>,
+    _reserved0: u32,
+    /// Offset: 0x020 (R/W) APB1 Peripheral Reset Register
+    pub apb1rstr: RW<u32>,
+    /// Offset: 0x024 (R/W) APB2 Peripheral Reset Register
+    pub apb2rstr: RW<u32>,
+    _reserved1: [u32; 2],
+    /// Offset: 0x030 (R/W) AHB1 Peripheral Clock Enable Register
+    pub ahb1enr: RW<u32>,
+    /// Offset: 0x034 (R/W) AHB2 Peripheral Clock Enable Register
+    pub ahb2enr: RW<u32>,
+    /// Offset: 0x038 (R/W) AHB3 Peripheral Clock Enable Register
+    pub ahb3enr: RW<u32>,
+    _reserved2: u32,
+    /// Offset: 0x040 (R/W) APB1 Peripheral Clock Enable Register
+    pub apb1enr: RW<u32>,
+    /// Offset: 0x044 (R/W) APB2 Peripheral Clock Enable Register
+    pub apb2enr: RW<u32>,
+}
+
+const RCC: *const RccRegs = RCC_BASE as *const RccRegs;
+
+/// Flash Access Control Register
+const FLASH_ACR: *mut u32 = FLASH_BASE as *mut u32;
+
+const CR_HSION: u32 = 1 << 0;
+const CR_HSIRDY: u32 = 1 << 1;
+const CR_PLLON: u32 = 1 << 24;
+const CR_PLLRDY: u32 = 1 << 25;
+
+// PLL input = HSI / M = 1 MHz, VCO = 1 MHz * N = 336 MHz, SYSCLK = VCO / P,
+// 48 MHz clock (USB) = VCO / Q
+const PLLM: u32 = 16;
+const PLLN: u32 = 336;
+const PLLP: u32 = 2;
+const PLLQ: u32 = 7;
+
+const PLLCFGR_PLLN_POS: u32 = 6;
+const PLLCFGR_PLLP_POS: u32 = 16;
+const PLLCFGR_PLLQ_POS: u32 = 24;
+
+const CFGR_SW_MASK: u32 = 0b11;
+const CFGR_SW_PLL: u32 = 0b10;
+const CFGR_SWS_POS: u32 = 2;
+const CFGR_PPRE1_POS: u32 = 10;
+const CFGR_PPRE2_POS: u32 = 13;
+const PPRE_DIV2: u32 = 0b100;
+const PPRE_DIV4: u32 = 0b101;
+
+// 5 wait states (2.7-3.6 V at 168 MHz), prefetch, instruction and data caches
+const FLASH_ACR_LATENCY_5WS: u32 = 5;
+const FLASH_ACR_PRFTEN: u32 = 1 << 8;
+const FLASH_ACR_ICEN: u32 = 1 << 9;
+const FLASH_ACR_DCEN: u32 = 1 << 10;
+
+/// APB prescalers, applied to the system clock
+pub const APB1_DIV: u32 = 4;
+pub const APB2_DIV: u32 = 2;
+
+const APB2ENR_USART1EN: u32 = 1 << 4;
+const APB2ENR_USART6EN: u32 = 1 << 5;
+const APB1ENR_USART2EN: u32 = 1 << 17;
+const APB1ENR_USART3EN: u32 = 1 << 18;
+
+/// Iterations of the waits for the ready flags
+const READY_TIMEOUT: u32 = 100_000;
+
+/// Clocks which did not start within `READY_TIMEOUT` iterations.
+#[derive(Debug)]
+pub enum ClockError {
+    /// The internal oscillator is not ready.
+    HsiNotReady,
+    /// The PLL did not lock.
+    PllNotReady,
+    /// The system clock did not switch to the PLL.
+    PllNotSelected,
+}
+
+fn wait_for(cond: impl Fn() -> bool, err: ClockError) -> Result<(), ClockError> {
+    if (0..READY_TIMEOUT).any(|_| cond()) {
+        Ok(())
+    } else {
+        Err(err)
+    }
+}
+
+/// Switches the system clock to the PLL (168 MHz), must run before any
+/// peripheral is configured.
+///
+/// # Returns
+/// - `Ok(())` once the system clock is confirmed to run from the PLL.
+/// - `Err(ClockError)` if a clock did not start, the system clock is then not
+///   168 MHz.
+pub fn init() -> Result<(), ClockError> {
+    let rcc = unsafe { &*RCC };
+
+    // HSI is enabled after reset, the RCC is not modeled if it reads as zero
+    // (QEMU)
+    if rcc.cr.read() & CR_HSION == 0 {
+        return Ok(());
+    }
+
+    // HSI is the clock after reset
+    wait_for(|| rcc.cr.read() & CR_HSIRDY != 0, ClockError::HsiNotReady)?;
+
+    unsafe {
+        rcc.pllcfgr.write(
+            PLLQ << PLLCFGR_PLLQ_POS
+                | (PLLP / 2 - 1) << PLLCFGR_PLLP_POS
+                | PLLN << PLLCFGR_PLLN_POS
+                | PLLM,
+        );
+        rcc.cr.modify(|cr| cr | CR_PLLON);
+    }
+
+    wait_for(|| rcc.cr.read() & CR_PLLRDY != 0, ClockError::PllNotReady)?;
+
+    unsafe {
+        // Flash wait states before raising the frequency
+        FLASH_ACR.write_volatile(
+            FLASH_ACR_LATENCY_5WS | FLASH_ACR_PRFTEN | FLASH_ACR_ICEN | FLASH_ACR_DCEN,
+        );
+
+        rcc.cfgr.modify(|cfgr| {
+            (cfgr & !CFGR_SW_MASK)
+                | PPRE_DIV4 << CFGR_PPRE1_POS
+                | PPRE_DIV2 << CFGR_PPRE2_POS
+                | CFGR_SW_PLL
+        });
+    }
+
+    wait_for(
+        || (rcc.cfgr.read() >> CFGR_SWS_POS) & CFGR_SW_MASK == CFGR_SW_PLL,
+        ClockError::PllNotSelected,
+    )
+}
+
+/// Clock of the APB bus of the peripheral at the given address.
+pub const fn pclk(sysclk: u32, periph: usize) -> u32 {
+    if periph >= APB2_BASE {
+        sysclk / APB2_DIV
+    } else {
+        sysclk / APB1_DIV
+    }
+}
+
+/// Enables the clock of the USART at the given address.
+pub fn enable_usart(usart: usize) {
+    let rcc = unsafe { &*RCC };
+
+    unsafe {
+        match usart {
+            USART1_BASE => rcc.apb2enr.modify(|enr| enr | APB2ENR_USART1EN),
+            USART6_BASE => rcc.apb2enr.modify(|enr| enr | APB2ENR_USART6EN),
+            USART2_BASE => rcc.apb1enr.modify(|enr| enr | APB1ENR_USART2EN),
+            USART3_BASE => rcc.apb1enr.modify(|enr| enr | APB1ENR_USART3EN),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use volatile_register::RW;
+
+use super::rcc;
+use crate::serial::{SerialConfig, SerialTrait};
+
+/// Universal Synchronous Asynchronous Receiver Transmitter (USART)
+#[repr(C)]
+pub struct Usart {
+    /// Offset: 0x000 (R/W) Status Register
+    pub sr: RW<u32>,
+    /// Offset: 0x004 (R/W) Data Register
+    pub dr: RW<u32>,
+    /// Offset: 0x008 (R/W) Baud Rate Register
+    pub brr: RW<u32>,
+    /// Offset: 0x00C (R/W) Control Register 1
+    pub cr1: RW<u32>,
+    /// Offset: 0x010 (R/W) Control Register 2
+    pub cr2: RW<u32>,
+    /// Offset: 0x014 (R/W) Control Register 3
+    pub cr3: RW<u32>,
+    /// Offset: 0x018 (R/W) Guard Time and Prescaler Register
+    pub gtpr: RW<u32>,
+}
+
+const SR_RXNE: u32 = 1 << 5;
+const SR_TXE: u32 = 1 << 7;
+
+const CR1_RE: u32 = 1 << 2;
+const CR1_TE: u32 = 1 << 3;
+const CR1_UE: u32 = 1 << 13;
+
+pub struct UartDevice<const FCPU: u32> {
+    usart: *mut Usart,
+}
+
+impl<const FCPU: u32> UartDevice<FCPU> {
+    pub fn new(usart: *mut Usart) -> Self {
+        UartDevice { usart }
+    }
+
+    /// BRR register value (oversampling by 16): the USARTDIV fixed point value
+    /// (12.4) is the APB clock divided by the baudrate, rounded.
+    fn brr(&self, baudrate: u32) -> u32 {
+        let pclk = rcc::pclk(FCPU, self.usart as usize);
+        (pclk + baudrate / 2) / baudrate
+    }
+}
+
+impl<const FCPU: u32> SerialTrait for UartDevice<FCPU> {
+    fn init(&self, config: &SerialConfig) {
+        rcc::enable_usart(self.usart as usize);
+
+        unsafe {
+            (*self.usart).cr1.write(0);
+            (*self.usart).brr.write(self.brr(config.baudrate));
+            // 8 data bits, no parity, 1 stop bit
+            (*self.usart).cr2.write(0);
+            (*self.usart).cr3.write(0);
+            (*self.usart).cr1.write(CR1_UE | CR1_TE | CR1_RE);
+        }
+    }
+
+    fn write_byte(&self, c: u8) {
+        while unsafe { (*self.usart).sr.read() } & SR_TXE == 0 {}
+        unsafe { (*self.usart).dr.write(c as u32) }
+    }
+
+    fn read(&self) -> Option<u8> {
+        if unsafe { (*self.usart).sr.read() } & SR_RXNE != 0 {
+            let data = unsafe { (*self.usart).dr.read() };
+            Some(data as u8)
+        } else {
+            None
+        }
+    }
+}
+
+impl<const FCPU: u32> core::fmt::Write for UartDevice<FCPU> {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        for b in s.as_bytes() {
+            self.write_byte(*b);
+        }
+        Ok(())
+    }
+}
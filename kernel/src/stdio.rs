@@ -1,11 +1,73 @@
 use core::fmt::{Arguments, Write};
 
-use crate::{cortex_m::cortex_m_rt::FCPU, serial::SerialTrait, soc::mps2_an38x::UartDevice};
+use crate::{
+    cortex_m::{cortex_m_rt::FCPU, semihosting},
+    serial::SerialTrait,
+    soc::UartDevice,
+};
 
-static mut STDIO_UART: Option<UartDevice<FCPU>> = None;
+#[cfg(feature = "hosted")]
+use crate::hosted::terminal::Terminal;
+#[cfg(not(feature = "hosted"))]
+use crate::{serial::SerialConfig, soc::UART0};
+
+/// Stdio backend.
+pub enum Console {
+    Uart(UartDevice<FCPU>),
+    /// Debugger console (e.g. QEMU with `-semihosting-config enable=on`),
+    /// output only.
+    Semihosting(semihosting::Console),
+    /// Terminal of the hosted process.
+    #[cfg(feature = "hosted")]
+    Terminal(Terminal),
+}
+
+impl Console {
+    fn write_byte(&mut self, byte: u8) {
+        match self {
+            Console::Uart(uart) => uart.write_byte(byte),
+            Console::Semihosting(console) => console.write_byte(byte),
+            #[cfg(feature = "hosted")]
+            Console::Terminal(terminal) => terminal.write_byte(byte),
+        }
+    }
+
+    fn read(&self) -> Option<u8> {
+        match self {
+            Console::Uart(uart) => uart.read(),
+            Console::Semihosting(console) => console.read(),
+            #[cfg(feature = "hosted")]
+            Console::Terminal(terminal) => terminal.read(),
+        }
+    }
+}
+
+impl Write for Console {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        match self {
+            Console::Uart(uart) => uart.write_str(s),
+            Console::Semihosting(console) => console.write_str(s),
+            #[cfg(feature = "hosted")]
+            Console::Terminal(terminal) => terminal.write_str(s),
+        }
+    }
+}
+
+static mut STDIO_UART: Option<Console> = None;
 
 pub fn set_uart(uart: UartDevice<FCPU>) {
-    unsafe { STDIO_UART = Some(uart) };
+    unsafe { STDIO_UART = Some(Console::Uart(uart)) };
+}
+
+/// Uses the debugger console as stdio, input is not available.
+pub fn set_semihosting() {
+    unsafe { STDIO_UART = Some(Console::Semihosting(semihosting::Console)) };
+}
+
+/// Uses the terminal of the hosted process as stdio.
+#[cfg(feature = "hosted")]
+pub fn set_terminal(terminal: Terminal) {
+    unsafe { STDIO_UART = Some(Console::Terminal(terminal)) };
 }
 
 pub fn read() -> Option<u8> {
@@ -49,6 +111,26 @@ pub fn write_args(args: Arguments<'_>, nl: bool) {
     }
 }
 
+/// Writes to the stdio UART, or directly to UART0 (the terminal for the hosted
+/// port) if it has not been set yet (e.g. panic during early initialization).
+pub fn write_args_fallback(args: Arguments<'_>) {
+    match unsafe { STDIO_UART.as_mut() } {
+        Some(uart) => {
+            let _ = uart.write_fmt(args);
+        }
+        #[cfg(feature = "hosted")]
+        None => {
+            let _ = Terminal.write_fmt(args);
+        }
+        #[cfg(not(feature = "hosted"))]
+        None => {
+            let mut uart = UartDevice::<FCPU>::new(UART0);
+            uart.init(&SerialConfig::default());
+            let _ = uart.write_fmt(args);
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! print {
     () => {};
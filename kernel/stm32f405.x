@@ -0,0 +1,7 @@
+/* Memory map for the STM32F405 (QEMU olimex-stm32-h405) */
+
+MEMORY
+{
+  FLASH (rx)            : ORIGIN = 0x08000000, LENGTH = 0x00100000    /* 1MB */
+  RAM (rwx)             : ORIGIN = 0x20000000, LENGTH = 0x00020000    /* 128KB SRAM1 & SRAM2 */
+}
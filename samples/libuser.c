@@ -68,3 +68,18 @@ int32_t __syscall_driver(uint32_t arg0, uint32_t arg1, uint32_t arg2, uint32_t a
 
 	return (int32_t)r0;
 }
+
+uint64_t __syscall_kernel64(uint32_t arg0, uint32_t arg1, uint32_t arg2, uint32_t arg3)
+{
+    register uint32_t r0 __asm__("r0") = arg0;
+    register uint32_t r1 __asm__("r1") = arg1;
+    register uint32_t r2 __asm__("r2") = arg2;
+    register uint32_t r3 __asm__("r3") = arg3;
+
+	__asm__ __volatile__("svc 1\n"
+						 : "+r"(r0), "+r"(r1)
+						 : "r"(r2), "r"(r3)
+						 : "memory");
+
+	return ((uint64_t)r1 << 32) | r0;
+}
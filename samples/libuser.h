@@ -14,8 +14,52 @@ int32_t __syscall_kernel(uint32_t arg0, uint32_t arg1, uint32_t arg2, uint32_t a
 int32_t __syscall_io(uint32_t arg0, uint32_t arg1, uint32_t arg2, uint32_t arg3);
 int32_t __syscall_driver(uint32_t arg0, uint32_t arg1, uint32_t arg2, uint32_t arg3);
 
+// Kernel syscall returning a 64 bits value (r0: low word, r1: high word)
+uint64_t __syscall_kernel64(uint32_t arg0, uint32_t arg1, uint32_t arg2, uint32_t arg3);
+
 #define k_yield()					   __syscall_kernel(0, 0, 0, 0)
 #define k_sleep(ms)					   __syscall_kernel(ms, 0, 0, 1)
+#define k_sleep_until(ticks)		   __syscall_kernel(ticks, 1, 0, 1)
+#define k_sleep_until_ms(ms)		   __syscall_kernel(ms, 2, 0, 1)
+#define k_usleep(us)				   __syscall_kernel(us, 3, 0, 1)
 #define k_syscall_test(r0, r1, r2, r3) __syscall_test(r0, r1, r2, r3)
+#define k_stack_usage()				   ((uint32_t)__syscall_kernel(0, 0, 0, 13))
+
+#define k_uptime_ms()	 __syscall_kernel64(0, 0, 0, 100)
+#define k_uptime_ticks() __syscall_kernel64(0, 0, 0, 101)
+#define k_cycle_get()	 ((uint32_t)__syscall_kernel(0, 0, 0, 102))
+#define k_uptime_us()	 __syscall_kernel64(0, 0, 0, 103)
+
+// Kernel statistics (kernel-stats feature), r0: query | index << 8
+struct k_thread_stats {
+	uint32_t syscalls;
+	uint32_t switches;
+	uint32_t cpu_ticks;
+	uint32_t cpu_cycles;
+	uint32_t pending_ticks;
+};
+
+struct k_kernel_stats {
+	uint32_t idle_ticks;
+	uint32_t idle_cycles;
+	uint32_t switches;
+	uint32_t syscalls;
+};
+
+#define k_thread_stats(info)                                                   \
+	__syscall_kernel(0, (uint32_t)(info), sizeof(struct k_thread_stats), 14)
+#define k_kernel_stats(info)                                                   \
+	__syscall_kernel(1, (uint32_t)(info), sizeof(struct k_kernel_stats), 14)
+
+// System introspection, returns the number of entries written for threads (1)
+// and kernel objects (2), see kernel/src/kernel/sysinfo.rs for the layouts
+#define k_sysinfo(query, buf, len)                                             \
+	__syscall_kernel(query, (uint32_t)(buf), len, 15)
+
+#define k_kill(tid)		  __syscall_kernel(tid, 0, 0, 16)
+#define k_load(name, len) __syscall_kernel((uint32_t)(name), len, 0, 17)
+#define k_reboot()		  __syscall_kernel(0, 0, 0, 18)
+#define k_trace_dump()	  __syscall_kernel(0, 0, 0, 19)
+#define k_exit(status)	  __syscall_kernel(status, 0, 0, 20)
 
 #endif // _libc_h
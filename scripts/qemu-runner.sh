@@ -1,11 +1,19 @@
 #!/usr/bin/bash
 
-# get first argument
-elf=$1
+# machine (mps2-an385, mps2-an386, mps2-an505, microbit or olimex-stm32-h405), then the elf
+machine=$1
+elf=$2
+
+case ${machine} in
+    mps2-an385) cpu=cortex-m3 ;;
+    mps2-an505) cpu=cortex-m33 ;;
+    microbit) cpu=cortex-m0 ;;
+    *) cpu=cortex-m4 ;;
+esac
 
 qemu-system-arm \
-    -cpu cortex-m4 \
-    -machine mps2-an386 \
+    -cpu ${cpu} \
+    -machine ${machine} \
     -nographic \
     -vga none \
     -semihosting-config enable=on,target=native \
@@ -0,0 +1,245 @@
+#!/usr/bin/env python3
+"""Decodes kernel trace dumps (kernel-trace feature) into a Chrome trace.
+
+The input is a raw capture of the stdio UART, e.g. QEMU started with
+`-serial file:io.txt`, in which each `TraceDump` syscall (`trace` shell
+command) wrote a binary dump. Console output around the dumps is skipped.
+
+The output JSON can be opened with chrome://tracing or https://ui.perfetto.dev
+
+    ./scripts/trace_decode.py io.txt -o trace.json
+    ./scripts/trace_decode.py io.txt --text
+"""
+
+import argparse
+import json
+import struct
+import sys
+
+MAGIC = b"KTRC"
+VERSION = 1
+HEADER = struct.Struct("<4sBBHIII")
+RECORD = struct.Struct("<IIHBx")
+
+# TraceEvent (kernel/src/kernel/trace.rs)
+THREAD_SWITCH = 0
+SYSCALL_ENTRY = 1
+SYSCALL_EXIT = 2
+SYSCALL_PENDING = 3
+INTERRUPT = 4
+TICK = 5
+KOBJ_PEND = 6
+KOBJ_RELEASE = 7
+TIMEOUT = 8
+THREAD_STOP = 9
+
+EVENT_NAMES = {
+    THREAD_SWITCH: "switch",
+    SYSCALL_ENTRY: "syscall",
+    SYSCALL_EXIT: "syscall exit",
+    SYSCALL_PENDING: "syscall pending",
+    INTERRUPT: "interrupt",
+    TICK: "tick",
+    KOBJ_PEND: "kobj pend",
+    KOBJ_RELEASE: "kobj release",
+    TIMEOUT: "timeout",
+    THREAD_STOP: "thread stop",
+}
+
+# SyscallId and functions (kernel/src/kernel/syscalls.rs)
+SYSCALLS = {
+    0: ("test", {}),
+    1: (
+        "kernel",
+        {
+            0: "yield",
+            1: "sleep",
+            3: "sync_create",
+            4: "sync",
+            5: "pend",
+            6: "cancel",
+            7: "stop",
+            8: "malloc",
+            9: "free",
+            10: "fork",
+            11: "work_submit",
+            12: "work_next",
+            13: "stack_usage",
+            14: "stats",
+            15: "sysinfo",
+            16: "kill",
+            17: "load",
+            18: "reboot",
+            19: "trace_dump",
+            100: "uptime_ms",
+            101: "uptime_ticks",
+            102: "cycle_get",
+            103: "uptime_us",
+        },
+    ),
+    2: ("io", {0: "write", 1: "read", 10: "hexprint", 11: "read1"}),
+    3: ("driver", {}),
+}
+
+IDLE_TID = 1
+# Track of the events which do not belong to a thread (syscalls, ticks)
+KERNEL_TID = 0
+
+
+def syscall_name(arg):
+    syscall_id, function = arg & 0xFF, arg >> 8
+    group, functions = SYSCALLS.get(syscall_id, (str(syscall_id), {}))
+    return "%s:%s" % (group, functions.get(function, function))
+
+
+def find_dumps(data):
+    """Yields (fcpu, lost, records) for each dump found in the capture."""
+    pos = data.find(MAGIC)
+    while pos >= 0:
+        if pos + HEADER.size > len(data):
+            break
+
+        magic, version, record_size, _, fcpu, count, lost = HEADER.unpack_from(data, pos)
+        start = pos + HEADER.size
+        end = start + count * record_size
+
+        if version != VERSION or record_size != RECORD.size or end > len(data):
+            print("skipping invalid dump at offset %d" % pos, file=sys.stderr)
+            pos = data.find(MAGIC, pos + 1)
+            continue
+
+        records = [RECORD.unpack_from(data, offset) for offset in range(start, end, record_size)]
+        yield fcpu, lost, records
+
+        pos = data.find(MAGIC, end)
+
+
+def decode(data):
+    """Returns the events of all dumps, as (time_us, tid, event, arg) tuples.
+
+    Timestamps are 32 bits cycle counters, they are unwrapped assuming less
+    than 2^32 cycles elapse between two consecutive events.
+    """
+    events = []
+    high = 0
+    last = None
+
+    for fcpu, lost, records in find_dumps(data):
+        if lost:
+            print("%d events lost before this dump" % lost, file=sys.stderr)
+
+        for timestamp, arg, tid, event in records:
+            if last is not None and timestamp < last:
+                high += 1 << 32
+            last = timestamp
+
+            events.append(((high + timestamp) * 1e6 / fcpu, tid, event, arg))
+
+    return events
+
+
+def signed(arg):
+    return arg - (1 << 32) if arg & 0x80000000 else arg
+
+
+def to_text(events):
+    lines = []
+    for ts, tid, event, arg in events:
+        name = EVENT_NAMES.get(event, str(event))
+        if event == SYSCALL_ENTRY:
+            detail = syscall_name(arg)
+        elif event == SYSCALL_EXIT:
+            detail = str(signed(arg))
+        elif event in (KOBJ_PEND, KOBJ_RELEASE):
+            detail = "kobj %d" % arg
+        elif event in (THREAD_SWITCH, TIMEOUT, THREAD_STOP):
+            detail = "tid %d" % arg
+        elif event == TICK:
+            detail = str(arg)
+        else:
+            detail = ""
+        lines.append("%14.3f us  tid %-3d %-16s %s" % (ts, tid, name, detail))
+    return "\n".join(lines)
+
+
+def to_chrome(events, pid=1):
+    trace = []
+    tids = {KERNEL_TID}
+
+    def complete(name, tid, begin, end, **args):
+        trace.append(
+            {"name": name, "ph": "X", "pid": pid, "tid": tid, "ts": begin, "dur": end - begin, "args": args}
+        )
+
+    def instant(name, tid, ts, **args):
+        trace.append({"name": name, "ph": "i", "s": "t", "pid": pid, "tid": tid, "ts": ts, "args": args})
+
+    for i, (ts, tid, event, arg) in enumerate(events):
+        tids.add(tid)
+
+        if event == THREAD_SWITCH:
+            # The thread runs until the next event, recorded once back in the kernel
+            if i + 1 < len(events):
+                name = "idle" if arg == IDLE_TID else "running"
+                complete(name, arg, ts, events[i + 1][0])
+        elif event == SYSCALL_ENTRY:
+            end = next(
+                (e for e in events[i + 1 :] if e[2] in (SYSCALL_EXIT, SYSCALL_PENDING)),
+                None,
+            )
+            if end is not None:
+                args = {"thread": tid}
+                if end[2] == SYSCALL_EXIT:
+                    args["ret"] = signed(end[3])
+                else:
+                    args["pending"] = True
+                complete(syscall_name(arg), KERNEL_TID, ts, end[0], **args)
+        elif event == INTERRUPT:
+            instant("interrupt", tid, ts)
+        elif event == TICK:
+            instant("tick", KERNEL_TID, ts, ticks=arg)
+        elif event in (KOBJ_PEND, KOBJ_RELEASE):
+            instant(EVENT_NAMES[event], tid, ts, kobj=arg)
+        elif event in (TIMEOUT, THREAD_STOP):
+            tids.add(arg)
+            instant(EVENT_NAMES[event], arg, ts)
+
+    for tid in sorted(tids):
+        if tid == KERNEL_TID:
+            name = "kernel"
+        elif tid == IDLE_TID:
+            name = "idle"
+        else:
+            name = "thread %d" % tid
+        trace.append({"name": "thread_name", "ph": "M", "pid": pid, "tid": tid, "args": {"name": name}})
+
+    return {"traceEvents": trace, "displayTimeUnit": "ns"}
+
+
+def main():
+    parser = argparse.ArgumentParser(description=__doc__, formatter_class=argparse.RawDescriptionHelpFormatter)
+    parser.add_argument("capture", help="raw capture of the stdio UART")
+    parser.add_argument("-o", "--output", help="output file (default: stdout)")
+    parser.add_argument("--text", action="store_true", help="print the events instead of a Chrome trace")
+    args = parser.parse_args()
+
+    with open(args.capture, "rb") as f:
+        events = decode(f.read())
+
+    if not events:
+        print("no trace dump found", file=sys.stderr)
+        return 1
+
+    output = to_text(events) if args.text else json.dumps(to_chrome(events), indent=1)
+
+    if args.output:
+        with open(args.output, "w") as f:
+            f.write(output + "\n")
+    else:
+        print(output)
+
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
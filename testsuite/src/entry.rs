@@ -0,0 +1,53 @@
+use core::fmt::Write;
+
+use kernel::{
+    cortex_m::{arch::CortexM, cortex_m_rt::FCPU, irqn::SysIrqn, scb::SCB, systick::SysTick},
+    kernel::{elf_loader::loader::ElfImage, kernel::Kernel},
+    println,
+    serial::{SerialConfig, SerialTrait},
+    soc::{UartDevice, UART0},
+    stdio,
+};
+
+use crate::harness;
+
+pub const FREQ_SYS_TICK: u32 = 100; // Hz
+pub const KOBJS: usize = 32;
+
+#[no_mangle]
+pub extern "C" fn _start() {
+    let mut uart = UartDevice::<FCPU>::new(UART0);
+    uart.init(&SerialConfig::default());
+    let _ = uart.write_str("kernel testsuite starting\n");
+
+    stdio::set_uart(uart);
+
+    #[cfg(feature = "semihosting")]
+    stdio::set_semihosting();
+
+    let mut scb = SCB::instance();
+    scb.set_priority(SysIrqn::PENDSV, 0x7);
+    scb.set_priority(SysIrqn::SVCALL, 0x7);
+    scb.set_priority(SysIrqn::SYSTICK, 0);
+
+    let systick = SysTick::<FREQ_SYS_TICK>::configure_period::<FCPU>(true);
+    let mut kernel = Kernel::<CortexM, KOBJS, FREQ_SYS_TICK>::init(systick);
+
+    let image = ElfImage {
+        name: "hello",
+        bytes: include_bytes!("../../samples/hello_world.elf"),
+    };
+    if let Err(e) = kernel.register_elf_image(image) {
+        println!("Error registering elf image: {:?}", e);
+    }
+
+    let (runner, threads) = harness::init_threads();
+    kernel.register_thread(&runner);
+    for thread in threads.iter() {
+        kernel.register_thread(thread);
+    }
+
+    loop {
+        kernel.kernel_loop();
+    }
+}
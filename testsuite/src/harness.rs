@@ -0,0 +1,267 @@
+//! Test runner, running the test cases one after the other and reporting the
+//! results in the TAP format (https://testanything.org):
+//!
+//! ```text
+//! TAP version 13
+//! 1..2
+//! ok 1 - sleep
+//! not ok 2 - mutex
+//!   ---
+//!   message: 'testsuite/src/tests.rs:42: ret == 0'
+//!   ...
+//! ok 3 - fpu # SKIP no FPU
+//! # passed: 1, failed: 1, skipped: 1
+//! ```
+//!
+//! Each test case runs in its own thread, along with an optional helper thread
+//! for the tests involving several threads. The runner then exits QEMU with the
+//! number of failed tests as status code.
+
+use core::{
+    ffi::c_void,
+    ptr::addr_of,
+    sync::atomic::{AtomicI32, AtomicU32, AtomicUsize, Ordering},
+};
+
+use kernel::{
+    kernel::{
+        stack::Stack,
+        sysinfo::{ThreadInfo, ThreadInfoState},
+        thread::Thread,
+        timeout::Timeout,
+        userspace, CpuVariant,
+    },
+    user_println,
+};
+
+use crate::tests::{TESTS, TEST_COUNT};
+
+pub const TEST_THREAD_SIZE: usize = 8192;
+
+/// Maximum duration of a test case, its threads are killed once elapsed.
+const TEST_TIMEOUT_MS: u64 = 2000;
+
+/// Interval at which the threads poll for their turn or for the result
+const POLL_MS: u32 = 1;
+
+const MAX_THREADS: usize = 2 * TEST_COUNT + 4;
+
+/// Location and expression of a failed check.
+#[derive(Clone, Copy, Debug)]
+pub struct Failure {
+    pub file: &'static str,
+    pub line: u32,
+    pub message: &'static str,
+}
+
+pub type TestResult = Result<(), Failure>;
+
+pub struct TestCase {
+    pub name: &'static str,
+    /// Test body, a failed check returns early with an error
+    pub body: fn() -> TestResult,
+    /// Runs in a second thread, started along with the body
+    pub helper: Option<fn()>,
+    /// Reason why the test case does not run on this target, if any
+    pub skip: Option<&'static str>,
+}
+
+/// Fails the test if the condition does not hold.
+#[macro_export]
+macro_rules! check {
+    ($cond:expr) => {
+        if !$cond {
+            return Err($crate::harness::Failure {
+                file: file!(),
+                line: line!(),
+                message: stringify!($cond),
+            });
+        }
+    };
+}
+
+/// Fails the test if the two expressions are not equal.
+#[macro_export]
+macro_rules! check_eq {
+    ($left:expr, $right:expr) => {
+        $crate::check!($left == $right)
+    };
+}
+
+const STATE_PENDING: u32 = 0;
+const STATE_PASSED: u32 = 1;
+const STATE_FAILED: u32 = 2;
+
+/// Index of the test case allowed to run, `usize::MAX` before the first one
+static CURRENT: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+static STATES: [AtomicU32; TEST_COUNT] = [const { AtomicU32::new(STATE_PENDING) }; TEST_COUNT];
+
+/// Failure of each test case, written before its state is set to failed
+static mut FAILURES: [Option<Failure>; TEST_COUNT] = [None; TEST_COUNT];
+
+/// Kernel object shared by the body of the current test case with its helper
+/// thread, reset before each test case
+static SHARED_KOBJ: AtomicI32 = AtomicI32::new(-1);
+
+/// Shares a kernel object with the helper thread of the current test case.
+pub fn publish(kobj: i32) {
+    SHARED_KOBJ.store(kobj, Ordering::Release);
+}
+
+/// Waits for the kernel object published by the body of the current test case.
+pub fn subscribe() -> i32 {
+    loop {
+        let kobj = SHARED_KOBJ.load(Ordering::Acquire);
+        if kobj >= 0 {
+            return kobj;
+        }
+        userspace::k_sleep(Timeout::from_ms(POLL_MS));
+    }
+}
+
+/// Creates the runner thread followed by a body and a helper thread per test
+/// case, the runner must be registered first so that it preempts test threads
+/// of the same priority.
+pub fn init_threads<'a, CPU: CpuVariant>() -> (Thread<'a, CPU>, [Thread<'a, CPU>; 2 * TEST_COUNT]) {
+    #[link_section = ".noinit"]
+    static mut RUNNER_STACK: Stack<TEST_THREAD_SIZE> = Stack::uninit();
+    #[link_section = ".noinit"]
+    static mut TEST_STACKS: [Stack<TEST_THREAD_SIZE>; 2 * TEST_COUNT] =
+        [const { Stack::uninit() }; 2 * TEST_COUNT];
+
+    let stack = unsafe { RUNNER_STACK.get_info() };
+    let runner = Thread::init(&stack, runner_entry, core::ptr::null_mut(), 0);
+    runner.set_name("runner");
+    // Kills the threads of the test cases which time out, exits at the end
+    runner.set_trusted();
+
+    let threads = core::array::from_fn(|i| {
+        let (index, entry): (usize, kernel::kernel::ThreadEntry) = if i < TEST_COUNT {
+            (i, body_entry)
+        } else {
+            (i - TEST_COUNT, helper_entry)
+        };
+
+        let stack = unsafe { TEST_STACKS[i].get_info() };
+        let thread = Thread::init(&stack, entry, index as *mut c_void, 0);
+        thread.set_name(TESTS[index].name);
+        // The test cases load and kill threads, not their helper thread
+        if i < TEST_COUNT {
+            thread.set_trusted();
+        }
+        thread
+    });
+
+    (runner, threads)
+}
+
+/// Waits until the runner starts the given test case.
+fn wait_turn(index: usize) {
+    while CURRENT.load(Ordering::Acquire) != index {
+        userspace::k_sleep(Timeout::from_ms(POLL_MS));
+    }
+}
+
+extern "C" fn body_entry(arg: *mut c_void) -> ! {
+    let index = arg as usize;
+
+    if TESTS[index].skip.is_none() {
+        wait_turn(index);
+
+        let state = match (TESTS[index].body)() {
+            Ok(()) => STATE_PASSED,
+            Err(failure) => {
+                unsafe { FAILURES[index] = Some(failure) };
+                STATE_FAILED
+            }
+        };
+        STATES[index].store(state, Ordering::Release);
+    }
+
+    userspace::k_stop();
+}
+
+extern "C" fn helper_entry(arg: *mut c_void) -> ! {
+    let index = arg as usize;
+
+    if let (Some(helper), None) = (TESTS[index].helper, TESTS[index].skip) {
+        wait_turn(index);
+        helper();
+    }
+
+    userspace::k_stop();
+}
+
+/// Kills the threads of a test case which did not complete in time.
+fn kill_test_threads(name: &str) {
+    let mut threads = [ThreadInfo::default(); MAX_THREADS];
+    let count = userspace::k_sysinfo_threads(&mut threads).max(0) as usize;
+
+    for thread in threads[..count]
+        .iter()
+        .filter(|thread| thread.name() == name && thread.state != ThreadInfoState::Stopped)
+    {
+        userspace::k_kill(thread.id);
+    }
+}
+
+extern "C" fn runner_entry(_arg: *mut c_void) -> ! {
+    let mut failed = 0;
+    let mut skipped = 0;
+
+    user_println!("TAP version 13");
+    user_println!("1..{}", TESTS.len());
+
+    for (index, test) in TESTS.iter().enumerate() {
+        if let Some(reason) = test.skip {
+            skipped += 1;
+            user_println!("ok {} - {} # SKIP {}", index + 1, test.name, reason);
+            continue;
+        }
+
+        let deadline = userspace::k_uptime_ms() + TEST_TIMEOUT_MS;
+        SHARED_KOBJ.store(-1, Ordering::Release);
+        CURRENT.store(index, Ordering::Release);
+
+        let state = loop {
+            let state = STATES[index].load(Ordering::Acquire);
+            if state != STATE_PENDING || userspace::k_uptime_ms() >= deadline {
+                break state;
+            }
+            userspace::k_sleep(Timeout::from_ms(POLL_MS));
+        };
+
+        // Do not let a helper thread interfere with the next test cases
+        kill_test_threads(test.name);
+
+        if state == STATE_PASSED {
+            user_println!("ok {} - {}", index + 1, test.name);
+            continue;
+        }
+
+        failed += 1;
+        user_println!("not ok {} - {}", index + 1, test.name);
+        user_println!("  ---");
+        match unsafe { (*addr_of!(FAILURES))[index] } {
+            Some(failure) if state == STATE_FAILED => user_println!(
+                "  message: '{}:{}: {}'",
+                failure.file,
+                failure.line,
+                failure.message
+            ),
+            _ => user_println!("  message: 'timed out after {} ms'", TEST_TIMEOUT_MS),
+        }
+        user_println!("  ...");
+    }
+
+    user_println!(
+        "# passed: {}, failed: {}, skipped: {}",
+        TESTS.len() - failed - skipped,
+        failed,
+        skipped
+    );
+
+    let err = userspace::k_exit(failed as u32);
+    panic!("exit failed: {}", err)
+}
@@ -0,0 +1,7 @@
+#![no_std]
+#![no_main]
+#![feature(asm_const)]
+
+pub mod entry;
+pub mod harness;
+pub mod tests;
@@ -0,0 +1,595 @@
+//! Test cases, names are at most 16 characters (thread names).
+
+use core::{
+    ffi::c_void,
+    sync::atomic::{AtomicBool, AtomicU32, Ordering},
+};
+
+use kernel::kernel::{
+    errno::Kerr,
+    stats::ThreadStatsInfo,
+    sysinfo::{MemoryInfo, ThreadInfo, VersionInfo, FEATURE_WORKQUEUE},
+    timeout::Timeout,
+    userspace,
+};
+
+use crate::{
+    check, check_eq,
+    entry::FREQ_SYS_TICK,
+    harness::{self, TestCase, TestResult},
+};
+
+pub const TEST_COUNT: usize = 14;
+
+/// The FPU test cases are reported as skipped on the SoCs without FPU
+const FPU_SKIP: Option<&str> = if cfg!(feature = "fpu") {
+    None
+} else {
+    Some("no FPU")
+};
+
+pub static TESTS: [TestCase; TEST_COUNT] = [
+    TestCase {
+        name: "sleep",
+        body: test_sleep,
+        helper: None,
+        skip: None,
+    },
+    TestCase {
+        name: "sleep_until",
+        body: test_sleep_until,
+        helper: None,
+        skip: None,
+    },
+    TestCase {
+        name: "scheduling",
+        body: test_scheduling,
+        helper: Some(helper_scheduling),
+        skip: None,
+    },
+    TestCase {
+        name: "mutex",
+        body: test_mutex,
+        helper: Some(helper_mutex),
+        skip: None,
+    },
+    TestCase {
+        name: "mutex_stopped",
+        body: test_mutex_stopped,
+        helper: Some(helper_mutex_stopped),
+        skip: None,
+    },
+    TestCase {
+        name: "semaphore",
+        body: test_semaphore,
+        helper: Some(helper_semaphore),
+        skip: None,
+    },
+    TestCase {
+        name: "signal",
+        body: test_signal,
+        helper: Some(helper_signal),
+        skip: None,
+    },
+    TestCase {
+        name: "workqueue",
+        body: test_workqueue,
+        helper: None,
+        skip: None,
+    },
+    TestCase {
+        name: "stats",
+        body: test_stats,
+        helper: None,
+        skip: None,
+    },
+    TestCase {
+        name: "sysinfo",
+        body: test_sysinfo,
+        helper: None,
+        skip: None,
+    },
+    TestCase {
+        name: "elf",
+        body: test_elf,
+        helper: None,
+        skip: None,
+    },
+    TestCase {
+        name: "untrusted",
+        body: test_untrusted,
+        helper: Some(helper_untrusted),
+        skip: None,
+    },
+    TestCase {
+        name: "fpu",
+        body: test_fpu,
+        helper: Some(helper_fpu),
+        skip: FPU_SKIP,
+    },
+    TestCase {
+        name: "fpu_first_use",
+        body: test_fpu_first_use,
+        helper: Some(helper_fpu_first_use),
+        skip: FPU_SKIP,
+    },
+];
+
+const TICK_MS: u64 = 1000 / FREQ_SYS_TICK as u64;
+
+/// Set by the helper thread once it made progress
+static HELPER_DONE: AtomicBool = AtomicBool::new(false);
+/// Value observed by the helper thread
+static HELPER_VALUE: AtomicU32 = AtomicU32::new(0);
+
+/// Shares a kernel object with the helper thread, once the helper state is reset.
+fn publish(kobj: i32) {
+    HELPER_DONE.store(false, Ordering::Release);
+    HELPER_VALUE.store(0, Ordering::Release);
+    harness::publish(kobj);
+}
+
+fn test_sleep() -> TestResult {
+    let start = userspace::k_uptime_ms();
+    check_eq!(userspace::k_sleep(Timeout::from_ms(50)), 0);
+    let elapsed = userspace::k_uptime_ms() - start;
+
+    // The sleep starts at the next tick at the latest
+    check!(elapsed >= 50);
+    check!(elapsed <= 50 + 2 * TICK_MS);
+
+    Ok(())
+}
+
+fn test_sleep_until() -> TestResult {
+    let deadline = userspace::k_uptime_ms() + 30;
+    check_eq!(userspace::k_sleep_until_ms(deadline), 0);
+
+    let now = userspace::k_uptime_ms();
+    check!(now >= deadline);
+    check!(now <= deadline + TICK_MS);
+
+    // Deadline already reached, returns immediately
+    check_eq!(userspace::k_sleep_until_ms(deadline), 0);
+
+    Ok(())
+}
+
+static SPINS: AtomicU32 = AtomicU32::new(0);
+static SPINS_STOP: AtomicBool = AtomicBool::new(false);
+
+fn test_scheduling() -> TestResult {
+    // The helper thread runs while the body sleeps, then the body preempts it
+    // once it wakes up
+    check_eq!(userspace::k_sleep(Timeout::from_ms(20)), 0);
+    check!(SPINS.load(Ordering::Acquire) > 0);
+
+    SPINS_STOP.store(true, Ordering::Release);
+
+    Ok(())
+}
+
+fn helper_scheduling() {
+    while !SPINS_STOP.load(Ordering::Acquire) {
+        SPINS.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+fn test_mutex() -> TestResult {
+    // The first kernel object created by the threads, the one of the work
+    // queue is reserved
+    let mutex = userspace::k_mutex_create();
+    check_eq!(mutex, 0);
+
+    check_eq!(userspace::k_mutex_lock(mutex, Timeout::Forever), 0);
+    publish(mutex);
+
+    // The helper thread pends on the mutex until it is unlocked
+    userspace::k_sleep(Timeout::from_ms(20));
+    check!(!HELPER_DONE.load(Ordering::Acquire));
+
+    check_eq!(userspace::k_mutex_unlock(mutex), 0);
+    userspace::k_sleep(Timeout::from_ms(20));
+    check!(HELPER_DONE.load(Ordering::Acquire));
+
+    // Unlocked by the helper thread, no thread waiting
+    check_eq!(userspace::k_mutex_lock(mutex, Timeout::from_ms(0)), 0);
+    check_eq!(userspace::k_mutex_unlock(mutex), 0);
+
+    // Not locked anymore
+    check_eq!(userspace::k_mutex_unlock(mutex), Kerr::NotSupported as i32);
+
+    Ok(())
+}
+
+fn helper_mutex() {
+    let mutex = harness::subscribe();
+
+    if userspace::k_mutex_lock(mutex, Timeout::Forever) == 0 {
+        HELPER_DONE.store(true, Ordering::Release);
+        userspace::k_mutex_unlock(mutex);
+    }
+}
+
+fn test_mutex_stopped() -> TestResult {
+    let mutex = userspace::k_mutex_create();
+    check!(mutex >= 0);
+    publish(mutex);
+
+    // The helper thread stops while owning the mutex, which is then released
+    while !HELPER_DONE.load(Ordering::Acquire) {
+        userspace::k_sleep(Timeout::from_ms(1));
+    }
+    check_eq!(userspace::k_mutex_lock(mutex, Timeout::from_ms(500)), 0);
+    check_eq!(userspace::k_mutex_unlock(mutex), 0);
+
+    Ok(())
+}
+
+fn helper_mutex_stopped() {
+    let mutex = harness::subscribe();
+
+    if userspace::k_mutex_lock(mutex, Timeout::Forever) == 0 {
+        HELPER_DONE.store(true, Ordering::Release);
+        userspace::k_sleep(Timeout::from_ms(20));
+        userspace::k_stop();
+    }
+}
+
+fn test_semaphore() -> TestResult {
+    let sem = userspace::k_semaphore_create(0, 2);
+    check!(sem >= 0);
+
+    check_eq!(
+        userspace::k_sem_take(sem, Timeout::from_ms(0)),
+        Kerr::TryAgain as i32
+    );
+    check_eq!(
+        userspace::k_sem_take(sem, Timeout::from_ms(20)),
+        Kerr::TimedOut as i32
+    );
+
+    // The count saturates at the maximum
+    for _ in 0..3 {
+        check_eq!(userspace::k_sem_give(sem), 0);
+    }
+    check_eq!(userspace::k_sem_take(sem, Timeout::from_ms(0)), 0);
+    check_eq!(userspace::k_sem_take(sem, Timeout::from_ms(0)), 0);
+    check_eq!(
+        userspace::k_sem_take(sem, Timeout::from_ms(0)),
+        Kerr::TryAgain as i32
+    );
+
+    // The count is handed over to the pending helper thread
+    publish(sem);
+    userspace::k_sleep(Timeout::from_ms(20));
+    check!(!HELPER_DONE.load(Ordering::Acquire));
+
+    check_eq!(userspace::k_sem_give(sem), 0);
+    userspace::k_sleep(Timeout::from_ms(20));
+    check!(HELPER_DONE.load(Ordering::Acquire));
+    check_eq!(
+        userspace::k_sem_take(sem, Timeout::from_ms(0)),
+        Kerr::TryAgain as i32
+    );
+
+    Ok(())
+}
+
+fn helper_semaphore() {
+    let sem = harness::subscribe();
+
+    if userspace::k_sem_take(sem, Timeout::Forever) == 0 {
+        HELPER_DONE.store(true, Ordering::Release);
+    }
+}
+
+fn test_signal() -> TestResult {
+    const VALUE: u32 = 12345;
+
+    let signal = userspace::k_signal_create();
+    check!(signal >= 0);
+
+    check_eq!(
+        userspace::k_signal_poll(signal, Timeout::from_ms(0)),
+        Kerr::TryAgain as i32
+    );
+
+    publish(signal);
+    userspace::k_sleep(Timeout::from_ms(20));
+    check!(!HELPER_DONE.load(Ordering::Acquire));
+
+    check_eq!(userspace::k_signal(signal, VALUE), 0);
+    userspace::k_sleep(Timeout::from_ms(20));
+    check!(HELPER_DONE.load(Ordering::Acquire));
+    check_eq!(HELPER_VALUE.load(Ordering::Acquire), VALUE);
+
+    // The signal keeps its value
+    check_eq!(
+        userspace::k_signal_poll(signal, Timeout::from_ms(0)),
+        VALUE as i32
+    );
+
+    Ok(())
+}
+
+fn helper_signal() {
+    let signal = harness::subscribe();
+
+    let value = userspace::k_signal_poll(signal, Timeout::Forever);
+    HELPER_VALUE.store(value as u32, Ordering::Release);
+    HELPER_DONE.store(true, Ordering::Release);
+}
+
+extern "C" fn work_handler(arg: *mut c_void) {
+    HELPER_VALUE.store(arg as u32, Ordering::Release);
+    HELPER_DONE.store(true, Ordering::Release);
+}
+
+fn test_workqueue() -> TestResult {
+    const VALUE: u32 = 0x5a5a;
+
+    publish(0);
+
+    check_eq!(
+        userspace::k_work_submit(work_handler, VALUE as *mut c_void),
+        0
+    );
+    userspace::k_sleep(Timeout::from_ms(20));
+    check!(HELPER_DONE.load(Ordering::Acquire));
+    check_eq!(HELPER_VALUE.load(Ordering::Acquire), VALUE);
+
+    Ok(())
+}
+
+fn test_stats() -> TestResult {
+    let mut info = ThreadStatsInfo::default();
+
+    // Written by the kernel, the counters include this syscall
+    check_eq!(userspace::k_thread_stats(&mut info), 0);
+    check!(info.syscalls > 0);
+    check!(info.switches > 0);
+
+    Ok(())
+}
+
+fn test_sysinfo() -> TestResult {
+    // The snapshots are written by the kernel in the buffers
+    let mut version = VersionInfo::default();
+    check_eq!(userspace::k_sysinfo_version(&mut version), 0);
+    check!(version.version[0] != 0);
+    check!(version.features & FEATURE_WORKQUEUE != 0);
+
+    let mut memory = MemoryInfo::default();
+    check_eq!(userspace::k_sysinfo_memory(&mut memory), 0);
+    check!(memory.heap_size > 0);
+
+    let mut threads = [ThreadInfo::default(); 4];
+    let count = userspace::k_sysinfo_threads(&mut threads);
+    check_eq!(count, threads.len() as i32);
+    check!(threads.iter().all(|thread| thread.id != 0));
+
+    Ok(())
+}
+
+fn test_elf() -> TestResult {
+    check_eq!(userspace::k_load("missing"), Kerr::NoEntry as i32);
+
+    let tid = userspace::k_load("hello");
+    check!(tid > 0);
+
+    let mut threads = [ThreadInfo::default(); 32];
+    let count = userspace::k_sysinfo_threads(&mut threads);
+    check!(count > 0);
+    check!(threads[..count as usize]
+        .iter()
+        .any(|thread| thread.id == tid as u32));
+
+    check_eq!(userspace::k_kill(tid as u32), 0);
+
+    Ok(())
+}
+
+fn test_untrusted() -> TestResult {
+    publish(0);
+
+    // The system syscalls fail in the helper thread, which is not trusted
+    while !HELPER_DONE.load(Ordering::Acquire) {
+        userspace::k_sleep(Timeout::from_ms(1));
+    }
+    check_eq!(HELPER_VALUE.load(Ordering::Acquire), 3);
+
+    Ok(())
+}
+
+fn helper_untrusted() {
+    harness::subscribe();
+
+    let denied = [
+        userspace::k_load("hello"),
+        userspace::k_kill(0),
+        userspace::k_exit(1),
+    ]
+    .iter()
+    .filter(|&&ret| ret == Kerr::EPERM as i32)
+    .count();
+    HELPER_VALUE.store(denied as u32, Ordering::Release);
+    HELPER_DONE.store(true, Ordering::Release);
+}
+
+/// Values loaded in s0-s31 by the body (`0`) and the helper thread (`1`)
+#[cfg(feature = "fpu")]
+const FPU_PATTERNS: [[u32; 32]; 2] = {
+    let mut patterns = [[0; 32]; 2];
+    let mut i = 0;
+    while i < 32 {
+        // 1.0 and -2.0 with an increasing mantissa
+        patterns[0][i] = 0x3f80_0000 + i as u32;
+        patterns[1][i] = 0xc000_0000 + i as u32;
+        i += 1;
+    }
+    patterns
+};
+
+/// Sleep rounds of the body, the helper thread is preempted with its values
+/// in the FP registers between them
+#[cfg(feature = "fpu")]
+const FPU_ROUNDS: usize = 4;
+
+#[cfg(feature = "fpu")]
+static FPU_SPINS: AtomicU32 = AtomicU32::new(0);
+#[cfg(feature = "fpu")]
+static FPU_ERRORS: AtomicU32 = AtomicU32::new(0);
+#[cfg(feature = "fpu")]
+static FPU_STOP: AtomicBool = AtomicBool::new(false);
+
+#[cfg(feature = "fpu")]
+fn test_fpu() -> TestResult {
+    use kernel::kernel::syscalls::{KernelSyscallId, SyscallId};
+
+    let (r0, r1) = Timeout::from_ms(10).to_raw();
+
+    for _ in 0..FPU_ROUNDS {
+        let mut regs = [0u32; 32];
+        let ret: i32;
+
+        // The compiler could use the FP registers between two asm blocks,
+        // load them, sleep and read them back at once
+        unsafe {
+            core::arch::asm!(
+                "vldmia {pattern}, {{s0-s31}}",
+                "svc #{svc_num}",
+                "vstmia {regs}, {{s0-s31}}",
+                pattern = in(reg) FPU_PATTERNS[0].as_ptr(),
+                regs = in(reg) regs.as_mut_ptr(),
+                svc_num = const SyscallId::Kernel as u8,
+                inlateout("r0") r0 => ret,
+                in("r1") r1,
+                in("r2") 0,
+                in("r3") KernelSyscallId::Sleep as u32,
+                out("d0") _, out("d1") _, out("d2") _, out("d3") _,
+                out("d4") _, out("d5") _, out("d6") _, out("d7") _,
+                out("d8") _, out("d9") _, out("d10") _, out("d11") _,
+                out("d12") _, out("d13") _, out("d14") _, out("d15") _,
+                options(nostack),
+            );
+        }
+
+        check_eq!(ret, 0);
+        check!(regs == FPU_PATTERNS[0]);
+    }
+
+    // The helper thread used the FPU in between
+    check!(FPU_SPINS.load(Ordering::Acquire) > 0);
+    FPU_STOP.store(true, Ordering::Release);
+
+    userspace::k_sleep(Timeout::from_ms(20));
+    check_eq!(FPU_ERRORS.load(Ordering::Acquire), 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "fpu")]
+fn helper_fpu() {
+    while !FPU_STOP.load(Ordering::Acquire) {
+        let mut regs = [0u32; 32];
+
+        // Keeps its values in the FP registers for a while, the body preempts
+        // it in the meantime
+        unsafe {
+            core::arch::asm!(
+                "vldmia {pattern}, {{s0-s31}}",
+                "2:",
+                "vcmp.f32 s0, s31",
+                "subs {count}, {count}, #1",
+                "bne 2b",
+                "vstmia {regs}, {{s0-s31}}",
+                pattern = in(reg) FPU_PATTERNS[1].as_ptr(),
+                regs = in(reg) regs.as_mut_ptr(),
+                count = inout(reg) 1000u32 => _,
+                out("d0") _, out("d1") _, out("d2") _, out("d3") _,
+                out("d4") _, out("d5") _, out("d6") _, out("d7") _,
+                out("d8") _, out("d9") _, out("d10") _, out("d11") _,
+                out("d12") _, out("d13") _, out("d14") _, out("d15") _,
+                options(nostack),
+            );
+        }
+
+        if regs != FPU_PATTERNS[1] {
+            FPU_ERRORS.fetch_add(1, Ordering::AcqRel);
+        }
+        FPU_SPINS.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(feature = "fpu")]
+fn test_fpu_first_use() -> TestResult {
+    use kernel::kernel::syscalls::{KernelSyscallId, SyscallId};
+
+    publish(0);
+
+    // Leaves its values in the FP registers while the helper thread starts
+    // using the FPU
+    let (r0, r1) = Timeout::from_ms(1).to_raw();
+    while !HELPER_DONE.load(Ordering::Acquire) {
+        unsafe {
+            core::arch::asm!(
+                "vldmia {pattern}, {{s0-s31}}",
+                "svc #{svc_num}",
+                pattern = in(reg) FPU_PATTERNS[0].as_ptr(),
+                svc_num = const SyscallId::Kernel as u8,
+                inlateout("r0") r0 => _,
+                in("r1") r1,
+                in("r2") 0,
+                in("r3") KernelSyscallId::Sleep as u32,
+                out("d0") _, out("d1") _, out("d2") _, out("d3") _,
+                out("d4") _, out("d5") _, out("d6") _, out("d7") _,
+                out("d8") _, out("d9") _, out("d10") _, out("d11") _,
+                out("d12") _, out("d13") _, out("d14") _, out("d15") _,
+                options(nostack),
+            );
+        }
+    }
+
+    // The helper thread found its FP registers cleared
+    check_eq!(HELPER_VALUE.load(Ordering::Acquire), 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "fpu")]
+fn helper_fpu_first_use() {
+    harness::subscribe();
+
+    // First floating point instruction of the thread
+    let mut regs = [u32::MAX; 32];
+    unsafe {
+        core::arch::asm!(
+            "vstmia {regs}, {{s0-s31}}",
+            regs = in(reg) regs.as_mut_ptr(),
+            options(nostack),
+        );
+    }
+
+    let dirty = regs.iter().filter(|&&reg| reg != 0).count();
+    HELPER_VALUE.store(dirty as u32, Ordering::Release);
+    HELPER_DONE.store(true, Ordering::Release);
+}
+
+// Without FPU support, the threads must not use the FP registers: the test
+// cases are skipped
+#[cfg(not(feature = "fpu"))]
+fn test_fpu() -> TestResult {
+    Ok(())
+}
+
+#[cfg(not(feature = "fpu"))]
+fn helper_fpu() {}
+
+#[cfg(not(feature = "fpu"))]
+fn test_fpu_first_use() -> TestResult {
+    Ok(())
+}
+
+#[cfg(not(feature = "fpu"))]
+fn helper_fpu_first_use() {}